@@ -21,5 +21,11 @@ fn text_plain(b: &mut Bencher) {
     b.iter(|| tree_magic::from_u8(include_bytes!("text/plain")));
 }
 
-benchmark_group!(benches, image_gif, image_png, application_zip, text_plain);
+/// Binary data that matches no specific type
+fn application_octet_stream(b: &mut Bencher) {
+    let input: Vec<u8> = (0..2048u32).map(|x| (x * 7919 % 251) as u8).collect();
+    b.iter(|| tree_magic::from_u8(&input));
+}
+
+benchmark_group!(benches, image_gif, image_png, application_zip, text_plain, application_octet_stream);
 benchmark_main!(benches);
@@ -0,0 +1,64 @@
+#[macro_use]
+extern crate bencher;
+extern crate tree_magic;
+use bencher::Bencher;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts every allocation, so the benchmarks can check there are none
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Benchmarks match_u8, and fails if any call allocates
+fn bench_match(b: &mut Bencher, mimetype: &str, input: &[u8]) {
+    let mimetype: mime::Mime = mimetype.parse().unwrap();
+    tree_magic::match_u8(mimetype.clone(), input);
+
+    let mut calls = 0;
+    let mut allocations = 0;
+    b.iter(|| {
+        // Cloning a parsed MIME allocates, so leave it out of the count
+        let x = mimetype.clone();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let result = tree_magic::match_u8(x, input);
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        calls += 1;
+        result
+    });
+    assert_eq!(allocations, 0, "{} allocations in {} calls", allocations, calls);
+}
+
+///Image benchmarks
+fn image_gif(b: &mut Bencher) {
+    bench_match(b, "image/gif", include_bytes!("image/gif"));
+}
+fn image_png(b: &mut Bencher) {
+    bench_match(b, "image/png", include_bytes!("image/png"));
+}
+
+/// Archive tests
+fn application_zip(b: &mut Bencher) {
+    bench_match(b, "application/zip", include_bytes!("application/zip"));
+}
+
+/// Text tests
+fn text_plain(b: &mut Bencher) {
+    bench_match(b, "text/plain", include_bytes!("text/plain"));
+}
+
+benchmark_group!(benches, image_gif, image_png, application_zip, text_plain);
+benchmark_main!(benches);
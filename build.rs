@@ -0,0 +1,257 @@
+//! Compiles the bundled shared-mime-info database into static Rust tables
+//!
+//! `src/fdo_magic/builtin/{magic,aliases,subclasses}` are parsed here instead
+//! of at runtime, and written to `$OUT_DIR/builtin.rs` as nested `StaticRule`
+//! trees plus (alias, MIME) and (parent, child) string tables. Types and
+//! aliases are sorted so they can be looked up by binary search, and subclass
+//! links already point at real types. A malformed bundled file fails the build
+//! instead of silently dropping types.
+
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::path::Path;
+
+const BUILTIN_DIR: &str = "src/fdo_magic/builtin";
+
+struct Rule {
+	indent_level: u32,
+	start_off: u32,
+	val: Vec<u8>,
+	mask: Option<Vec<u8>>,
+	word_len: u32,
+	region_len: u32
+}
+
+struct Entry {
+	priority: u32,
+	mimetype: String,
+	rules: Vec<Rule>
+}
+
+fn check_mime(s: &str, file: &str) {
+	if s.parse::<mime::Mime>().is_err() {
+		panic!("{}: invalid MIME type \"{}\"", file, s);
+	}
+}
+
+/// Reads decimal digits starting at `*i`, or returns `default` if there are none
+fn read_number(b: &[u8], i: &mut usize, default: u32) -> u32 {
+	let start = *i;
+	while *i < b.len() && b[*i].is_ascii_digit() {
+		*i += 1;
+	}
+	match std::str::from_utf8(&b[start..*i]).unwrap().parse() {
+		Ok(x) => x,
+		Err(_) => default
+	}
+}
+
+fn expect(b: &[u8], i: &mut usize, c: u8) {
+	if b.get(*i) != Some(&c) {
+		panic!("magic: expected '{}' at byte {}", c as char, *i);
+	}
+	*i += 1;
+}
+
+/// Parses the binary `magic` file written by update-mime-database
+fn parse_magic(b: &[u8]) -> Vec<Entry> {
+	const HEADER: &[u8] = b"MIME-Magic\0\n";
+	if !b.starts_with(HEADER) {
+		panic!("magic: missing MIME-Magic header");
+	}
+
+	let mut out = Vec::<Entry>::new();
+	let mut i = HEADER.len();
+	while i < b.len() {
+		// Section header: [priority:mime/type]
+		if b[i] == b'[' {
+			let end = i + b[i..].windows(2).position(|x| x == b"]\n")
+				.unwrap_or_else(|| panic!("magic: unterminated section at byte {}", i));
+			let header = std::str::from_utf8(&b[i + 1..end]).expect("magic: section is not UTF-8");
+			let (priority, mimetype) = header.split_at(header.find(':')
+				.unwrap_or_else(|| panic!("magic: bad section \"{}\"", header)));
+			let mimetype = &mimetype[1..];
+			check_mime(mimetype, "magic");
+			out.push(Entry{
+				priority: priority.parse().unwrap_or_else(|_| panic!("magic: bad priority \"{}\"", priority)),
+				mimetype: mimetype.to_string(),
+				rules: Vec::new()
+			});
+			i = end + 2;
+			continue;
+		}
+
+		// Rule: [indent]>start-offset=<len><value>[&<mask>][~word-size][+range-length]
+		let entry = out.last_mut().unwrap_or_else(|| panic!("magic: rule before first section"));
+		let indent_level = read_number(b, &mut i, 0);
+		expect(b, &mut i, b'>');
+		let start_off = read_number(b, &mut i, 0);
+		expect(b, &mut i, b'=');
+
+		let len = match b.get(i..i + 2) {
+			Some(x) => u16::from_be_bytes([x[0], x[1]]) as usize,
+			None => panic!("magic: truncated rule at byte {}", i)
+		};
+		i += 2;
+		let val = b.get(i..i + len).unwrap_or_else(|| panic!("magic: truncated value at byte {}", i)).to_vec();
+		i += len;
+
+		let mut mask = None;
+		if b.get(i) == Some(&b'&') {
+			i += 1;
+			mask = Some(b.get(i..i + len).unwrap_or_else(|| panic!("magic: truncated mask at byte {}", i)).to_vec());
+			i += len;
+		}
+		let mut word_len = 1;
+		if b.get(i) == Some(&b'~') {
+			i += 1;
+			word_len = read_number(b, &mut i, 1);
+		}
+		let mut region_len = 0;
+		if b.get(i) == Some(&b'+') {
+			i += 1;
+			region_len = read_number(b, &mut i, 0);
+		}
+		expect(b, &mut i, b'\n');
+
+		if val.is_empty() {
+			panic!("magic: empty value in {}", entry.mimetype);
+		}
+		if word_len != 1 && word_len != 2 && word_len != 4 {
+			panic!("magic: bad word size {} in {}", word_len, entry.mimetype);
+		}
+		if !val.chunks_exact(word_len as usize).remainder().is_empty() {
+			panic!("magic: value is not a whole number of words in {}", entry.mimetype);
+		}
+
+		entry.rules.push(Rule{indent_level, start_off, val, mask, word_len, region_len});
+	}
+
+	out
+}
+
+/// Parses a file of whitespace-separated pairs, one per line
+fn parse_pairs(text: &str, file: &str) -> Vec<(String, String)> {
+	let mut out = Vec::<(String, String)>::new();
+	for line in text.lines().filter(|x| !x.trim().is_empty()) {
+		let mut words = line.split_whitespace();
+		let (a, b) = match (words.next(), words.next()) {
+			(Some(a), Some(b)) => (a, b),
+			_ => panic!("{}: bad line \"{}\"", file, line)
+		};
+		check_mime(a, file);
+		check_mime(b, file);
+		out.push((a.to_string(), b.to_string()));
+	}
+	out
+}
+
+/// Puts word-sized values into target byte order
+fn swap_words(b: &mut [u8], word_len: u32, little_endian: bool) {
+	if !little_endian || word_len < 2 {
+		return;
+	}
+	for word in b.chunks_exact_mut(word_len as usize) {
+		word.reverse();
+	}
+}
+
+/// Writes the rules starting at `rules[*i]` that are deeper than `parent_level`
+/// as a `&[StaticRule]` literal. Like `fdo_magic::ruleset::gen_graph`, each rule
+/// becomes a child of the closest rule before it with a lower indent level.
+fn write_rules(out: &mut String, rules: &[Rule], i: &mut usize, parent_level: i64, little_endian: bool) {
+	out.push_str("&[");
+	while *i < rules.len() && rules[*i].indent_level as i64 > parent_level {
+		let rule = &rules[*i];
+		let mut val = rule.val.clone();
+		swap_words(&mut val, rule.word_len, little_endian);
+		let mask = rule.mask.clone().map(|mut x| {
+			swap_words(&mut x, rule.word_len, little_endian);
+			x
+		});
+
+		write!(out, "StaticRule{{start_off: {}, val: &{:?}, mask: ", rule.start_off, val).unwrap();
+		match mask {
+			Some(x) => write!(out, "Some(&{:?})", x).unwrap(),
+			None => out.push_str("None")
+		}
+		write!(out, ", region_len: {}, children: ", rule.region_len).unwrap();
+
+		*i += 1;
+		write_rules(out, rules, i, rule.indent_level as i64, little_endian);
+		out.push_str("},");
+	}
+	out.push(']');
+}
+
+fn main() {
+	let dir = Path::new(BUILTIN_DIR);
+	for file in &["magic", "aliases", "subclasses"] {
+		println!("cargo:rerun-if-changed={}/{}", BUILTIN_DIR, file);
+	}
+	println!("cargo:rerun-if-changed=build.rs");
+
+	let little_endian = env::var("CARGO_CFG_TARGET_ENDIAN").map(|x| x == "little").unwrap_or(false);
+
+	let mut magic = parse_magic(&fs::read(dir.join("magic")).expect("cannot read magic"));
+	magic.sort_by(|a, b| a.mimetype.cmp(&b.mimetype));
+	// One entry per type, with the highest priority of its sections
+	magic.dedup_by(|next, entry| {
+		if next.mimetype != entry.mimetype {
+			return false;
+		}
+		entry.priority = std::cmp::max(entry.priority, next.priority);
+		entry.rules.append(&mut next.rules);
+		true
+	});
+
+	let mut aliases = parse_pairs(&fs::read_to_string(dir.join("aliases")).expect("cannot read aliases"), "aliases");
+	aliases.sort();
+	aliases.dedup();
+	for x in aliases.windows(2) {
+		if x[0].0 == x[1].0 {
+			panic!("aliases: \"{}\" is an alias of more than one type", x[0].0);
+		}
+	}
+	let resolve = |x: String| match aliases.binary_search_by(|a| a.0.cmp(&x)) {
+		Ok(i) => aliases[i].1.clone(),
+		Err(_) => x
+	};
+
+	// If child or parent refers to an alias, change it to the real type
+	let subclasses: Vec<(String, String)> = parse_pairs(
+		&fs::read_to_string(dir.join("subclasses")).expect("cannot read subclasses"),
+		"subclasses"
+	).into_iter().map(|(a, b)| (resolve(a), resolve(b))).collect();
+
+	let mut out = String::new();
+	out.push_str("// Generated by build.rs from the bundled magic, aliases and subclasses files\n\n");
+
+	out.push_str("/// Magic rules of every bundled type, sorted by MIME\n");
+	out.push_str("pub static MAGIC: &[StaticEntry] = &[\n");
+	for entry in magic.iter().filter(|x| !x.rules.is_empty()) {
+		write!(out, "StaticEntry{{priority: {}, mimetype: {:?}, rules: ", entry.priority, entry.mimetype).unwrap();
+		write_rules(&mut out, &entry.rules, &mut 0, -1, little_endian);
+		out.push_str("},\n");
+	}
+	out.push_str("];\n\n");
+
+	out.push_str("/// (alias, MIME) pairs, sorted by alias\n");
+	out.push_str("pub static ALIASLIST: &[(&str, &str)] = &[\n");
+	for (alias, mimetype) in aliases.iter() {
+		writeln!(out, "({:?}, {:?}),", alias, mimetype).unwrap();
+	}
+	out.push_str("];\n\n");
+
+	// The subclasses file lists child first
+	out.push_str("/// (parent, child) pairs\n");
+	out.push_str("pub static SUBCLASSLIST: &[(&str, &str)] = &[\n");
+	for (child, parent) in subclasses.iter() {
+		writeln!(out, "({:?}, {:?}),", parent, child).unwrap();
+	}
+	out.push_str("];\n");
+
+	let dest = Path::new(&env::var("OUT_DIR").expect("OUT_DIR not set")).join("builtin.rs");
+	fs::write(dest, out).expect("cannot write builtin.rs");
+}
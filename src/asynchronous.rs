@@ -0,0 +1,97 @@
+//! Detect types from tokio readers and files without blocking the executor
+//!
+//! Only available with the `async` feature, which depends on tokio's I/O
+//! traits but not on its runtime.
+//!
+//! A reader can't seek, so `from_async_reader` only reads the first
+//! `probe::HEADER_LEN` bytes, and rules that look past them don't match.
+//! A ZIP file is then only `application/zip`, as its central directory is
+//! at the end.
+//!
+//! `from_path_async` gets the same results as `from_filepath`. The checkers
+//! run on the parts of the file read so far, which never blocks, and the
+//! parts they asked for but didn't get are read with seeks before they run
+//! again. Most files take one run on the header.
+//!
+//! # Examples
+//! ```rust
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let input: &[u8] = include_bytes!("../tests/image/gif");
+//! let result = tree_magic::asynchronous::from_async_reader(input).await.unwrap();
+//! assert_eq!(result, "image/gif");
+//! # });
+//! ```
+
+use std::io;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use crate::probe::{self, Prefetch, Probe};
+use crate::MIME;
+
+/// Most times the checkers run on one file, as reads can lead to more reads
+const PASSES: usize = 8;
+
+/// Gets the type of the data from an async reader.
+///
+/// Reads at most `probe::HEADER_LEN` bytes, so the reader doesn't have to end.
+/// Rules that look past them don't match, unlike with `from_path_async`.
+/// Returns an error if reading fails.
+pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> io::Result<MIME> {
+	let mut b = Vec::<u8>::with_capacity(probe::HEADER_LEN);
+	reader.take(probe::HEADER_LEN as u64).read_to_end(&mut b).await?;
+	let probe = Probe::from_header(b);
+	crate::from_probe(&probe).ok_or_else(|| io::Error::other("Unknown file type"))
+}
+
+/// Gets the type of a file from a filepath, without blocking.
+///
+/// Does not look at file name or extension, just the contents. Reads the
+/// same parts of the file `from_filepath` would.
+/// Returns an error if the file can't be opened or read, or if it is
+/// a special file other than a directory.
+///
+/// # Examples
+/// ```rust
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let result = tree_magic::asynchronous::from_path_async("tests/image/png").await.unwrap();
+/// assert_eq!(result, "image/png");
+/// # });
+/// ```
+pub async fn from_path_async<P: AsRef<Path>>(filepath: P) -> io::Result<MIME> {
+	let meta = tokio::fs::metadata(filepath.as_ref()).await?;
+
+	// Without contents, only directories have a type of their own
+	if meta.is_dir() {
+		return "inode/directory".parse().map_err(io::Error::other);
+	}
+	if !meta.is_file() {
+		return Err(io::Error::other("Unknown file type"));
+	}
+
+	let mut file = tokio::fs::File::open(filepath.as_ref()).await?;
+	let mut prefetch = Prefetch::new(meta.len() as usize);
+	prefetch.insert(0, read_at(&mut file, 0, probe::HEADER_LEN).await?);
+
+	let mut passes = 1;
+	loop {
+		// The probe borrows the reads, so it mustn't live across an await
+		let result = crate::from_probe(&Probe::from_prefetch(&prefetch));
+		let misses = prefetch.take_misses();
+		if misses.is_empty() || passes == PASSES {
+			return result.ok_or_else(|| io::Error::other("Unknown file type"));
+		}
+		for (offset, len) in misses {
+			let b = read_at(&mut file, offset, len).await?;
+			prefetch.insert(offset, b);
+		}
+		passes += 1;
+	}
+}
+
+/// Reads up to `len` bytes of a file, starting at `offset`
+async fn read_at(file: &mut tokio::fs::File, offset: usize, len: usize) -> io::Result<Vec<u8>> {
+	file.seek(io::SeekFrom::Start(offset as u64)).await?;
+	let mut b = Vec::<u8>::new();
+	(&mut *file).take(len as u64).read_to_end(&mut b).await?;
+	Ok(b)
+}
@@ -1,5 +1,5 @@
-use std::path::Path;
-use crate::{read_bytes, MIME};
+use crate::MIME;
+use crate::probe::Probe;
 
 /// If there are any null bytes, return False. Otherwise return True.
 fn is_text_plain_from_u8(b: &[u8]) -> bool
@@ -7,14 +7,14 @@ fn is_text_plain_from_u8(b: &[u8]) -> bool
 	b.iter().filter(|&x| *x == 0).count() == 0
 }
 
-// TODO: Hoist the main logic here somewhere else. This'll get redundant fast!
-fn is_text_plain_from_filepath(filepath: &Path) -> bool
+/// Checks the probe's text view, so UTF-16 with a byte order mark counts
+/// as text too. A header that doesn't decode is checked for null bytes.
+fn is_text_plain(probe: &Probe) -> bool
 {
-	let b = match read_bytes(filepath, 512) {
-		Ok(x) => x,
-		Err(_) => return false
-	};
-	is_text_plain_from_u8(b.as_slice())
+	match probe.text() {
+		Some(x) => !x.contains('\0'),
+		None => probe.header().is_some_and(is_text_plain_from_u8)
+	}
 }
 
 #[allow(unused_variables)]
@@ -33,22 +33,28 @@ pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
 	}
 }
 
-pub fn from_filepath(filepath: &Path, mimetype: MIME) -> bool
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
 {
-	use std::fs;
+	// A bytestream has no metadata to check
+	if probe.path().is_none() {
+		if mimetype == "text/plain" {
+			return is_text_plain(probe);
+		}
+		return probe.header().is_some_and(|b| from_u8(b, mimetype));
+	}
 
 	// Being bad with error handling here,
 	// but if you can't open it it's probably not a file.
-	let meta = match fs::metadata(filepath) {
-		Ok(x) => x,
-		Err(_) => {return false;}
+	let meta = match probe.metadata() {
+		Some(x) => x,
+		None => {return false;}
 	};
 
 	match mimetype.to_string().as_str() {
 		"all/all" => return true,
 		"all/allfiles" | "application/octet-stream" => return meta.is_file(),
 		"inode/directory" => return meta.is_dir(),
-		"text/plain" => return is_text_plain_from_filepath(filepath),
+		"text/plain" => return is_text_plain(probe),
 		_ => return false
 	}
 }
\ No newline at end of file
@@ -0,0 +1,29 @@
+use crate::MIME;
+use crate::probe::Probe;
+use super::COMPOUNDS;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	from_probe(&Probe::from_u8(b), mimetype)
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	let format = match super::format_of(probe) {
+		Some(x) => x,
+		None => return false
+	};
+	if mimetype == format.mimetype {
+		return true;
+	}
+
+	// The contents of a compound type are checked like any other input
+	let inner = match COMPOUNDS.iter().find(|x| mimetype == x.2 && x.0 == format.mimetype) {
+		Some(x) => x.1,
+		None => return false
+	};
+	match (inner.parse(), probe.decompressed()) {
+		(Ok(inner), Some(b)) => crate::match_u8(inner, b),
+		_ => false
+	}
+}
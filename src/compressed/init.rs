@@ -0,0 +1,31 @@
+use fnv::FnvHashMap;
+use crate::MIME;
+use super::{COMPOUNDS, FORMATS};
+
+/// Compound types of the enabled formats, as (container, compound type)
+fn enabled_compounds() -> impl Iterator<Item = (&'static str, &'static str)> {
+	COMPOUNDS.iter()
+		.filter(|x| FORMATS.iter().any(|y| x.0 == y.mimetype))
+		.map(|x| (x.0, x.2))
+}
+
+/// Get list of supported MIME types
+pub fn get_supported() -> Vec<MIME> {
+	FORMATS.iter()
+		.filter(|x| x.add_type)
+		.map(|x| x.mimetype)
+		.chain(enabled_compounds().map(|x| x.1))
+		.filter_map(|x| x.parse().ok())
+		.collect()
+}
+
+/// Get list of parent -> child subclass links
+pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+	enabled_compounds()
+		.filter_map(|(a, b)| Some( (a.parse().ok()?, b.parse().ok()?) ))
+		.collect()
+}
+
+pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
+	FnvHashMap::default()
+}
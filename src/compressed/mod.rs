@@ -0,0 +1,214 @@
+//! Look inside gzip, bzip2, xz, zstd and LZ4 files
+//!
+//! A compression format only says how a file was packed, not what it is.
+//! With the `gzip`, `bzip2`, `xz`, `zstd` or `lz4` features, the start of a
+//! compressed input is decompressed and run back through the type graph.
+//! `from_probe` reports the container, the type of its contents, and the
+//! compound type shared-mime-info defines for the pair, if there is one
+//! (a gzipped tar is an `application/x-compressed-tar`).
+//!
+//! The compound types also join the type graph under their containers, so
+//! `tree_magic::from_u8` finds `application/x-compressed-tar` where it used
+//! to stop at `application/gzip`.
+//!
+//! Only the first `PAYLOAD_LEN` bytes of the contents are decompressed, from
+//! at most `INPUT_LEN` bytes of input. gzip streams can be decompressed from
+//! any prefix, but bzip2, xz, zstd and LZ4 have no output until their first
+//! block is complete. If the input ends first, the contents aren't known.
+//!
+//! # Examples
+//! ```rust
+//! # #[cfg(feature = "gzip")] {
+//! use std::path::Path;
+//!
+//! let path = Path::new("tests/application/x-compressed-tar");
+//! let result = tree_magic::compressed::from_filepath(path).unwrap();
+//! assert_eq!(result.container, "application/gzip");
+//! assert_eq!(result.inner.unwrap(), "application/x-tar");
+//! assert_eq!(result.compound.unwrap(), "application/x-compressed-tar");
+//!
+//! assert_eq!(tree_magic::from_filepath(path).unwrap(), "application/x-compressed-tar");
+//! # }
+//! ```
+
+use std::io::{self, Read};
+use std::path::Path;
+use crate::probe::{Probe, HEADER_LEN};
+use crate::MIME;
+
+pub mod check;
+pub mod init;
+
+/// Bytes of the contents that are decompressed and classified
+pub const PAYLOAD_LEN: usize = HEADER_LEN;
+
+/// Most bytes of compressed input read to decompress `PAYLOAD_LEN` bytes
+///
+/// Large enough to hold the first block of any of the formats, the biggest
+/// being a 4 MiB LZ4 block, with its frame header.
+pub const INPUT_LEN: usize = (4 << 20) + 64;
+
+/// Wraps compressed input in a reader of the decompressed contents
+type Decoder = for<'r> fn(Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>>;
+
+/// A compression format with an enabled decoder
+struct Format {
+	mimetype: &'static str,
+	magic: &'static [u8],
+	decoder: Decoder,
+	/// Set if the bundled database has no magic for this format,
+	/// so this checker adds it to the graph
+	add_type: bool
+}
+
+const FORMATS: &[Format] = &[
+	#[cfg(feature = "gzip")]
+	Format{mimetype: "application/gzip", magic: b"\x1f\x8b", decoder: gzip, add_type: false},
+	#[cfg(feature = "bzip2")]
+	Format{mimetype: "application/x-bzip", magic: b"BZh", decoder: bzip2, add_type: false},
+	#[cfg(feature = "xz")]
+	Format{mimetype: "application/x-xz", magic: b"\xfd7zXZ\x00", decoder: xz, add_type: false},
+	#[cfg(feature = "zstd")]
+	Format{mimetype: "application/zstd", magic: b"\x28\xb5\x2f\xfd", decoder: zstd, add_type: true},
+	#[cfg(feature = "lz4")]
+	Format{mimetype: "application/x-lz4", magic: b"\x04\x22\x4d\x18", decoder: lz4, add_type: false}
+];
+
+/// Compound types from shared-mime-info, as (container, contents, compound type)
+///
+/// More specific contents come first, so an EPS file isn't reported as PostScript.
+const COMPOUNDS: &[(&str, &str, &str)] = &[
+	("application/gzip",   "application/x-tar",            "application/x-compressed-tar"),
+	("application/gzip",   "application/x-cpio",           "application/x-cpio-compressed"),
+	("application/gzip",   "image/svg+xml",                "image/svg+xml-compressed"),
+	("application/gzip",   "application/pdf",              "application/x-gzpdf"),
+	("application/gzip",   "image/x-eps",                  "image/x-gzeps"),
+	("application/gzip",   "application/postscript",       "application/x-gzpostscript"),
+	("application/gzip",   "application/x-dvi",            "application/x-gzdvi"),
+	("application/gzip",   "application/x-font-linux-psf", "application/x-gz-font-linux-psf"),
+	("application/x-bzip", "application/x-tar",            "application/x-bzip-compressed-tar"),
+	("application/x-bzip", "application/pdf",              "application/x-bzpdf"),
+	("application/x-bzip", "image/x-eps",                  "image/x-bzeps"),
+	("application/x-bzip", "application/postscript",       "application/x-bzpostscript"),
+	("application/x-bzip", "application/x-dvi",            "application/x-bzdvi"),
+	("application/x-xz",   "application/x-tar",            "application/x-xz-compressed-tar"),
+	("application/x-xz",   "application/pdf",              "application/x-xzpdf"),
+	("application/zstd",   "application/x-tar",            "application/x-zstd-compressed-tar"),
+	("application/x-lz4",  "application/x-tar",            "application/x-lz4-compressed-tar")
+];
+
+#[cfg(feature = "gzip")]
+fn gzip<'r>(input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+	Ok(Box::new(flate2::read::MultiGzDecoder::new(input)))
+}
+
+#[cfg(feature = "bzip2")]
+fn bzip2<'r>(input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+	Ok(Box::new(bzip2::read::MultiBzDecoder::new(input)))
+}
+
+#[cfg(feature = "xz")]
+fn xz<'r>(input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+	Ok(Box::new(lzma_rust2::XzReader::new(input, true)))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd<'r>(input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+	let decoder = ruzstd::decoding::StreamingDecoder::new(input).map_err(io::Error::other)?;
+	Ok(Box::new(decoder))
+}
+
+#[cfg(feature = "lz4")]
+fn lz4<'r>(input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+	Ok(Box::new(lz4_flex::frame::FrameDecoder::new(input)))
+}
+
+/// What a compressed file holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compressed {
+	/// Compression format, such as `application/gzip`
+	pub container: MIME,
+	/// Type of the decompressed contents, or None if nothing could be decompressed
+	pub inner: Option<MIME>,
+	/// Type shared-mime-info gives this container and contents,
+	/// such as `application/x-compressed-tar`
+	pub compound: Option<MIME>
+}
+
+/// Reads a probe's input from the start, stopping after `INPUT_LEN` bytes
+///
+/// Files are read in growing chunks, so short streams don't read the whole limit.
+struct ProbeReader<'p, 'a> {
+	probe: &'p Probe<'a>,
+	pos: usize
+}
+
+impl Read for ProbeReader<'_, '_> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let end = std::cmp::min(self.pos.saturating_add(buf.len()), INPUT_LEN);
+		let b = self.probe.bytes(end)
+			.ok_or_else(|| io::Error::other("input can't be read"))?;
+		let n = b.len().saturating_sub(self.pos);
+		buf[..n].copy_from_slice(&b[self.pos..self.pos + n]);
+		self.pos += n;
+		Ok(n)
+	}
+}
+
+/// Finds the compression format of a probed input
+fn format_of(probe: &Probe) -> Option<&'static Format> {
+	let header = probe.bytes(8)?;
+	FORMATS.iter().find(|x| header.starts_with(x.magic))
+}
+
+/// Decompresses up to `PAYLOAD_LEN` bytes of a probed input
+///
+/// Use `Probe::decompressed` instead, which keeps the result.
+pub(crate) fn decompress(probe: &Probe) -> Option<Vec<u8>> {
+	let format = format_of(probe)?;
+	let decoder = (format.decoder)(Box::new(ProbeReader{probe, pos: 0})).ok()?;
+
+	// A damaged or cut off stream still says something about its contents
+	let mut b = Vec::<u8>::with_capacity(PAYLOAD_LEN);
+	let _ = decoder.take(PAYLOAD_LEN as u64).read_to_end(&mut b);
+	match b.is_empty() {
+		true => None,
+		false => Some(b)
+	}
+}
+
+/// Finds the compound type for a container and its decompressed contents
+fn compound_of(container: &str, payload: &[u8]) -> Option<MIME> {
+	COMPOUNDS.iter()
+		.filter(|x| x.0 == container)
+		.find(|x| x.1.parse().is_ok_and(|inner| crate::match_u8(inner, payload)))
+		.and_then(|x| x.2.parse().ok())
+}
+
+/// Looks inside a probed file or bytestream
+///
+/// Returns None if the input isn't compressed in one of the enabled formats.
+pub fn from_probe(probe: &Probe) -> Option<Compressed> {
+	let format = format_of(probe)?;
+	let payload = probe.decompressed();
+	Some(Compressed{
+		container: format.mimetype.parse().ok()?,
+		inner: payload.map(crate::from_u8),
+		compound: payload.and_then(|b| compound_of(format.mimetype, b))
+	})
+}
+
+/// Looks inside a compressed bytestream
+///
+/// Returns None if the bytestream isn't compressed in one of the enabled formats.
+pub fn from_u8(bytes: &[u8]) -> Option<Compressed> {
+	from_probe(&Probe::from_u8(bytes))
+}
+
+/// Looks inside a compressed file
+///
+/// Returns None if the file can't be read, or isn't compressed in one of the
+/// enabled formats.
+pub fn from_filepath(filepath: &Path) -> Option<Compressed> {
+	from_probe(&Probe::from_filepath(filepath))
+}
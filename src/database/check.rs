@@ -0,0 +1,20 @@
+use crate::{typetable, MIME};
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	match super::detect(b) {
+		Some(x) => typetable::is_ancestor_or_self(&mimetype, x),
+		None => false
+	}
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	// The schema fills the first page of an SQLite database
+	let len = match probe.header() {
+		Some(b) => super::needed_len(b),
+		None => return false
+	};
+	probe.bytes(len).is_some_and(|b| from_u8(b, mimetype))
+}
@@ -0,0 +1,8 @@
+use fnv::FnvHashMap;
+use crate::MIME;
+
+pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
+	let mut out = FnvHashMap::default();
+	out.insert("application/x-sqlite3".parse().unwrap(), "application/vnd.sqlite3".parse().unwrap());
+	out
+}
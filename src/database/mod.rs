@@ -0,0 +1,229 @@
+//! Handles embedded database files: SQLite, Berkeley DB, LMDB and DuckDB
+//!
+//! An SQLite database must have a valid header, not just its magic string:
+//! a page size that is a power of two, known read and write versions, and
+//! the payload fractions every version of SQLite writes.
+//!
+//! Many applications keep their data in SQLite, and those formats are
+//! subclasses of `application/vnd.sqlite3`. They are recognized by the
+//! `application_id` in the header, or by the tables of the schema, which is
+//! stored on the first page. A schema that doesn't fit on the first page, or
+//! past the end of the input, isn't read.
+
+pub mod check;
+pub mod init;
+
+use std::convert::TryInto;
+use crate::probe::HEADER_LEN;
+use crate::typetable::TypeTable;
+
+/// Types handled here, with their parents
+///
+/// `application/x-sqlite3` is an old name of `application/vnd.sqlite3`
+/// that the magic rules still use. It's handled here so it never matches,
+/// and files get the real name.
+pub const TYPES: &TypeTable = &[
+	("application/vnd.sqlite3",                 &[]),
+	("application/x-sqlite3",                   &[]),
+	("application/geopackage+sqlite3",          &["application/vnd.sqlite3"]),
+	("application/x-mozilla-places+sqlite3",    &["application/vnd.sqlite3"]),
+	("application/x-kexiproject-sqlite3",       &["application/vnd.sqlite3"]),
+	("application/x-berkeley-db",               &[]),
+	("application/x-lmdb",                      &[]),
+	("application/x-duckdb",                    &[])
+];
+
+/// Length of the header of an SQLite database
+const SQLITE_HEADER_LEN: usize = 100;
+
+/// Formats by `application_id`
+const APPLICATION_IDS: [(&[u8; 4], &str); 3] =
+[
+	(b"GPKG", "application/geopackage+sqlite3"),
+	// GeoPackage 1.0 and 1.1
+	(b"GP10", "application/geopackage+sqlite3"),
+	(b"GP11", "application/geopackage+sqlite3")
+];
+
+/// Formats by tables they all have, for formats without an `application_id`
+const SCHEMAS: [(&[&str], &str); 3] =
+[
+	(&["gpkg_contents", "gpkg_spatial_ref_sys"], "application/geopackage+sqlite3"),
+	(&["moz_places", "moz_bookmarks"],           "application/x-mozilla-places+sqlite3"),
+	(&["kexi__db", "kexi__objects"],             "application/x-kexiproject-sqlite3")
+];
+
+/// Gets the page size of an SQLite database, if its header is valid
+fn sqlite_page_size(b: &[u8]) -> Option<usize> {
+	let header = b.get(..SQLITE_HEADER_LEN).filter(|x| x.starts_with(b"SQLite format 3\0"))?;
+	let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+		1 => 65536,
+		x if x >= 512 && x.is_power_of_two() => x as usize,
+		_ => return None
+	};
+	// Write and read versions: 1 for a rollback journal, 2 for WAL
+	if !matches!(header[18], 1 | 2) || !matches!(header[19], 1 | 2) {
+		return None;
+	}
+	// Payload fractions, which can't be changed
+	if header[21..24] != [64, 32, 32] {
+		return None;
+	}
+	Some(page_size)
+}
+
+/// Reads a variable-length integer of SQLite, as (value, length)
+fn read_varint(b: &[u8]) -> Option<(u64, usize)> {
+	let mut value = 0u64;
+	for (i, &x) in b.iter().take(9).enumerate() {
+		if i == 8 {
+			return Some( ((value << 8) | u64::from(x), 9) );
+		}
+		value = (value << 7) | u64::from(x & 0x7f);
+		if x & 0x80 == 0 {
+			return Some( (value, i + 1) );
+		}
+	}
+	None
+}
+
+/// Gets the length of a column value from its serial type
+fn serial_type_len(serial_type: u64) -> usize {
+	match serial_type {
+		1..=4 => serial_type as usize,
+		5 => 6,
+		6 | 7 => 8,
+		x if x >= 12 => ((x - 12) / 2) as usize,
+		_ => 0
+	}
+}
+
+/// Reads the names of the tables in the schema on the first page
+///
+/// The first page starts after the database header. Only a schema that fits
+/// on this page, as a single leaf page of the `sqlite_schema` table, is read.
+fn table_names(page: &[u8]) -> Vec<&[u8]> {
+	let mut out = Vec::new();
+	let header = match page.get(SQLITE_HEADER_LEN..SQLITE_HEADER_LEN + 8) {
+		// A leaf page of a table
+		Some(x) if x[0] == 0x0d => x,
+		_ => return out
+	};
+	let cells = u16::from_be_bytes([header[3], header[4]]) as usize;
+	let pointers = SQLITE_HEADER_LEN + 8;
+
+	for i in 0..cells {
+		let pointer = match page.get(pointers + 2 * i..pointers + 2 * i + 2) {
+			Some(x) => u16::from_be_bytes([x[0], x[1]]) as usize,
+			None => break
+		};
+		if let Some((kind, name)) = schema_row(page.get(pointer..).unwrap_or_default()) {
+			if kind == b"table" {
+				out.push(name);
+			}
+		}
+	}
+	out
+}
+
+/// Reads the type and name columns of a row of the schema
+fn schema_row(cell: &[u8]) -> Option<(&[u8], &[u8])> {
+	// Payload length and row ID
+	let (_, len1) = read_varint(cell)?;
+	let (_, len2) = read_varint(cell.get(len1..)?)?;
+	let record = cell.get(len1 + len2..)?;
+
+	let (header_len, mut pos) = read_varint(record)?;
+	let header_len = header_len as usize;
+	let mut columns = Vec::new();
+	while pos < header_len && columns.len() < 2 {
+		let (serial_type, len) = read_varint(record.get(pos..header_len)?)?;
+		columns.push(serial_type);
+		pos += len;
+	}
+	let [kind, name]: [u64; 2] = columns.try_into().ok()?;
+	let kind_len = serial_type_len(kind);
+	let name_len = serial_type_len(name);
+	let kind = record.get(header_len..header_len + kind_len)?;
+	let name = record.get(header_len + kind_len..header_len + kind_len + name_len)?;
+	Some( (kind, name) )
+}
+
+fn sqlite(b: &[u8]) -> Option<&'static str> {
+	let page_size = sqlite_page_size(b)?;
+	let application_id = &b[68..72];
+	if let Some(x) = APPLICATION_IDS.iter().find(|x| x.0 == application_id) {
+		return Some(x.1);
+	}
+
+	let tables = table_names(&b[..std::cmp::min(page_size, b.len())]);
+	let format = SCHEMAS.iter()
+		.find(|x| x.0.iter().all(|&table| tables.contains(&table.as_bytes())))
+		.map(|x| x.1);
+	Some(format.unwrap_or("application/vnd.sqlite3"))
+}
+
+/// Magic numbers of Berkeley DB access methods: B-tree, hash, queue and heap
+const BERKELEY_DB_MAGIC: [u32; 4] = [0x00053162, 0x00061561, 0x00042253, 0x00074582];
+
+/// Berkeley DB files store the magic number at offset 12 in the byte order
+/// of the machine that wrote them, followed by the version.
+fn berkeley_db(b: &[u8]) -> Option<&'static str> {
+	let magic: [u8; 4] = b.get(12..16)?.try_into().ok()?;
+	let version: [u8; 4] = b.get(16..20)?.try_into().ok()?;
+	let big_endian = match magic {
+		_ if BERKELEY_DB_MAGIC.contains(&u32::from_le_bytes(magic)) => false,
+		_ if BERKELEY_DB_MAGIC.contains(&u32::from_be_bytes(magic)) => true,
+		_ => return None
+	};
+	let version = match big_endian {
+		true => u32::from_be_bytes(version),
+		false => u32::from_le_bytes(version)
+	};
+	match (1..=20).contains(&version) {
+		true => Some("application/x-berkeley-db"),
+		false => None
+	}
+}
+
+/// LMDB files start with a meta page: a page header, 16 bytes long on 64-bit
+/// machines and 12 on 32-bit ones, then the magic number and format version,
+/// in the byte order of the machine.
+fn lmdb(b: &[u8]) -> Option<&'static str> {
+	let valid = [16, 12].iter().any(|&offset| {
+		let (magic, version) = match (b.get(offset..offset + 4), b.get(offset + 4..offset + 8)) {
+			(Some(x), Some(y)) => (x, y),
+			_ => return false
+		};
+		(magic == 0xbeefc0deu32.to_le_bytes() && version == 1u32.to_le_bytes()) ||
+			(magic == 0xbeefc0deu32.to_be_bytes() && version == 1u32.to_be_bytes())
+	});
+	match valid {
+		true => Some("application/x-lmdb"),
+		false => None
+	}
+}
+
+/// DuckDB files start with a checksum, then `DUCK` and the storage version.
+fn duckdb(b: &[u8]) -> Option<&'static str> {
+	let version = u64::from_le_bytes(b.get(12..20)?.try_into().ok()?);
+	match &b[8..12] == b"DUCK" && version > 0 && version < 0x10000 {
+		true => Some("application/x-duckdb"),
+		false => None
+	}
+}
+
+/// Gets how many bytes from the start of a file `detect` needs
+fn needed_len(b: &[u8]) -> usize {
+	std::cmp::max(sqlite_page_size(b).unwrap_or(0), HEADER_LEN)
+}
+
+/// Works out the type of a database from its start
+///
+/// Returns None if it isn't one.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	sqlite(b)
+		.or_else(|| duckdb(b))
+		.or_else(|| lmdb(b))
+		.or_else(|| berkeley_db(b))
+}
@@ -0,0 +1,15 @@
+use crate::{typetable, MIME};
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	match super::detect(b) {
+		Some(x) => typetable::is_ancestor_or_self(&mimetype, x),
+		None => false
+	}
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	probe.bytes(super::WINDOW_LEN).is_some_and(|b| from_u8(b, mimetype))
+}
@@ -0,0 +1,185 @@
+//! Handles EBML files: Matroska and WebM
+//!
+//! Both start with an EBML header element whose `DocType` child names the
+//! format. Elements are an ID and a length, each a variable-length integer,
+//! so the header has no fixed layout for magic rules to rely on.
+//!
+//! Whether a file is audio or video is up to its tracks. If the `Tracks`
+//! element of the first segment is in the input (it usually comes before the
+//! first cluster), a file with only audio tracks is `audio/webm` or
+//! `audio/x-matroska`, and a Matroska file with a stereoscopic video track is
+//! `video/x-matroska-3d`. Otherwise the file gets the type of its `DocType`.
+
+pub mod check;
+
+use crate::typetable::TypeTable;
+
+/// Most bytes of a file searched for the `Tracks` element
+///
+/// `SeekHead`, `Info` and padding usually come before it.
+const WINDOW_LEN: usize = 16 * crate::probe::HEADER_LEN;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("application/x-matroska", &[]),
+	("video/x-matroska",       &["application/x-matroska"]),
+	("audio/x-matroska",       &["application/x-matroska"]),
+	("video/x-matroska-3d",    &["application/x-matroska"]),
+	("video/webm",             &[]),
+	("audio/webm",             &["video/webm"])
+];
+
+/// Element IDs, with their length markers
+const EBML: u32 = 0x1a45dfa3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x18538067;
+const CLUSTER: u32 = 0x1f43b675;
+const TRACKS: u32 = 0x1654ae6b;
+const TRACK_ENTRY: u32 = 0xae;
+const TRACK_TYPE: u32 = 0x83;
+const VIDEO: u32 = 0xe0;
+const STEREO_MODE: u32 = 0x53b8;
+
+/// Values of `TrackType`
+const TRACK_VIDEO: u64 = 1;
+const TRACK_AUDIO: u64 = 2;
+const TRACK_COMPLEX: u64 = 3;
+
+/// Reads a variable-length integer: the number of leading zero bits in the
+/// first byte says how many more bytes follow
+///
+/// Returns (value with the length marker, value without it, length).
+fn read_vint(b: &[u8]) -> Option<(u64, u64, usize)> {
+	let first = *b.first()?;
+	if first == 0 {
+		return None;
+	}
+	let len = first.leading_zeros() as usize + 1;
+	let raw = b.get(..len)?.iter().fold(0u64, |n, &x| (n << 8) | u64::from(x));
+	let marker = 1u64 << (7 * len);
+	Some( (raw, raw & (marker - 1), len) )
+}
+
+/// An element, with its contents cut short if the input ends first
+struct Element<'a> {
+	id: u32,
+	contents: &'a [u8],
+	complete: bool
+}
+
+/// Iterates over a list of elements
+///
+/// An element that runs past the end of the input is cut short and ends the
+/// list. So does one of unknown length, which takes the rest of the input.
+struct Elements<'a> {
+	rest: &'a [u8]
+}
+
+impl<'a> Iterator for Elements<'a> {
+	type Item = Element<'a>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let b = self.rest;
+		let (id, _, id_len) = read_vint(b)?;
+		// IDs are at most 4 bytes long
+		if id_len > 4 {
+			self.rest = &[];
+			return None;
+		}
+		let (_, size, size_len) = read_vint(&b[id_len..])?;
+		let start = id_len + size_len;
+
+		// A length of all ones means the length is unknown
+		let unknown = size == (1u64 << (7 * size_len)) - 1;
+		let end = match unknown {
+			true => b.len(),
+			false => std::cmp::min(size.saturating_add(start as u64), b.len() as u64) as usize
+		};
+		let complete = !unknown && end as u64 == size.saturating_add(start as u64);
+		self.rest = &b[end..];
+		Some(Element{id: id as u32, contents: &b[start..end], complete})
+	}
+}
+
+fn elements(b: &[u8]) -> Elements<'_> {
+	Elements{rest: b}
+}
+
+/// Reads an unsigned integer element
+fn read_uint(b: &[u8]) -> Option<u64> {
+	match b.len() {
+		1..=8 => Some(b.iter().fold(0u64, |n, &x| (n << 8) | u64::from(x))),
+		_ => None
+	}
+}
+
+/// What the tracks of a file hold
+#[derive(Default)]
+struct Tracks {
+	video: bool,
+	audio: bool,
+	stereo: bool
+}
+
+/// Reads the track types from a complete `Tracks` element
+fn read_tracks(tracks: &[u8]) -> Tracks {
+	let mut out = Tracks::default();
+	for entry in elements(tracks).filter(|x| x.id == TRACK_ENTRY) {
+		for x in elements(entry.contents) {
+			match x.id {
+				TRACK_TYPE => match read_uint(x.contents) {
+					Some(TRACK_VIDEO) | Some(TRACK_COMPLEX) => out.video = true,
+					Some(TRACK_AUDIO) => out.audio = true,
+					_ => {}
+				},
+				VIDEO => {
+					let stereo = elements(x.contents)
+						.find(|y| y.id == STEREO_MODE)
+						.and_then(|y| read_uint(y.contents));
+					out.stereo |= stereo.is_some_and(|y| y != 0);
+				}
+				_ => {}
+			}
+		}
+	}
+	out
+}
+
+/// Works out the type of an EBML file from its start
+///
+/// Returns None if it isn't one, or its `DocType` is unknown.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	let mut top = elements(b);
+	let header = top.next().filter(|x| x.id == EBML)?;
+	let doc_type = elements(header.contents).find(|x| x.id == DOC_TYPE)?.contents;
+	// Strings may be padded with NULs
+	let doc_type = match doc_type.iter().position(|&x| x == 0) {
+		Some(i) => &doc_type[..i],
+		None => doc_type
+	};
+	let is_webm = match doc_type {
+		b"webm" => true,
+		b"matroska" => false,
+		_ => return None
+	};
+
+	// Tracks are only trusted if the whole element is there
+	let tracks = top.find(|x| x.id == SEGMENT)
+		.and_then(|segment| {
+			elements(segment.contents)
+				.take_while(|x| x.id != CLUSTER)
+				.find(|x| x.id == TRACKS)
+		})
+		.filter(|x| x.complete)
+		.map(|x| read_tracks(x.contents))
+		.unwrap_or_default();
+
+	Some(match (is_webm, tracks) {
+		(true, Tracks{video: false, audio: true, ..}) => "audio/webm",
+		(true, _) => "video/webm",
+		(false, Tracks{video: true, stereo: true, ..}) => "video/x-matroska-3d",
+		(false, Tracks{video: true, ..}) => "video/x-matroska",
+		(false, Tracks{audio: true, ..}) => "audio/x-matroska",
+		(false, _) => "application/x-matroska"
+	})
+}
@@ -0,0 +1,232 @@
+//! Handles executables: ELF, PE and Mach-O
+//!
+//! Whether a binary is a program, a library, an object file or a core dump
+//! is a field of its header, and the magic rules can only look at it at a
+//! fixed offset in one byte order. Here the header is parsed instead:
+//!
+//! - ELF: `e_type`, in either byte order. A shared object with a `PT_INTERP`
+//!   program header is a position-independent executable, so it's an
+//!   `application/x-executable` rather than an `application/x-sharedlib`.
+//! - Mach-O: `filetype`, for 32 and 64-bit headers in either byte order.
+//!   A universal binary gets the type of its first slice.
+//! - MS-DOS: an MZ executable whose `e_lfanew` points to a PE header is an
+//!   `application/vnd.microsoft.portable-executable`, or an
+//!   `application/x-ms-dll` if its `IMAGE_FILE_DLL` flag is set.
+//!
+//! DLLs are also shared libraries, and PE images are also MS-DOS executables.
+//! Java class files share the magic number of universal binaries, so they
+//! are told apart here too.
+
+pub mod check;
+
+use std::convert::{TryFrom, TryInto};
+use crate::probe::HEADER_LEN;
+use crate::typetable::TypeTable;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("application/x-executable",        &[]),
+	("application/x-sharedlib",         &[]),
+	("application/x-object",            &[]),
+	("application/x-core",              &[]),
+	("application/x-ms-dos-executable", &[]),
+	("application/vnd.microsoft.portable-executable", &["application/x-ms-dos-executable"]),
+	("application/x-ms-dll",            &["application/vnd.microsoft.portable-executable", "application/x-sharedlib"]),
+	("application/x-java",              &[])
+];
+
+/// Most bytes read to find a PE header or the first slice of a universal binary
+const MAX_LEN: usize = 256 * HEADER_LEN;
+
+/// Reads integers in a byte order
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+	b: &'a [u8],
+	big_endian: bool
+}
+
+impl<'a> Reader<'a> {
+	fn u16(self, offset: usize) -> Option<u16> {
+		let x = self.b.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+		Some(match self.big_endian {
+			true => u16::from_be_bytes(x),
+			false => u16::from_le_bytes(x)
+		})
+	}
+
+	fn u32(self, offset: usize) -> Option<u32> {
+		let x = self.b.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+		Some(match self.big_endian {
+			true => u32::from_be_bytes(x),
+			false => u32::from_le_bytes(x)
+		})
+	}
+
+	fn u64(self, offset: usize) -> Option<u64> {
+		let x = self.b.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+		Some(match self.big_endian {
+			true => u64::from_be_bytes(x),
+			false => u64::from_le_bytes(x)
+		})
+	}
+}
+
+/// ELF `e_type` values
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+/// ELF program header type of the interpreter's path
+const PT_INTERP: u32 = 3;
+
+fn elf(b: &[u8]) -> Option<&'static str> {
+	if !b.starts_with(b"\x7fELF") {
+		return None;
+	}
+	let is_64 = match b.get(4)? {
+		1 => false,
+		2 => true,
+		_ => return None
+	};
+	let r = Reader{b, big_endian: match b.get(5)? {
+		1 => false,
+		2 => true,
+		_ => return None
+	}};
+
+	match r.u16(16)? {
+		ET_REL => Some("application/x-object"),
+		ET_EXEC => Some("application/x-executable"),
+		ET_CORE => Some("application/x-core"),
+		ET_DYN => {
+			let (phoff, phentsize, phnum) = match is_64 {
+				true => (r.u64(32)?, r.u16(54)?, r.u16(56)?),
+				false => (u64::from(r.u32(28)?), r.u16(42)?, r.u16(44)?)
+			};
+			// Program headers that are cut off can't be checked
+			let interp = (0..u64::from(phnum))
+				.map(|i| phoff.saturating_add(i * u64::from(phentsize)))
+				.map_while(|x| r.u32(usize::try_from(x).ok()?))
+				.any(|x| x == PT_INTERP);
+			match interp {
+				true => Some("application/x-executable"),
+				false => Some("application/x-sharedlib")
+			}
+		}
+		_ => None
+	}
+}
+
+/// Mach-O `filetype` values
+fn mach_o_filetype(filetype: u32) -> Option<&'static str> {
+	match filetype {
+		// Objects and debug symbols
+		1 | 10 => Some("application/x-object"),
+		// Executables, preloaded executables and the dynamic linker
+		2 | 5 | 7 => Some("application/x-executable"),
+		// Fixed VM libraries, dynamic libraries, stubs, bundles and kernel extensions
+		3 | 6 | 8 | 9 | 11 => Some("application/x-sharedlib"),
+		4 => Some("application/x-core"),
+		_ => None
+	}
+}
+
+/// Reads the magic number of a Mach-O header, as whether it's big-endian
+fn mach_o_byte_order(b: &[u8]) -> Option<bool> {
+	match b.get(..4)? {
+		[0xfe, 0xed, 0xfa, 0xce] | [0xfe, 0xed, 0xfa, 0xcf] => Some(true),
+		[0xce, 0xfa, 0xed, 0xfe] | [0xcf, 0xfa, 0xed, 0xfe] => Some(false),
+		_ => None
+	}
+}
+
+fn mach_o(b: &[u8]) -> Option<&'static str> {
+	let big_endian = mach_o_byte_order(b)?;
+	mach_o_filetype(Reader{b, big_endian}.u32(12)?)
+}
+
+/// Gets the offset of the first slice of a universal binary
+///
+/// Java class files share the magic number of 32-bit universal binaries.
+/// They're told apart by the next field: the number of slices is small, but
+/// the class file version is at least 45.
+fn fat_offset(b: &[u8]) -> Option<usize> {
+	let r = Reader{b, big_endian: true};
+	let is_64 = match r.u32(0)? {
+		0xcafebabe => false,
+		0xcafebabf => true,
+		_ => return None
+	};
+	let count = r.u32(4)?;
+	if count == 0 || count > 20 {
+		return None;
+	}
+	let offset = match is_64 {
+		true => r.u64(16)?,
+		false => u64::from(r.u32(16)?)
+	};
+	usize::try_from(offset).ok()
+}
+
+fn fat(b: &[u8]) -> Option<&'static str> {
+	mach_o(b.get(fat_offset(b)?..)?)
+}
+
+fn java(b: &[u8]) -> Option<&'static str> {
+	match b.starts_with(b"\xca\xfe\xba\xbe") && fat_offset(b).is_none() {
+		true => Some("application/x-java"),
+		false => None
+	}
+}
+
+/// `IMAGE_FILE_DLL` in the characteristics of a COFF header
+const IMAGE_FILE_DLL: u16 = 0x2000;
+
+/// Gets the offset of the PE header of an MZ executable
+fn pe_offset(b: &[u8]) -> Option<usize> {
+	if !b.starts_with(b"MZ") {
+		return None;
+	}
+	usize::try_from(Reader{b, big_endian: false}.u32(0x3c)?).ok()
+}
+
+fn mz(b: &[u8]) -> Option<&'static str> {
+	if !b.starts_with(b"MZ") {
+		return None;
+	}
+	let pe = match pe_offset(b) {
+		Some(x) if b.get(x..).is_some_and(|y| y.starts_with(b"PE\0\0")) => x,
+		_ => return Some("application/x-ms-dos-executable")
+	};
+	let characteristics = Reader{b, big_endian: false}.u16(pe + 22);
+	match characteristics {
+		Some(x) if x & IMAGE_FILE_DLL != 0 => Some("application/x-ms-dll"),
+		_ => Some("application/vnd.microsoft.portable-executable")
+	}
+}
+
+/// Gets how many bytes from the start of a file `detect` needs
+fn needed_len(b: &[u8]) -> usize {
+	let len = match (pe_offset(b), fat_offset(b)) {
+		// The COFF header
+		(Some(x), _) => x.saturating_add(24),
+		// The Mach-O header
+		(_, Some(x)) => x.saturating_add(16),
+		_ => 0
+	};
+	match len <= MAX_LEN {
+		true => std::cmp::max(len, HEADER_LEN),
+		false => HEADER_LEN
+	}
+}
+
+/// Works out the type of an executable from its start
+///
+/// Returns None if it isn't one.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	elf(b)
+		.or_else(|| mach_o(b))
+		.or_else(|| fat(b))
+		.or_else(|| java(b))
+		.or_else(|| mz(b))
+}
@@ -0,0 +1,152 @@
+//! Check a file's extension against its contents
+//!
+//! Nothing stops `invoice.pdf` from being an executable. `check_extension`
+//! detects the type of a file from its contents, looks up which types claim
+//! its extension, and says whether the two agree.
+//!
+//! An extension agrees with the contents if it belongs to the detected type
+//! or to one of its aliases or ancestors (a `.txt` HTML page is fine). It also
+//! agrees if it belongs to a more specific type than the one detected (a
+//! `.docx` detected as a ZIP), unless nothing more than
+//! `application/octet-stream` could be detected.
+//!
+//! `suggest_name` picks a name whose extension fits, for files stored under
+//! names like `blob` or `download`.
+//!
+//! # Examples
+//! ```rust
+//! use std::path::Path;
+//! use tree_magic::extension::{check_extension, Verdict};
+//!
+//! let result = check_extension(Path::new("tests/image/gif")).unwrap();
+//! assert_eq!(result.detected, "image/gif");
+//! assert_eq!(result.verdict, Verdict::UnknownExtension);
+//! ```
+
+use std::path::{Path, PathBuf};
+use crate::metadata::{self, MimeInfo};
+use crate::MIME;
+
+/// Whether a file's extension agrees with its contents
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+	/// The extension belongs to the detected type, or a type related to it
+	Consistent,
+	/// The extension belongs to these types, and none of them fit the contents
+	Mismatch(Vec<MIME>),
+	/// The file has no extension, or no known type uses it
+	UnknownExtension
+}
+
+/// The result of `check_extension`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionCheck {
+	/// Type detected from the contents
+	pub detected: MIME,
+	/// Extension of the file name, without the dot
+	pub extension: Option<String>,
+	pub verdict: Verdict
+}
+
+/// Types a detection falls back to, which say nothing about the format
+fn is_generic(mimetype: &MIME) -> bool {
+	*mimetype == "application/octet-stream" ||
+	*mimetype == "all/allfiles" ||
+	*mimetype == "all/all"
+}
+
+/// Checks if an extension's type agrees with the detected type, going by the
+/// same type graph the detection walked
+fn agrees(detected: &MIME, claimed: &MIME) -> bool {
+	metadata::is_subclass(detected.clone(), claimed.clone()) ||
+	(!is_generic(detected) && metadata::is_subclass(claimed.clone(), detected.clone()))
+}
+
+/// Type information from the same database as the magic rules
+fn default_info() -> &'static MimeInfo {
+	#[cfg(feature = "sys_fdo_magic")]
+	return &crate::fdo_magic::sys::INFO;
+	#[cfg(not(feature = "sys_fdo_magic"))]
+	return &metadata::BUILTIN;
+}
+
+/// Checks a file's extension against its contents, using the bundled type
+/// information, with the installed packages over it under `sys_fdo_magic`
+///
+/// Returns None if the file can't be read.
+pub fn check_extension(filepath: &Path) -> Option<ExtensionCheck> {
+	check_extension_with(filepath, default_info())
+}
+
+/// Checks a file's extension against its contents, using the given type information
+///
+/// Use this with `MimeInfo::from_dir` to take the system's extensions into account.
+/// Returns None if the file can't be read.
+pub fn check_extension_with(filepath: &Path, info: &MimeInfo) -> Option<ExtensionCheck> {
+	let detected = crate::from_filepath(filepath)?;
+	let extension = filepath.extension()
+		.and_then(|x| x.to_str())
+		.map(|x| x.to_string());
+
+	let claimed = match extension {
+		Some(ref x) => info.types_with_extension(x),
+		None => Vec::new()
+	};
+
+	let verdict = if claimed.is_empty() {
+		Verdict::UnknownExtension
+	} else if claimed.iter().any(|x| agrees(&detected, x)) {
+		Verdict::Consistent
+	} else {
+		Verdict::Mismatch(claimed)
+	};
+
+	Some(ExtensionCheck{detected, extension, verdict})
+}
+
+/// Suggests a name for a file whose extension doesn't fit its contents, using
+/// the same type information as `check_extension`
+///
+/// See `suggest_name_with`.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// let result = tree_magic::extension::suggest_name(Path::new("tests/image/gif"));
+/// assert_eq!(result, Some(Path::new("tests/image/gif.gif").to_path_buf()));
+/// ```
+pub fn suggest_name(filepath: &Path) -> Option<PathBuf> {
+	suggest_name_with(filepath, default_info())
+}
+
+/// Suggests a name for a file whose extension doesn't fit its contents
+///
+/// An extension that belongs to another type is replaced with the preferred
+/// extension of the detected type. Otherwise the preferred extension is added
+/// to the end of the name. Returns None if the extension already fits, if the
+/// file is empty or can't be read, if the detected type has no extension, or
+/// if nothing more specific than `application/octet-stream` was detected.
+pub fn suggest_name_with(filepath: &Path, info: &MimeInfo) -> Option<PathBuf> {
+	// An empty file passes for text, but has no real type to name it after
+	if std::fs::metadata(filepath).ok()?.len() == 0 {
+		return None;
+	}
+
+	let check = check_extension_with(filepath, info)?;
+	if check.verdict == Verdict::Consistent ||
+	   is_generic(&check.detected) ||
+	   check.detected.type_() == "inode"
+	{
+		return None;
+	}
+	let extension = info.preferred_extension(check.detected.clone())?;
+
+	let mut name = match check.verdict {
+		Verdict::Mismatch(_) => filepath.file_stem()?,
+		_ => filepath.file_name()?
+	}.to_os_string();
+	name.push(".");
+	name.push(extension);
+	Some(filepath.with_file_name(name))
+}
@@ -1,62 +1,42 @@
 use fnv::FnvHashMap;
 use crate::MIME;
+use crate::fdo_magic::prefilter::Prefilter;
 
-/// Read all subclass lines from file
-fn read_subclasses() -> Result<Vec<(MIME, MIME)>, std::io::Error> {
-
-	let r = include_str!("subclasses");
-	let mut subclasses = Vec::<(MIME, MIME)>::new();
-	
-	for line in r.lines() {
-		let child = line.split_whitespace().nth(0).unwrap_or("").parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
-		let parent = line.split_whitespace().nth(1).unwrap_or("").parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
-		
-		subclasses.push( (parent, child) );
-	}
-	
-	Ok(subclasses)
-}
-
-// Get filetype aliases
-fn read_aliaslist() -> Result<FnvHashMap<MIME, MIME>, std::io::Error> {
-	let raliases = include_str!("aliases");
-	let mut aliaslist = FnvHashMap::<MIME, MIME>::default();
-	
-	for line in raliases.lines() {
-		let a = line.split_whitespace().nth(0).unwrap_or("").parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
-		let b = line.split_whitespace().nth(1).unwrap_or("").parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
-		aliaslist.insert(a,b);
-	}
-	
-	let aliaslist = aliaslist;
-	Ok(aliaslist)
+/// Parse a static table of MIME pairs
+///
+/// Every entry was already validated by `build.rs`.
+fn parse_pairs(list: &[(&str, &str)]) -> Vec<(MIME, MIME)> {
+	list.iter()
+		.filter_map(|&(a, b)| Some( (a.parse().ok()?, b.parse().ok()?) ))
+		.collect()
 }
 
 pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
-	read_aliaslist().unwrap_or(FnvHashMap::default())
+	parse_pairs(super::ALIASLIST).into_iter().collect()
 }
 
 /// Get list of supported MIME types
 pub fn get_supported() -> Vec<MIME> {
-	super::ALLRULES.keys().cloned().collect()
+	super::MAGIC.iter()
+		.filter_map(|x| x.mimetype.parse().ok())
+		.collect()
+}
+
+/// Get the magic priority of every type
+pub fn get_priorities() -> FnvHashMap<MIME, u32> {
+	super::MAGIC.iter()
+		.filter_map(|x| Some( (x.mimetype.parse().ok()?, x.priority) ))
+		.collect()
 }
 
 /// Get list of parent -> child subclass links
+///
+/// `build.rs` already changed aliases to the real types.
 pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+	parse_pairs(super::SUBCLASSLIST)
+}
 
-	let mut subclasses = read_subclasses().unwrap_or(Vec::<(MIME, MIME)>::new());
-	
-	// If child or parent refers to an alias, change it to the real type
-	for x in 0..subclasses.len(){
-		match super::ALIASES.get(&subclasses[x].0) {
-			Some(alias) => {subclasses[x].0 = alias.clone();}
-			None => {}
-		}
-		match super::ALIASES.get(&subclasses[x].1) {
-			Some(alias) => {subclasses[x].1 = alias.clone();}
-			None => {}
-		}
-	}
-	
-	subclasses
-}
\ No newline at end of file
+/// Get the prefilter over every type's root rules
+pub fn get_prefilter() -> &'static Prefilter {
+	&super::PREFILTER
+}
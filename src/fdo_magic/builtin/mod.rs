@@ -1,21 +1,26 @@
 //! Read magic file bundled in crate
+//!
+//! The bundled `magic`, `aliases` and `subclasses` files are compiled into
+//! the static tables below by `build.rs`, so nothing is parsed at runtime.
+//! Types and aliases are looked up by binary search in those tables; only
+//! the prefilter is built, on first use.
 
-use petgraph::prelude::*;
-use fnv::FnvHashMap;
 use crate::MIME;
-use super::MagicRule;
+use super::{StaticEntry, StaticRule};
+use super::prefilter::Prefilter;
 
-/// Preload alias list
-lazy_static! {
-	static ref ALIASES: FnvHashMap<MIME, MIME> = {
-		init::get_aliaslist()
-	};
+include!(concat!(env!("OUT_DIR"), "/builtin.rs"));
+
+/// Gets the magic rules of a type
+fn get_entry(mimetype: &MIME) -> Option<&'static StaticEntry> {
+	let i = MAGIC.binary_search_by(|x| x.mimetype.cmp(mimetype.essence_str())).ok()?;
+	Some(&MAGIC[i])
 }
 
-/// Load magic file before anything else.
+/// Prefilter over the root rules of every type
 lazy_static! {
-    static ref ALLRULES: FnvHashMap<MIME, DiGraph<MagicRule, u32>> = {
-        super::ruleset::from_u8(include_bytes!("magic")).unwrap_or(FnvHashMap::default())
+    static ref PREFILTER: Prefilter = {
+        Prefilter::new(MAGIC)
     };
 }
 
@@ -1,95 +1,82 @@
 use petgraph::prelude::*;
-use crate::{MIME};
 
-fn from_u8_singlerule(file: &[u8], rule: &super::MagicRule) -> bool {
-	
-	// Check if we're even in bounds
-	let bound_min =
-		rule.start_off as usize;
-	let bound_max =
-			rule.start_off as usize +
-			rule.val_len as usize +
-			rule.region_len as usize;
-
-	if (file.len()) < bound_max {
+/// Test a value against the given bytes, on borrowed slices only
+///
+/// The value is compared at every offset of the region, which is
+/// `region_len` bytes long (a length of 0 means 1). With a mask,
+/// both the file bytes and the value are masked before comparing.
+fn from_u8_value(
+	file: &[u8],
+	start_off: u32,
+	val: &[u8],
+	mask: Option<&[u8]>,
+	region_len: u32
+) -> bool {
+
+	// Rules are validated when loaded, but never panic on a bad one
+	if val.is_empty() || mask.is_some_and(|x| x.len() != val.len()) {
 		return false;
 	}
-	
-	if rule.region_len == 0 {
-		
-		//println!("Region == 0");
-		
-		match rule.mask {
-			None => {
-				//println!("\tMask == None");
-				let x: Vec<u8> = file.iter().skip(bound_min).take(bound_max - bound_min).map(|&x| x).collect();
-				//println!("\t{:?} / {:?}", x, rule.val);
-				//println!("\tIndent: {}, Start: {}", rule.indent_level, rule.start_off);
-				return rule.val.iter().eq(x.iter());
-			},
-			Some(ref mask) => {
-				//println!("\tMask == Some, len == {}", mask.len());
-				//println!("\tIndent: {}, Start: {}", rule.indent_level, rule.start_off);
-				let mut x: Vec<u8> = file.iter()
-					.skip(bound_min) // Skip to start of area
-					.take(bound_max - bound_min) // Take until end of area - region length
-					.map(|&x| x).collect(); // Convert to vector
-				let mut val: Vec<u8> = rule.val.iter().map(|&x| x).collect();
-				//println!("\t{:?} / {:?}", x, rule.val);
-				
-				
-				assert_eq!(x.len(), mask.len());
-				for i in 0..std::cmp::min(x.len(), mask.len()) {
-					x[i] &= mask[i];
-					val[i] = val[i] & mask[i];
-				}
-				//println!("\t & {:?} => {:?}", mask, x);
-				
-				return rule.val.iter().eq(x.iter());
-			}
-		}
-	
-	} else {
-		//println!("\tRegion == {}", rule.region_len);
-		//println!("\tIndent: {}, Start: {}", rule.indent_level, rule.start_off);
-				
-		// Define our testing slice
-		let ref x: Vec<u8> = file.iter().take(file.len()).map(|&x| x).collect();
-		let testarea: Vec<u8> = x.iter().skip(bound_min).take(bound_max - bound_min).map(|&x| x).collect();
-		//println!("{:?}, {:?}, {:?}\n", file, testarea, rule.val);
-		
-		// Search down until we find a hit
-		let mut y = Vec::<u8>::with_capacity(testarea.len());
-		for x in testarea.windows(rule.val_len as usize) {
 
-			y.clear();
-			
-			// Apply mask to value
-			let ref rule_mask = rule.mask;
-			match *rule_mask {
-				Some(ref mask) => {
-
-					for i in 0..rule.val_len {
-						y.push(x[i as usize] & mask[i as usize]);
-					}
-				},
-				None => y = x.to_vec(),
-			}
-		
-			if y.iter().eq(rule.val.iter()) {
-				return true;
-			}
+	let start_off = start_off as usize;
+	let region_len = std::cmp::max(region_len, 1) as usize;
+
+	for pos in start_off..start_off + region_len {
+		// Later offsets are out of bounds as well
+		let x = match file.get(pos..pos + val.len()) {
+			Some(x) => x,
+			None => return false
+		};
+
+		let hit = match mask {
+			None => x == val,
+			Some(mask) => x.iter()
+				.zip(val.iter())
+				.zip(mask.iter())
+				.all(|((x, v), m)| x & m == v & m)
+		};
+		if hit {
+			return true;
 		}
 	}
 
 	false
 }
 
+/// Get # of bytes needed to test a value at every offset of its region
+pub fn value_scanlen(start_off: u32, val_len: usize, region_len: u32) -> usize {
+	start_off as usize +
+	val_len +
+	std::cmp::max(region_len, 1) as usize - 1
+}
+
+fn from_u8_singlerule(file: &[u8], rule: &super::MagicRule) -> bool {
+	from_u8_value(file, rule.start_off, &rule.val, rule.mask.as_deref(), rule.region_len)
+}
+
+/// Test a static rule, then any of its children
+pub fn from_u8_staticrule(file: &[u8], rule: &super::StaticRule) -> bool {
+	if !from_u8_value(file, rule.start_off, rule.val, rule.mask, rule.region_len) {
+		return false;
+	}
+
+	rule.children.is_empty() ||
+		rule.children.iter().any(|x| from_u8_staticrule(file, x))
+}
+
+/// Get # of bytes needed to test a static rule and its children
+pub fn staticrule_scanlen(rule: &super::StaticRule) -> usize {
+	let own = value_scanlen(rule.start_off, rule.val.len(), rule.region_len);
+
+	rule.children.iter()
+		.map(staticrule_scanlen)
+		.fold(own, std::cmp::max)
+}
+
 /// Test every given rule by walking graph
 /// TODO: Not loving the code duplication here.
 pub fn from_u8_walker(
 	file: &[u8],
-	mimetype: MIME,
 	graph: &DiGraph<super::MagicRule, u32>,
 	node: NodeIndex,
 	isroot: bool
@@ -120,7 +107,7 @@ pub fn from_u8_walker(
 		if from_u8_singlerule(&file, rule) {
 			// Check next indent level if needed
 			if graph.neighbors_directed(y, Outgoing).count() != 0 {
-				return from_u8_walker(file, mimetype, graph, y, false);
+				return from_u8_walker(file, graph, y, false);
 			// Next indent level is lower, so this must be it
 			} else {
 				return true;
@@ -129,4 +116,32 @@ pub fn from_u8_walker(
 	}
 	
 	false
-}
\ No newline at end of file
+}
+
+/// Test against every ruleset in a type's rule graph
+pub fn from_u8_rulegraph(
+	file: &[u8],
+	graph: &DiGraph<super::MagicRule, u32>
+) -> bool {
+	for x in graph.externals(Incoming) {
+		if from_u8_walker(file, graph, x, true) {
+			return true;
+		}
+	}
+
+	false
+}
+
+/// Get # of bytes needed to test every rule in a rule graph
+pub fn rulegraph_scanlen(graph: &DiGraph<super::MagicRule, u32>) -> usize {
+	let mut scanlen = 0;
+	for x in graph.raw_nodes() {
+		let y = &x.weight;
+		let tmplen = value_scanlen(y.start_off, y.val_len as usize, y.region_len);
+			
+		if tmplen > scanlen {
+			scanlen = tmplen;
+		}
+	}
+	scanlen
+}
@@ -0,0 +1,479 @@
+//! Reader for the binary `mime.cache` format written by `update-mime-database`
+//!
+//! Nothing is parsed up front: a `MimeCache` is a view over the raw bytes
+//! (which may come straight from a memory map), and every lookup reads the
+//! big-endian tables in place. Offsets are bounds-checked, so a truncated or
+//! corrupt cache gives empty results instead of panicking.
+
+use std::cmp::Ordering;
+use crate::metadata::fnmatch;
+
+/// Size of the fixed header
+const HEADER_LEN: usize = 40;
+
+// Header fields, by index of the CARD32 after the version numbers
+const ALIAS_LIST: usize = 0;
+const PARENT_LIST: usize = 1;
+const LITERAL_LIST: usize = 2;
+const REVERSE_SUFFIX_TREE: usize = 3;
+const GLOB_LIST: usize = 4;
+const MAGIC_LIST: usize = 5;
+const NAMESPACE_LIST: usize = 6;
+const ICONS_LIST: usize = 7;
+const GENERIC_ICONS_LIST: usize = 8;
+
+/// Bit set in a glob weight when the pattern is case-sensitive
+const CASE_SENSITIVE: u32 = 0x100;
+
+/// Deepest matchlet nesting accepted. update-mime-database never writes
+/// anywhere near this many indent levels.
+const MAX_MATCHLET_DEPTH: usize = 64;
+
+/// A read-only view of a `mime.cache` file
+#[derive(Debug, Clone, Copy)]
+pub struct MimeCache<'a> {
+	data: &'a [u8]
+}
+
+impl<'a> MimeCache<'a> {
+
+	/// Checks the header and wraps the given bytes
+	///
+	/// Only major version 1 is supported.
+	pub fn new(data: &'a [u8]) -> Result<MimeCache<'a>, String> {
+		if data.len() < HEADER_LEN {
+			return Err("mime.cache is too short".to_string());
+		}
+		let cache = MimeCache{data};
+		let (major, minor) = cache.version();
+		if major != 1 {
+			return Err(format!("Unsupported mime.cache version {}.{}", major, minor));
+		}
+		Ok(cache)
+	}
+
+	/// Gets the (major, minor) version of the cache
+	pub fn version(&self) -> (u16, u16) {
+		(
+			u16::from_be_bytes([self.data[0], self.data[1]]),
+			u16::from_be_bytes([self.data[2], self.data[3]])
+		)
+	}
+
+	fn u32_at(&self, offset: usize) -> Option<u32> {
+		let b = self.data.get(offset..offset.checked_add(4)?)?;
+		Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+	}
+
+	/// Reads the NUL-terminated string at the given offset
+	fn str_at(&self, offset: u32) -> Option<&'a str> {
+		let b = self.data.get(offset as usize..)?;
+		let end = b.iter().position(|&x| x == 0)?;
+		std::str::from_utf8(&b[..end]).ok()
+	}
+
+	fn list_offset(&self, field: usize) -> usize {
+		self.u32_at(4 + field * 4).unwrap_or(0) as usize
+	}
+
+	/// Gets the entry count of a list, and the offset of its first entry
+	fn list(&self, field: usize) -> (usize, usize) {
+		let offset = self.list_offset(field);
+		match offset {
+			0 => (0, 0),
+			_ => (self.u32_at(offset).unwrap_or(0) as usize, offset + 4)
+		}
+	}
+
+	/// Binary search through a list sorted by the string at the start of each entry
+	fn search(&self, field: usize, entry_len: usize, key: &str) -> Option<usize> {
+		let (count, first) = self.list(field);
+		let (mut lo, mut hi) = (0, count);
+		while lo < hi {
+			let mid = (lo + hi) / 2;
+			let entry = first + mid * entry_len;
+			let name = self.str_at(self.u32_at(entry)?)?;
+			match name.as_bytes().cmp(key.as_bytes()) {
+				Ordering::Equal => return Some(entry),
+				Ordering::Less => lo = mid + 1,
+				Ordering::Greater => hi = mid
+			}
+		}
+		None
+	}
+
+	/// Iterates over a list of (string, string) pairs
+	fn pairs(&self, field: usize) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+		let cache = *self;
+		let (count, first) = self.list(field);
+		(0..count).filter_map(move |i| {
+			let entry = first + i * 8;
+			Some((
+				cache.str_at(cache.u32_at(entry)?)?,
+				cache.str_at(cache.u32_at(entry + 4)?)?
+			))
+		})
+	}
+
+	/// Iterates over every (alias, MIME) pair
+	pub fn aliases(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+		self.pairs(ALIAS_LIST)
+	}
+
+	/// Transforms an alias into its real type
+	pub fn get_alias(&self, alias: &str) -> Option<&'a str> {
+		let entry = self.search(ALIAS_LIST, 8, alias)?;
+		self.str_at(self.u32_at(entry + 4)?)
+	}
+
+	fn parents_at(&self, entry: usize) -> Vec<&'a str> {
+		let offset = match self.u32_at(entry + 4) {
+			Some(x) => x as usize,
+			None => return Vec::new()
+		};
+		let count = self.u32_at(offset).unwrap_or(0) as usize;
+		(0..count)
+			.filter_map(|i| self.str_at(self.u32_at(offset + 4 + i * 4)?))
+			.collect()
+	}
+
+	/// Iterates over every parent -> child relation
+	pub fn subclasses(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+		let cache = *self;
+		let (count, first) = self.list(PARENT_LIST);
+		(0..count).flat_map(move |i| {
+			let entry = first + i * 8;
+			let child = cache.u32_at(entry).and_then(|x| cache.str_at(x));
+			let parents = match child {
+				Some(_) => cache.parents_at(entry),
+				None => Vec::new()
+			};
+			parents.into_iter().map(move |x| (x, child.unwrap_or("")))
+		})
+	}
+
+	/// Iterates over every (namespaceURI, localName, MIME) triple
+	pub fn namespaces(&self) -> impl Iterator<Item = (&'a str, &'a str, &'a str)> + 'a {
+		let cache = *self;
+		let (count, first) = self.list(NAMESPACE_LIST);
+		(0..count).filter_map(move |i| {
+			let entry = first + i * 12;
+			Some((
+				cache.str_at(cache.u32_at(entry)?)?,
+				cache.str_at(cache.u32_at(entry + 4)?)?,
+				cache.str_at(cache.u32_at(entry + 8)?)?
+			))
+		})
+	}
+
+	/// Gets the number of bytes needed to test every magic rule
+	pub fn max_extent(&self) -> usize {
+		let offset = self.list_offset(MAGIC_LIST);
+		self.u32_at(offset + 4).unwrap_or(0) as usize
+	}
+
+	/// Iterates over the magic entries, highest priority first
+	pub fn magic(&self) -> impl Iterator<Item = MagicEntry<'a>> + 'a {
+		let cache = *self;
+		let offset = self.list_offset(MAGIC_LIST);
+		let count = self.u32_at(offset).unwrap_or(0) as usize;
+		let first = self.u32_at(offset + 8).unwrap_or(0) as usize;
+		(0..count).filter_map(move |i| MagicEntry::new(cache, first + i * 16))
+	}
+
+	/// Looks up a literal (whole file name) glob
+	fn lookup_literal(&self, filename: &str, case_sensitive: bool) -> Vec<(&'a str, u32)> {
+		let entry = match self.search(LITERAL_LIST, 12, filename) {
+			Some(x) => x,
+			None => return Vec::new()
+		};
+		let weight = self.u32_at(entry + 8).unwrap_or(0);
+		if weight & CASE_SENSITIVE != 0 && !case_sensitive {
+			return Vec::new();
+		}
+		match self.u32_at(entry + 4).and_then(|x| self.str_at(x)) {
+			Some(x) => vec![(x, weight & 0xff)],
+			None => Vec::new()
+		}
+	}
+
+	/// Walks the reverse suffix tree from the last character of a name
+	fn lookup_suffix(
+		&self,
+		name: &[char],
+		count: usize,
+		first: usize,
+		case_sensitive: bool,
+		out: &mut Vec<(&'a str, u32)>
+	) -> usize {
+		let c = match name.last() {
+			Some(&x) => x as u32,
+			None => return 0
+		};
+
+		// Nodes are sorted by character
+		let (mut lo, mut hi) = (0, count);
+		while lo < hi {
+			let mid = (lo + hi) / 2;
+			let node = first + mid * 12;
+			let character = match self.u32_at(node) {
+				Some(x) => x,
+				None => return 0
+			};
+			match character.cmp(&c) {
+				Ordering::Less => lo = mid + 1,
+				Ordering::Greater => hi = mid,
+				Ordering::Equal => {
+					let n_children = self.u32_at(node + 4).unwrap_or(0) as usize;
+					let child = self.u32_at(node + 8).unwrap_or(0) as usize;
+					let rest = &name[..name.len() - 1];
+
+					let found = match rest.is_empty() {
+						true => 0,
+						false => self.lookup_suffix(rest, n_children, child, case_sensitive, out)
+					};
+					if found != 0 {
+						return found;
+					}
+
+					// No longer suffix matched, so use the leaves here.
+					// Leaves have character 0, so they come first.
+					let mut found = 0;
+					for i in 0..n_children {
+						let leaf = child + i * 12;
+						if self.u32_at(leaf) != Some(0) {
+							break;
+						}
+						let mimetype = self.u32_at(leaf + 4).and_then(|x| self.str_at(x));
+						let weight = self.u32_at(leaf + 8).unwrap_or(0);
+						if let Some(x) = mimetype {
+							if weight & CASE_SENSITIVE == 0 || case_sensitive {
+								out.push((x, weight & 0xff));
+								found += 1;
+							}
+						}
+					}
+					return found;
+				}
+			}
+		}
+		0
+	}
+
+	/// Finds the types whose globs match a file name (not a full path)
+	///
+	/// As in xdgmime, literal names beat suffixes, the longest suffix beats
+	/// shorter ones, and only if neither match are the remaining globs tried.
+	/// Returns (MIME, weight) pairs.
+	pub fn lookup_glob(&self, filename: &str) -> Vec<(&'a str, u32)> {
+		let lower = filename.to_lowercase();
+
+		let mut out = self.lookup_literal(&lower, false);
+		if out.is_empty() {
+			out = self.lookup_literal(filename, true);
+		}
+		if !out.is_empty() {
+			return out;
+		}
+
+		let offset = self.list_offset(REVERSE_SUFFIX_TREE);
+		if offset != 0 {
+			let count = self.u32_at(offset).unwrap_or(0) as usize;
+			let first = self.u32_at(offset + 4).unwrap_or(0) as usize;
+			let chars: Vec<char> = lower.chars().collect();
+			if self.lookup_suffix(&chars, count, first, false, &mut out) == 0 {
+				let chars: Vec<char> = filename.chars().collect();
+				self.lookup_suffix(&chars, count, first, true, &mut out);
+			}
+		}
+		if !out.is_empty() {
+			return out;
+		}
+
+		let (count, first) = self.list(GLOB_LIST);
+		for i in 0..count {
+			let entry = first + i * 12;
+			let pattern = match self.u32_at(entry).and_then(|x| self.str_at(x)) {
+				Some(x) => x,
+				None => continue
+			};
+			let mimetype = match self.u32_at(entry + 4).and_then(|x| self.str_at(x)) {
+				Some(x) => x,
+				None => continue
+			};
+			let weight = self.u32_at(entry + 8).unwrap_or(0);
+			let name = match weight & CASE_SENSITIVE {
+				0 => lower.as_str(),
+				_ => filename
+			};
+			if fnmatch(pattern, name) {
+				out.push((mimetype, weight & 0xff));
+			}
+		}
+		out
+	}
+}
+
+/// One `[priority:mime]` section of the magic list
+#[derive(Debug, Clone, Copy)]
+pub struct MagicEntry<'a> {
+	cache: MimeCache<'a>,
+	pub priority: u32,
+	pub mimetype: &'a str,
+	n_matchlets: usize,
+	first_matchlet: usize
+}
+
+impl<'a> MagicEntry<'a> {
+	fn new(cache: MimeCache<'a>, offset: usize) -> Option<MagicEntry<'a>> {
+		Some(MagicEntry{
+			cache,
+			priority: cache.u32_at(offset)?,
+			mimetype: cache.str_at(cache.u32_at(offset + 4)?)?,
+			n_matchlets: cache.u32_at(offset + 8)? as usize,
+			first_matchlet: cache.u32_at(offset + 12)? as usize
+		})
+	}
+
+	/// Tests the given bytes against this entry's rules
+	pub fn matches(&self, file: &[u8]) -> bool {
+		self.any_matchlet(file, self.n_matchlets, self.first_matchlet)
+	}
+
+	/// Checks that every matchlet is well-formed, so matching never
+	/// has to deal with bad values, word sizes or child lists
+	pub fn validate(&self) -> Result<(), String> {
+		self.validate_matchlets(self.n_matchlets, self.first_matchlet, 0)
+			.map_err(|e| format!("{}: {}", self.mimetype, e))
+	}
+
+	fn validate_matchlets(&self, count: usize, first: usize, depth: usize) -> Result<(), String> {
+		// Child lists pointing back up the tree would otherwise recurse forever
+		if depth > MAX_MATCHLET_DEPTH {
+			return Err("Matchlets nested too deeply".to_string());
+		}
+
+		let c = &self.cache;
+		for i in 0..count {
+			let m = first + i * 32;
+			let field = |x: usize| c.u32_at(m + x)
+				.map(|y| y as usize)
+				.ok_or_else(|| format!("Matchlet at {} is out of bounds", m));
+			let (word_size, len, val_off, mask_off) = (field(8)?, field(12)?, field(16)?, field(20)?);
+
+			if len == 0 {
+				return Err(format!("Empty value in matchlet at {}", m));
+			}
+			if c.data.get(val_off..val_off.saturating_add(len)).is_none() {
+				return Err(format!("Value of matchlet at {} is out of bounds", m));
+			}
+			if mask_off != 0 && c.data.get(mask_off..mask_off.saturating_add(len)).is_none() {
+				return Err(format!("Mask of matchlet at {} is out of bounds", m));
+			}
+			if word_size != 1 && word_size != 2 && word_size != 4 {
+				return Err(format!("Bad word size {} in matchlet at {}", word_size, m));
+			}
+			if len % word_size != 0 {
+				return Err(format!("Value of matchlet at {} is not a whole number of words", m));
+			}
+
+			self.validate_matchlets(field(24)?, field(28)?, depth + 1)?;
+		}
+		Ok(())
+	}
+
+	/// Gets the number of bytes needed to test this entry
+	pub fn extent(&self) -> usize {
+		self.extent_of(self.n_matchlets, self.first_matchlet)
+	}
+
+	fn extent_of(&self, count: usize, first: usize) -> usize {
+		let c = &self.cache;
+		let mut res = 0;
+		for i in 0..count {
+			let m = first + i * 32;
+			let (start, range, len) = match (c.u32_at(m), c.u32_at(m + 4), c.u32_at(m + 12)) {
+				(Some(a), Some(b), Some(d)) => (a as usize, b as usize, d as usize),
+				_ => continue
+			};
+			res = std::cmp::max(res, start + range + len);
+			let n_children = c.u32_at(m + 24).unwrap_or(0) as usize;
+			let child = c.u32_at(m + 28).unwrap_or(0) as usize;
+			res = std::cmp::max(res, self.extent_of(n_children, child));
+		}
+		res
+	}
+
+	/// A matchlet matches if its value is found and, if it has children,
+	/// any one of them matches too
+	fn any_matchlet(&self, file: &[u8], count: usize, first: usize) -> bool {
+		let c = &self.cache;
+		for i in 0..count {
+			let m = first + i * 32;
+			if !self.matchlet_value(file, m) {
+				continue;
+			}
+			let n_children = c.u32_at(m + 24).unwrap_or(0) as usize;
+			if n_children == 0 {
+				return true;
+			}
+			let child = c.u32_at(m + 28).unwrap_or(0) as usize;
+			if self.any_matchlet(file, n_children, child) {
+				return true;
+			}
+		}
+		false
+	}
+
+	/// Compares the value of a single matchlet, without its children
+	fn matchlet_value(&self, file: &[u8], m: usize) -> bool {
+		let c = &self.cache;
+		let fields = (
+			c.u32_at(m), c.u32_at(m + 4), c.u32_at(m + 8),
+			c.u32_at(m + 12), c.u32_at(m + 16), c.u32_at(m + 20)
+		);
+		let (start, range, word_size, len, val_off, mask_off) = match fields {
+			(Some(a), Some(b), Some(d), Some(e), Some(f), Some(g)) =>
+				(a as usize, b as usize, d as usize, e as usize, f as usize, g as usize),
+			_ => return false
+		};
+
+		let val = match c.data.get(val_off..val_off.saturating_add(len)) {
+			Some(x) => x,
+			None => return false
+		};
+		let mask = match mask_off {
+			0 => None,
+			x => match c.data.get(x..x.saturating_add(len)) {
+				Some(y) => Some(y),
+				None => return false
+			}
+		};
+
+		// Numbers are stored big-endian, and swapped to host order
+		// a word at a time on little-endian machines
+		let swap = cfg!(target_endian = "little") && word_size > 1 && len % word_size == 0;
+		let index = |i: usize| match swap {
+			true => (i / word_size) * word_size + (word_size - 1 - i % word_size),
+			false => i
+		};
+
+		for pos in start..start.saturating_add(std::cmp::max(range, 1)) {
+			let window = match file.get(pos..pos.saturating_add(len)) {
+				Some(x) => x,
+				None => return false
+			};
+			let hit = (0..len).all(|i| {
+				let j = index(i);
+				match mask {
+					Some(mask) => window[i] & mask[j] == val[j] & mask[j],
+					None => window[i] == val[j]
+				}
+			});
+			if hit {
+				return true;
+			}
+		}
+		false
+	}
+}
@@ -1,5 +1,7 @@
 // Common routines for all fdo_magic parsers
 
+use std::path::PathBuf;
+
 pub mod builtin;
 
 #[derive(Debug, Clone)]
@@ -13,5 +15,85 @@ pub struct MagicRule {
     pub region_len: u32
 }
 
+impl MagicRule {
+	/// Checks that the rule can be matched as written
+	///
+	/// Bad rules are rejected when loading, so matching never has to.
+	pub fn validate(&self) -> Result<(), String> {
+		if self.val.is_empty() || self.val.len() != self.val_len as usize {
+			return Err(format!("Bad value length {} at offset {}", self.val_len, self.start_off));
+		}
+		if let Some(ref mask) = self.mask {
+			if mask.len() != self.val.len() {
+				return Err(format!("Mask length does not match value at offset {}", self.start_off));
+			}
+		}
+		if self.word_len != 1 && self.word_len != 2 && self.word_len != 4 {
+			return Err(format!("Bad word size {} at offset {}", self.word_len, self.start_off));
+		}
+		if !self.val.chunks_exact(self.word_len as usize).remainder().is_empty() {
+			return Err(format!("Value is not a whole number of words at offset {}", self.start_off));
+		}
+		Ok(())
+	}
+}
+
+/// A magic rule stored in static data, with its children inline
+///
+/// Unlike `MagicRule`, values are already in the target's byte order,
+/// so there is no word size.
+#[derive(Debug)]
+pub struct StaticRule {
+	pub start_off: u32,
+	pub val: &'static [u8],
+	pub mask: Option<&'static [u8]>,
+	pub region_len: u32,
+	pub children: &'static [StaticRule]
+}
+
+/// All static magic rules for a type
+#[derive(Debug)]
+pub struct StaticEntry {
+	pub priority: u32,
+	pub mimetype: &'static str,
+	pub rules: &'static [StaticRule]
+}
+
 pub mod ruleset;
+pub mod xml;
+pub mod mime_cache;
 pub mod check;
+pub mod prefilter;
+#[cfg(feature = "sys_fdo_magic")]
+pub mod sys;
+#[cfg(feature = "sys_mime_cache")]
+pub mod sys_cache;
+
+/// Get list of XDG `mime` directories, lowest precedence first
+///
+/// These are `$XDG_DATA_DIRS/mime` in reverse order followed by
+/// `$XDG_DATA_HOME/mime`, with the defaults from the XDG Base Directory spec.
+pub fn mime_dirs() -> Vec<PathBuf> {
+	use std::env;
+
+	let mut dirs = Vec::<PathBuf>::new();
+
+	let data_dirs = env::var("XDG_DATA_DIRS").unwrap_or_default();
+	let data_dirs = match data_dirs.as_str() {
+		"" => "/usr/local/share:/usr/share",
+		x => x
+	};
+	for dir in data_dirs.split(':').rev().filter(|x| !x.is_empty()) {
+		dirs.push(PathBuf::from(dir).join("mime"));
+	}
+
+	let data_home = env::var_os("XDG_DATA_HOME")
+		.map(PathBuf::from)
+		.filter(|x| x.is_absolute())
+		.or_else(|| env::var_os("HOME").map(|x| PathBuf::from(x).join(".local/share")));
+	if let Some(x) = data_home {
+		dirs.push(x.join("mime"));
+	}
+
+	dirs
+}
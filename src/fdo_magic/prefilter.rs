@@ -0,0 +1,181 @@
+//! Multi-pattern prefilter for static magic rules
+//!
+//! Walking the type graph tests each type's rules one at a time, which means
+//! hundreds of failed comparisons for text and unknown binaries. A `Prefilter`
+//! looks at every type's root rules at once instead: fixed-offset rules are
+//! dispatched on the byte at their offset, and region rules are found with a
+//! single Aho-Corasick pass over the buffer. Types whose root rules cannot
+//! match are left out of the resulting `Candidates`, so they are never walked.
+//!
+//! The prefilter only ever rules types out. Candidates still have to be
+//! checked with their full rules.
+
+use aho_corasick::{AhoCorasick, AhoCorasickKind};
+use fnv::FnvHashMap;
+use crate::MIME;
+use super::StaticEntry;
+
+/// A region rule found by the automaton: (start offset, region length, type ID)
+type RegionRule = (usize, usize, usize);
+
+/// Index over the root rules of a set of types
+pub struct Prefilter {
+	/// Every indexed type, sorted by MIME. A type's ID is its index here.
+	entries: &'static [StaticEntry],
+	/// Fixed-offset rules as (offset, (byte, type ID) sorted by byte)
+	fixed: Vec<(usize, Vec<(u8, usize)>)>,
+	/// Automaton over the distinct values of all unmasked region rules
+	automaton: Option<AhoCorasick>,
+	/// Rules using each automaton pattern, by pattern ID
+	regions: Vec<Vec<RegionRule>>,
+	/// Number of bytes the automaton needs to look at
+	region_scanlen: usize,
+	/// Bytes each region rule needs to look at, as (scan length, type ID)
+	region_ends: Vec<(usize, usize)>,
+	/// Types with a root rule that can't be indexed, and so are always candidates
+	always: Vec<usize>
+}
+
+/// Types that passed the prefilter for a given buffer
+pub struct Candidates {
+	bits: Vec<u64>
+}
+
+impl Candidates {
+	fn new(count: usize) -> Candidates {
+		Candidates{bits: vec![0; count.div_ceil(64)]}
+	}
+
+	fn insert(&mut self, id: usize) {
+		self.bits[id / 64] |= 1 << (id % 64);
+	}
+
+	/// Checks if the type with the given ID may match
+	pub fn contains(&self, id: usize) -> bool {
+		self.bits.get(id / 64).is_some_and(|x| x & (1 << (id % 64)) != 0)
+	}
+}
+
+impl Prefilter {
+	/// Builds the index from the root rules of every type in a sorted table
+	pub fn new(entries: &'static [StaticEntry]) -> Prefilter {
+		let mut fixed = FnvHashMap::<usize, Vec<(u8, usize)>>::default();
+		let mut patterns = FnvHashMap::<&'static [u8], usize>::default();
+		let mut regions = Vec::<Vec<RegionRule>>::new();
+		let mut region_scanlen = 0;
+		let mut region_ends = Vec::<(usize, usize)>::new();
+		let mut always = Vec::<usize>::new();
+
+		for (id, entry) in entries.iter().enumerate() {
+			for rule in entry.rules {
+				let start_off = rule.start_off as usize;
+				// A region of 0 or 1 bytes is a single offset
+				if rule.region_len <= 1 {
+					// Dispatch on the first byte the mask doesn't touch
+					let i = match rule.mask {
+						None => Some(0),
+						Some(mask) => mask.iter().position(|&x| x == 0xff)
+					};
+					match i {
+						Some(i) => fixed.entry(start_off + i).or_default().push((rule.val[i], id)),
+						None => always.push(id)
+					}
+				} else if rule.mask.is_none() {
+					let next = patterns.len();
+					let pattern = *patterns.entry(rule.val).or_insert(next);
+					if pattern == regions.len() {
+						regions.push(Vec::new());
+					}
+					regions[pattern].push((start_off, rule.region_len as usize, id));
+					let scanlen = super::check::value_scanlen(rule.start_off, rule.val.len(), rule.region_len);
+					region_scanlen = std::cmp::max(region_scanlen, scanlen);
+					region_ends.push((scanlen, id));
+				} else {
+					always.push(id);
+				}
+			}
+		}
+
+		let mut fixed: Vec<(usize, Vec<(u8, usize)>)> = fixed.into_iter().collect();
+		fixed.sort_by_key(|x| x.0);
+		for (_, x) in fixed.iter_mut() {
+			x.sort();
+			x.dedup();
+		}
+
+		let mut values: Vec<(&[u8], usize)> = patterns.into_iter().collect();
+		values.sort_by_key(|x| x.1);
+		let automaton = match values.is_empty() {
+			true => None,
+			// A DFA is bigger but noticeably faster to scan with
+			false => AhoCorasick::builder()
+				.kind(Some(AhoCorasickKind::DFA))
+				.build(values.iter().map(|x| x.0))
+				.ok()
+		};
+		// Without an automaton, region rules can't be ruled out
+		if automaton.is_none() {
+			always.extend(regions.iter().flatten().map(|x| x.2));
+		}
+		always.sort();
+		always.dedup();
+
+		Prefilter{entries, fixed, automaton, regions, region_scanlen, region_ends, always}
+	}
+
+	/// Gets the ID of a type, if it is indexed
+	pub fn id(&self, mimetype: &MIME) -> Option<usize> {
+		self.entries.binary_search_by(|x| x.mimetype.cmp(mimetype.essence_str())).ok()
+	}
+
+	/// Finds the types whose root rules may match the given buffer
+	///
+	/// If `complete` is false, the buffer is only the start of the file, and
+	/// rules that look past its end can't be ruled out.
+	pub fn candidates(&self, file: &[u8], complete: bool) -> Candidates {
+		let mut out = Candidates::new(self.entries.len());
+
+		for &id in self.always.iter() {
+			out.insert(id);
+		}
+
+		for (i, (offset, bytes)) in self.fixed.iter().enumerate() {
+			let byte = match file.get(*offset) {
+				Some(&x) => x,
+				// Offsets are sorted, so no later rule fits either
+				None => {
+					if !complete {
+						for &(_, id) in self.fixed[i..].iter().flat_map(|x| x.1.iter()) {
+							out.insert(id);
+						}
+					}
+					break;
+				}
+			};
+			let first = bytes.partition_point(|x| x.0 < byte);
+			for &(_, id) in bytes[first..].iter().take_while(|x| x.0 == byte) {
+				out.insert(id);
+			}
+		}
+
+		if !complete && file.len() < self.region_scanlen {
+			for &(_, id) in self.region_ends.iter().filter(|x| x.0 > file.len()) {
+				out.insert(id);
+			}
+		}
+
+		if let Some(ref automaton) = self.automaton {
+			let area = &file[..std::cmp::min(file.len(), self.region_scanlen)];
+			for found in automaton.find_overlapping_iter(area) {
+				let pos = found.start();
+				for &(start_off, region_len, id) in self.regions[found.pattern().as_usize()].iter() {
+					if start_off <= pos && pos < start_off + region_len {
+						out.insert(id);
+					}
+				}
+			}
+		}
+
+		out
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{fdo_magic, MIME};
+use crate::probe::Probe;
+
+// Packages add to the database loaded before them
+#[cfg(not(feature = "sys_mime_cache"))]
+use crate::fdo_magic::builtin as base;
+#[cfg(feature = "sys_mime_cache")]
+use crate::fdo_magic::sys_cache as base;
+
+/// Bytes to read when looking for an XML root element
+const ROOTXML_SCANLEN: usize = 2048;
+
+/// Checks if the packages left the base database's magic for a type in place
+fn keeps_base(mimetype: &MIME) -> bool {
+	!super::PACKAGES.magic_deleteall.contains(mimetype)
+}
+
+/// Test against the packages' rules, then against the XML root element
+fn from_u8_packages(file: &[u8], mimetype: &MIME) -> bool {
+
+	if let Some(graph) = super::PACKAGES.rules.get(mimetype) {
+		if fdo_magic::check::from_u8_rulegraph(file, graph) {
+			return true;
+		}
+	}
+
+	let roots = match super::PACKAGES.root_xml.get(mimetype) {
+		Some(item) => item,
+		None => return false
+	};
+	let (namespace, local) = match fdo_magic::xml::root_element(file) {
+		Some(x) => x,
+		None => return false
+	};
+
+	roots.iter().any(|x| x.0 == namespace && x.1 == local)
+}
+
+/// Test against the packages, then against the base database
+pub fn from_u8(file: &[u8], mimetype: MIME) -> bool {
+	from_u8_packages(file, &mimetype) ||
+		(keeps_base(&mimetype) && base::check::from_u8(file, mimetype))
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool {
+
+	// Get # of bytes to read
+	let mut scanlen = match super::PACKAGES.rules.get(&mimetype) {
+		Some(graph) => fdo_magic::check::rulegraph_scanlen(graph),
+		None => 0
+	};
+	if super::PACKAGES.root_xml.contains_key(&mimetype) {
+		scanlen = std::cmp::max(scanlen, ROOTXML_SCANLEN);
+	}
+
+	let found = match probe.bytes(scanlen) {
+		Some(b) => from_u8_packages(b, &mimetype),
+		None => false
+	};
+	found || (keeps_base(&mimetype) && base::check::from_probe(probe, mimetype))
+}
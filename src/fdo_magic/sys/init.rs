@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use fnv::FnvHashMap;
+use crate::{metadata, MIME};
+use crate::metadata::MimeInfo;
+use crate::fdo_magic::{self, xml::{self, Package}};
+
+/// List every package file, lowest precedence first
+fn package_paths() -> Vec<PathBuf> {
+	let mut res = Vec::<PathBuf>::new();
+
+	for dir in fdo_magic::mime_dirs() {
+		let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir.join("packages")) {
+			Ok(x) => x.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+			Err(_) => continue
+		};
+		paths.retain(|x| x.extension().is_some_and(|e| e == "xml"));
+		paths.sort();
+		res.extend(paths);
+	}
+
+	res
+}
+
+/// Read and merge every package file
+///
+/// Files that cannot be read or parsed are skipped, as are malformed
+/// elements in the others. Each is noted in the result's `errors`.
+pub fn read_packages() -> Package {
+	let mut res = Package::default();
+	for path in package_paths() {
+		match xml::from_filepath(&path) {
+			Ok(package) => res.merge(package),
+			Err(e) => res.errors.push(e)
+		}
+	}
+	res
+}
+
+/// Read the globs and descriptions of every package file over the bundled ones
+///
+/// Files are read in the same order as `read_packages`, and skipped the same way.
+/// `read_packages` already reports the files that are skipped.
+pub fn read_info() -> MimeInfo {
+	let mut res = metadata::BUILTIN.clone();
+	for path in package_paths() {
+		if let Ok(info) = MimeInfo::from_filepath(&path) {
+			res.merge(info);
+		}
+	}
+	res
+}
+
+pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
+	super::PACKAGES.aliases.clone()
+}
+
+/// Get list of supported MIME types
+pub fn get_supported() -> Vec<MIME> {
+	super::PACKAGES.rules.keys()
+		.chain(super::PACKAGES.root_xml.keys())
+		.chain(super::PACKAGES.magic_deleteall.iter())
+		.cloned()
+		.collect()
+}
+
+/// Get the magic priority of every type the packages give rules for
+pub fn get_priorities() -> FnvHashMap<MIME, u32> {
+	super::PACKAGES.priorities.clone()
+}
+
+/// Get list of parent -> child subclass links
+pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+
+	let mut subclasses = super::PACKAGES.subclasses.clone();
+	
+	// If child or parent refers to an alias, change it to the real type
+	for x in subclasses.iter_mut() {
+		if let Some(alias) = super::PACKAGES.aliases.get(&x.0) {
+			x.0 = alias.clone();
+		}
+		if let Some(alias) = super::PACKAGES.aliases.get(&x.1) {
+			x.1 = alias.clone();
+		}
+	}
+	
+	subclasses
+}
@@ -0,0 +1,26 @@
+//! Read shared-mime-info XML packages installed on the system
+//!
+//! Packages are loaded from `$XDG_DATA_DIRS/mime/packages` and then
+//! `$XDG_DATA_HOME/mime/packages`, so definitions in the user's home
+//! directory take precedence over system-wide ones. Their globs are merged
+//! over the bundled ones for `from_filename`.
+
+use crate::fdo_magic::xml::Package;
+use crate::metadata::{globs::GlobIndex, MimeInfo};
+
+/// Load every package file before anything else.
+lazy_static! {
+	pub static ref PACKAGES: Package = {
+		init::read_packages()
+	};
+}
+
+/// The bundled type information with the packages on top, for looking
+/// types up by name in the same database the magic comes from
+lazy_static! {
+	pub static ref INFO: MimeInfo = init::read_info();
+	pub static ref GLOBS: GlobIndex = GlobIndex::new(&INFO);
+}
+
+pub mod init;
+pub mod check;
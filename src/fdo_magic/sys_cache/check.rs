@@ -0,0 +1,50 @@
+use crate::{fdo_magic, MIME};
+use crate::probe::Probe;
+use crate::fdo_magic::builtin;
+
+/// Bytes to read when looking for an XML root element
+const ROOTXML_SCANLEN: usize = 2048;
+
+/// Test against all rules, then against the XML root element
+pub fn from_u8(file: &[u8], mimetype: MIME) -> bool {
+	if super::CACHEFILES.is_empty() {
+		return builtin::check::from_u8(file, mimetype);
+	}
+
+	if let Some(entries) = super::MAGIC.get(&mimetype) {
+		if entries.iter().any(|x| x.matches(file)) {
+			return true;
+		}
+	}
+
+	let roots = match super::ROOTXML.get(&mimetype) {
+		Some(item) => item,
+		None => return false
+	};
+	let (namespace, local) = match fdo_magic::xml::root_element(file) {
+		Some(x) => x,
+		None => return false
+	};
+
+	roots.iter().any(|x| x.0 == namespace && x.1 == local)
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool {
+	if super::CACHEFILES.is_empty() {
+		return builtin::check::from_probe(probe, mimetype);
+	}
+
+	// Get # of bytes to read
+	let mut scanlen = match super::MAGIC.get(&mimetype) {
+		Some(entries) => entries.iter().map(|x| x.extent()).max().unwrap_or(0),
+		None => 0
+	};
+	if super::ROOTXML.contains_key(&mimetype) {
+		scanlen = std::cmp::max(scanlen, ROOTXML_SCANLEN);
+	}
+
+	match probe.bytes(scanlen) {
+		Some(b) => from_u8(b, mimetype),
+		None => false
+	}
+}
@@ -0,0 +1,31 @@
+use crate::{metadata, MIME};
+
+/// Finds the type of a file from its name alone, using the globs of every cache
+///
+/// Only the last component of a path is looked at. The highest glob weight
+/// wins, and the cache with the highest precedence breaks ties.
+/// Falls back to the bundled globs if no cache was loaded.
+pub fn from_filename(filename: &str) -> Option<MIME> {
+	if super::CACHEFILES.is_empty() {
+		return metadata::BUILTIN_GLOBS.from_filename(filename);
+	}
+
+	let name = match filename.rfind('/') {
+		Some(i) => &filename[i + 1..],
+		None => filename
+	};
+	if name.is_empty() {
+		return None;
+	}
+
+	let mut best: Option<(&str, u32)> = None;
+	for cache in super::caches() {
+		for (mimetype, weight) in cache.lookup_glob(name) {
+			match best {
+				Some((_, x)) if x >= weight => {},
+				_ => best = Some((mimetype, weight))
+			}
+		}
+	}
+	best.and_then(|x| x.0.parse().ok())
+}
@@ -0,0 +1,134 @@
+use std::fs::File;
+use std::path::Path;
+use fnv::FnvHashMap;
+use memmap2::Mmap;
+use crate::MIME;
+use crate::fdo_magic::{self, builtin};
+use crate::fdo_magic::mime_cache::{MimeCache, MagicEntry};
+
+/// Map every valid `mime.cache`, highest precedence first
+pub fn read_caches() -> Vec<Mmap> {
+	fdo_magic::mime_dirs().iter().rev()
+		.filter_map(|x| map_cache(&x.join("mime.cache")))
+		.filter(|x| MimeCache::new(x).is_ok())
+		.collect()
+}
+
+/// Maps a cache file into memory
+fn map_cache(path: &Path) -> Option<Mmap> {
+	let f = File::open(path).ok()?;
+
+	// SAFETY: the mapping is only ever read. update-mime-database writes a
+	// new cache and renames it over the old one, so a mapped cache is never
+	// truncated or rewritten in place.
+	unsafe { Mmap::map(&f) }.ok()
+}
+
+pub fn index_magic() -> FnvHashMap<MIME, Vec<MagicEntry<'static>>> {
+	let mut res = FnvHashMap::<MIME, Vec<MagicEntry<'static>>>::default();
+	let mut seen = FnvHashMap::<MIME, usize>::default();
+
+	for (i, cache) in super::caches().enumerate() {
+		for entry in cache.magic() {
+			let mimetype: MIME = match entry.mimetype.parse() {
+				Ok(x) => x,
+				Err(_) => continue
+			};
+			// Reject malformed rules here instead of at match time
+			if entry.validate().is_err() {
+				continue;
+			}
+			// Skip types already defined by a higher-precedence cache
+			if *seen.entry(mimetype.clone()).or_insert(i) != i {
+				continue;
+			}
+			res.entry(mimetype).or_default().push(entry);
+		}
+	}
+
+	res
+}
+
+pub fn index_namespaces() -> FnvHashMap<MIME, Vec<(&'static str, &'static str)>> {
+	let mut res = FnvHashMap::<MIME, Vec<(&'static str, &'static str)>>::default();
+	let mut seen = FnvHashMap::<MIME, usize>::default();
+
+	for (i, cache) in super::caches().enumerate() {
+		for (namespace, local, mimetype) in cache.namespaces() {
+			let mimetype: MIME = match mimetype.parse() {
+				Ok(x) => x,
+				Err(_) => continue
+			};
+			if *seen.entry(mimetype.clone()).or_insert(i) != i {
+				continue;
+			}
+			res.entry(mimetype).or_default().push((namespace, local));
+		}
+	}
+
+	res
+}
+
+pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
+	if super::CACHEFILES.is_empty() {
+		return builtin::init::get_aliaslist();
+	}
+
+	let mut aliaslist = FnvHashMap::<MIME, MIME>::default();
+	// Lowest precedence first, so higher ones overwrite
+	let caches: Vec<MimeCache> = super::caches().collect();
+	for cache in caches.iter().rev() {
+		for (a, b) in cache.aliases() {
+			if let (Ok(a), Ok(b)) = (a.parse(), b.parse()) {
+				aliaslist.insert(a, b);
+			}
+		}
+	}
+	aliaslist
+}
+
+/// Get list of supported MIME types
+pub fn get_supported() -> Vec<MIME> {
+	if super::CACHEFILES.is_empty() {
+		return builtin::init::get_supported();
+	}
+
+	super::MAGIC.keys()
+		.chain(super::ROOTXML.keys())
+		.cloned()
+		.collect()
+}
+
+/// Get the highest magic priority of every type
+pub fn get_priorities() -> FnvHashMap<MIME, u32> {
+	if super::CACHEFILES.is_empty() {
+		return builtin::init::get_priorities();
+	}
+
+	super::MAGIC.iter()
+		.map(|(k, v)| (k.clone(), v.iter().map(|x| x.priority).max().unwrap_or(0)))
+		.collect()
+}
+
+/// Get list of parent -> child subclass links
+pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+	if super::CACHEFILES.is_empty() {
+		return builtin::init::get_subclasses();
+	}
+
+	// If child or parent refers to an alias, change it to the real type
+	let resolve = |x: &str| super::caches()
+		.find_map(|cache| cache.get_alias(x))
+		.unwrap_or(x)
+		.parse::<MIME>();
+
+	let mut subclasses = Vec::<(MIME, MIME)>::new();
+	for cache in super::caches() {
+		for (parent, child) in cache.subclasses() {
+			if let (Ok(a), Ok(b)) = (resolve(parent), resolve(child)) {
+				subclasses.push( (a, b) );
+			}
+		}
+	}
+	subclasses
+}
@@ -0,0 +1,39 @@
+//! Read the `mime.cache` files installed on the system
+//!
+//! Each cache is mapped into memory once and never parsed: aliases, subclasses,
+//! globs and magic rules are looked up straight from the cache's tables.
+//! If no usable cache is found, this checker falls back to the bundled
+//! database in `fdo_magic::builtin`.
+
+use fnv::FnvHashMap;
+use memmap2::Mmap;
+use crate::MIME;
+use crate::fdo_magic::mime_cache::{MimeCache, MagicEntry};
+
+lazy_static! {
+	/// Mapped cache files, highest precedence first
+	static ref CACHEFILES: Vec<Mmap> = {
+		init::read_caches()
+	};
+}
+
+/// Magic entries and XML root elements of each type, from the
+/// highest-precedence cache that defines them
+lazy_static! {
+	static ref MAGIC: FnvHashMap<MIME, Vec<MagicEntry<'static>>> = {
+		init::index_magic()
+	};
+	static ref ROOTXML: FnvHashMap<MIME, Vec<(&'static str, &'static str)>> = {
+		init::index_namespaces()
+	};
+}
+
+/// Iterates over the loaded caches, highest precedence first
+fn caches() -> impl Iterator<Item = MimeCache<'static>> {
+	let files: &'static Vec<Mmap> = &CACHEFILES;
+	files.iter().filter_map(|x| MimeCache::new(&x[..]).ok())
+}
+
+pub mod init;
+pub mod check;
+pub mod glob;
@@ -0,0 +1,442 @@
+//! Parser for shared-mime-info XML package files
+//!
+//! Compiles the `<magic>` sections of a `<mime-info>` document into the same
+//! `MagicRule` graphs the binary `magic` file produces, and collects the
+//! `<alias>`, `<sub-class-of>` and `<root-XML>` entries alongside them.
+
+use std::path::Path;
+use std::cmp::max;
+use petgraph::prelude::*;
+use fnv::{FnvHashMap, FnvHashSet};
+use crate::MIME;
+use super::MagicRule;
+
+/// Priority of `<magic>` sections that don't give one
+pub const DEFAULT_PRIORITY: u32 = 50;
+
+/// Magic-related contents of one or more package files
+#[derive(Debug, Clone, Default)]
+pub struct Package {
+	pub rules: FnvHashMap<MIME, DiGraph<MagicRule, u32>>,
+	/// Highest `<magic priority>` of each type with rules
+	pub priorities: FnvHashMap<MIME, u32>,
+	pub aliases: FnvHashMap<MIME, MIME>,
+	/// Parent -> child relations
+	pub subclasses: Vec<(MIME, MIME)>,
+	/// (namespaceURI, localName) pairs of XML document types
+	pub root_xml: FnvHashMap<MIME, Vec<(String, String)>>,
+	/// Types whose earlier magic should be discarded (`<magic-deleteall/>`)
+	pub magic_deleteall: FnvHashSet<MIME>,
+	/// Problems with the elements that were skipped
+	pub errors: Vec<String>
+}
+
+impl Package {
+	/// Adds the definitions of a package with higher precedence
+	///
+	/// Magic is added to the rules already known for a type, unless the
+	/// new package discards them with `<magic-deleteall/>`.
+	pub fn merge(&mut self, other: Package) {
+		for mimetype in other.magic_deleteall.iter() {
+			self.rules.remove(mimetype);
+			self.priorities.remove(mimetype);
+		}
+		for (mimetype, graph) in other.rules {
+			append_graph(self.rules.entry(mimetype).or_default(), graph);
+		}
+		for (mimetype, priority) in other.priorities {
+			let entry = self.priorities.entry(mimetype).or_insert(priority);
+			*entry = max(*entry, priority);
+		}
+		self.aliases.extend(other.aliases);
+		for x in other.subclasses {
+			if !self.subclasses.contains(&x) {
+				self.subclasses.push(x);
+			}
+		}
+		for (mimetype, roots) in other.root_xml {
+			let entry = self.root_xml.entry(mimetype).or_default();
+			for x in roots {
+				if !entry.contains(&x) {
+					entry.push(x);
+				}
+			}
+		}
+		self.magic_deleteall.extend(other.magic_deleteall);
+		self.errors.extend(other.errors);
+	}
+}
+
+/// Adds every rule of one rule graph to another, keeping both rule trees
+fn append_graph(graph: &mut DiGraph<MagicRule, u32>, other: DiGraph<MagicRule, u32>) {
+	let offset = graph.node_count();
+	let (nodes, edges) = other.into_nodes_edges();
+	for x in nodes {
+		graph.add_node(x.weight);
+	}
+	for x in edges {
+		graph.add_edge(
+			NodeIndex::new(offset + x.source().index()),
+			NodeIndex::new(offset + x.target().index()),
+			x.weight
+		);
+	}
+}
+
+/// Parses a number written the way `strtol` with base 0 reads it
+fn parse_number(s: &str) -> Result<u64, String> {
+	let s = s.trim();
+	let (negative, s) = match s.strip_prefix('-') {
+		Some(x) => (true, x),
+		None => (false, s)
+	};
+	let res = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+		u64::from_str_radix(hex, 16)
+	} else if s.len() > 1 && s.starts_with('0') {
+		u64::from_str_radix(&s[1..], 8)
+	} else {
+		s.parse::<u64>()
+	};
+	let res = res.map_err(|_| format!("Invalid number \"{}\"", s))?;
+
+	Ok(if negative { res.wrapping_neg() } else { res })
+}
+
+/// Expands the C-style escapes allowed in string values
+fn unescape(s: &str) -> Vec<u8> {
+	let b = s.as_bytes();
+	let mut out = Vec::<u8>::with_capacity(b.len());
+	let mut i = 0;
+
+	while i < b.len() {
+		if b[i] != b'\\' || i + 1 == b.len() {
+			out.push(b[i]);
+			i += 1;
+			continue;
+		}
+
+		i += 1;
+		match b[i] {
+			b'n' => { out.push(b'\n'); i += 1; },
+			b'r' => { out.push(b'\r'); i += 1; },
+			b't' => { out.push(b'\t'); i += 1; },
+			b'x' => {
+				// Up to two hex digits
+				let digits = b[i + 1..].iter()
+					.take(2)
+					.take_while(|x| x.is_ascii_hexdigit())
+					.count();
+				let hex = std::str::from_utf8(&b[i + 1..i + 1 + digits]).unwrap_or("");
+				match u8::from_str_radix(hex, 16) {
+					Ok(x) => out.push(x),
+					Err(_) => out.push(b'x')
+				}
+				i += 1 + digits;
+			},
+			b'0'..=b'7' => {
+				// Up to three octal digits
+				let digits = b[i..].iter()
+					.take(3)
+					.take_while(|x| (b'0'..=b'7').contains(x))
+					.count();
+				let oct = std::str::from_utf8(&b[i..i + digits]).unwrap_or("");
+				out.push(u32::from_str_radix(oct, 8).unwrap_or(0) as u8);
+				i += digits;
+			},
+			x => { out.push(x); i += 1; }
+		}
+	}
+
+	out
+}
+
+/// Decodes a `0x`-prefixed string mask
+fn parse_hex_mask(s: &str) -> Result<Vec<u8>, String> {
+	let hex = s.trim();
+	let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X"))
+		.ok_or_else(|| format!("String mask \"{}\" is not hexadecimal", s))?;
+	if hex.len() % 2 != 0 {
+		return Err(format!("String mask \"{}\" has an odd number of digits", s));
+	}
+
+	(0..hex.len()).step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+		.collect::<Result<Vec<u8>, _>>()
+		.map_err(|_| format!("String mask \"{}\" is not hexadecimal", s))
+}
+
+/// Encodes a numeric value with the width and byte order of a match type
+fn encode_number(matchtype: &str, x: u64) -> Result<Vec<u8>, String> {
+	Ok(match matchtype {
+		"byte" => vec![x as u8],
+		"big16" => (x as u16).to_be_bytes().to_vec(),
+		"big32" => (x as u32).to_be_bytes().to_vec(),
+		"little16" => (x as u16).to_le_bytes().to_vec(),
+		"little32" => (x as u32).to_le_bytes().to_vec(),
+		"host16" => (x as u16).to_ne_bytes().to_vec(),
+		"host32" => (x as u32).to_ne_bytes().to_vec(),
+		_ => return Err(format!("Unknown match type \"{}\"", matchtype))
+	})
+}
+
+/// Converts one `<match>` element into a rule
+fn parse_match(node: roxmltree::Node, indent_level: u32) -> Result<MagicRule, String> {
+	let matchtype = node.attribute("type").ok_or("<match> without type")?;
+	let value = node.attribute("value").ok_or("<match> without value")?;
+	let offset = node.attribute("offset").ok_or("<match> without offset")?;
+
+	// Offset is either "start" or "start:end", both inclusive
+	let (start_off, end_off) = match offset.find(':') {
+		Some(i) => (parse_number(&offset[..i])?, parse_number(&offset[i + 1..])?),
+		None => {
+			let x = parse_number(offset)?;
+			(x, x)
+		}
+	};
+	if end_off < start_off || end_off > u32::MAX as u64 {
+		return Err(format!("Invalid offset \"{}\"", offset));
+	}
+	// Stored the way update-mime-database writes "+range" to the magic file
+	let region_len = match end_off - start_off {
+		0 => 0,
+		x => x as u32 + 1
+	};
+
+	let (val, mask) = if matchtype == "string" {
+		let val = unescape(value);
+		let mask = match node.attribute("mask") {
+			Some(x) => Some(parse_hex_mask(x)?),
+			None => None
+		};
+		(val, mask)
+	} else {
+		let val = encode_number(matchtype, parse_number(value)?)?;
+		let mask = match node.attribute("mask") {
+			Some(x) => Some(encode_number(matchtype, parse_number(x)?)?),
+			None => None
+		};
+		(val, mask)
+	};
+
+	if val.len() > u16::MAX as usize {
+		return Err(format!("Invalid value length for \"{}\"", value));
+	}
+
+	let rule = MagicRule{
+		indent_level,
+		start_off: start_off as u32,
+		val_len: val.len() as u16,
+		val,
+		mask,
+		// Values are already in file byte order
+		word_len: 1,
+		region_len
+	};
+	rule.validate().map_err(|e| format!("{} in \"{}\"", e, value))?;
+	Ok(rule)
+}
+
+/// Flattens nested `<match>` elements into rules with indent levels,
+/// in the order the binary magic file would list them
+fn parse_matches(node: roxmltree::Node, indent_level: u32, out: &mut Vec<MagicRule>) -> Result<(), String> {
+	for child in node.children().filter(|x| x.has_tag_name("match")) {
+		out.push(parse_match(child, indent_level)?);
+		parse_matches(child, indent_level + 1, out)?;
+	}
+	Ok(())
+}
+
+/// Reads the rules of a `<magic>` element, one top-level `<match>` at a time
+///
+/// A malformed `<match>` drops the whole rule it belongs to, as what's left
+/// of the rule would match more than it should. Returns the problems found.
+fn parse_magic(node: roxmltree::Node, out: &mut Vec<MagicRule>) -> Vec<String> {
+	let mut errors = Vec::<String>::new();
+	for child in node.children().filter(|x| x.has_tag_name("match")) {
+		let mut rule = Vec::<MagicRule>::new();
+		let res = parse_match(child, 0)
+			.and_then(|x| {
+				rule.push(x);
+				parse_matches(child, 1, &mut rule)
+			});
+		match res {
+			Ok(()) => out.extend(rule),
+			Err(e) => errors.push(e)
+		}
+	}
+	errors
+}
+
+/// Converts a package file given as a &[u8] array into a Package
+///
+/// Fails if the file isn't a `<mime-info>` document. A malformed
+/// `<mime-type>` or `<match>` in it is skipped and noted in `errors`.
+pub fn from_u8(b: &[u8]) -> Result<Package, String> {
+	let text = std::str::from_utf8(b).map_err(|e| e.to_string())?;
+	let opt = roxmltree::ParsingOptions{allow_dtd: true, ..Default::default()};
+	let doc = roxmltree::Document::parse_with_options(text, opt).map_err(|e| e.to_string())?;
+
+	let root = doc.root_element();
+	if root.tag_name().name() != "mime-info" {
+		return Err(format!("Expected <mime-info>, found <{}>", root.tag_name().name()));
+	}
+
+	let mut res = Package::default();
+	for node in root.children().filter(|x| x.has_tag_name("mime-type")) {
+		let mimetype: MIME = match node.attribute("type").map(|x| (x, x.parse())) {
+			Some((_, Ok(x))) => x,
+			Some((x, Err(_))) => {
+				res.errors.push(format!("Invalid type \"{}\"", x));
+				continue;
+			},
+			None => {
+				res.errors.push("<mime-type> without type".to_string());
+				continue;
+			}
+		};
+
+		let mut rules = Vec::<MagicRule>::new();
+		let mut priority = None;
+		for child in node.children().filter(|x| x.is_element()) {
+			match child.tag_name().name() {
+				"magic" => {
+					let x = match child.attribute("priority").map(|x| (x, x.trim().parse::<u32>())) {
+						Some((_, Ok(x))) => x,
+						Some((x, Err(_))) => {
+							res.errors.push(format!("{}: Invalid priority \"{}\"", mimetype, x));
+							continue;
+						},
+						None => DEFAULT_PRIORITY
+					};
+					priority = Some(max(priority.unwrap_or(x), x));
+					for e in parse_magic(child, &mut rules) {
+						res.errors.push(format!("{}: {}", mimetype, e));
+					}
+				},
+				"magic-deleteall" => {
+					res.magic_deleteall.insert(mimetype.clone());
+				},
+				"alias" => {
+					match child.attribute("type").map(|x| (x, x.parse())) {
+						Some((_, Ok(x))) => { res.aliases.insert(x, mimetype.clone()); },
+						Some((x, Err(_))) => res.errors.push(format!("{}: Invalid type \"{}\" in <alias>", mimetype, x)),
+						None => res.errors.push(format!("{}: <alias> without type", mimetype))
+					}
+				},
+				"sub-class-of" => {
+					match child.attribute("type").map(|x| (x, x.parse())) {
+						Some((_, Ok(x))) => { res.subclasses.push( (x, mimetype.clone()) ); },
+						Some((x, Err(_))) => res.errors.push(format!("{}: Invalid type \"{}\" in <sub-class-of>", mimetype, x)),
+						None => res.errors.push(format!("{}: <sub-class-of> without type", mimetype))
+					}
+				},
+				"root-XML" => {
+					let ns = child.attribute("namespaceURI").unwrap_or("").to_string();
+					let local = child.attribute("localName").unwrap_or("").to_string();
+					res.root_xml.entry(mimetype.clone()).or_default().push( (ns, local) );
+				},
+				_ => {}
+			}
+		}
+
+		if rules.is_empty() {
+			continue;
+		}
+		if let Some(x) = priority {
+			let entry = res.priorities.entry(mimetype.clone()).or_insert(x);
+			*entry = max(*entry, x);
+		}
+		append_graph(res.rules.entry(mimetype).or_default(), super::ruleset::gen_graph(rules));
+	}
+
+	Ok(res)
+}
+
+/// Loads the given package file
+///
+/// Problems are prefixed with the file's path.
+pub fn from_filepath(filepath: &Path) -> Result<Package, String> {
+	let b = std::fs::read(filepath).map_err(|e| format!("{}: {}", filepath.display(), e))?;
+	let mut res = from_u8(b.as_slice()).map_err(|e| format!("{}: {}", filepath.display(), e))?;
+	for e in res.errors.iter_mut() {
+		*e = format!("{}: {}", filepath.display(), e);
+	}
+	Ok(res)
+}
+
+/// Finds the namespace and local name of the root element of an XML document
+///
+/// Only looks as far as the root element's start tag, so this works on
+/// the first few KiB of a file.
+pub fn root_element(b: &[u8]) -> Option<(String, String)> {
+	fn find(b: &[u8], pat: &[u8]) -> Option<usize> {
+		b.windows(pat.len()).position(|x| x == pat)
+	}
+
+	let mut b = b.strip_prefix(b"\xef\xbb\xbf").unwrap_or(b);
+
+	// Skip prolog, comments and doctype
+	loop {
+		let start = b.iter().position(|x| !x.is_ascii_whitespace())?;
+		b = &b[start..];
+		if b.starts_with(b"<?") {
+			b = &b[find(b, b"?>")? + 2..];
+		} else if b.starts_with(b"<!--") {
+			b = &b[find(b, b"-->")? + 3..];
+		} else if b.starts_with(b"<!") {
+			// Doctype, possibly with an internal subset
+			let mut depth = 0;
+			let end = b.iter().position(|&x| {
+				match x {
+					b'[' => depth += 1,
+					b']' => depth -= 1,
+					b'>' if depth == 0 => return true,
+					_ => {}
+				}
+				false
+			})?;
+			b = &b[end + 1..];
+		} else if b.starts_with(b"<") {
+			break;
+		} else {
+			return None;
+		}
+	}
+
+	// Read the start tag, minding quoted attribute values
+	let mut quote = None;
+	let end = b.iter().position(|&x| {
+		match quote {
+			Some(q) if x == q => quote = None,
+			Some(_) => {},
+			None if x == b'"' || x == b'\'' => quote = Some(x),
+			None => return x == b'>'
+		}
+		false
+	})?;
+	let tag = std::str::from_utf8(&b[1..end]).ok()?;
+	let tag = tag.trim_end_matches('/');
+
+	let name_end = tag.find(|x: char| x.is_whitespace()).unwrap_or(tag.len());
+	let (name, mut attrs) = tag.split_at(name_end);
+	let (prefix, local) = match name.find(':') {
+		Some(i) => (&name[..i], &name[i + 1..]),
+		None => ("", name)
+	};
+
+	// Look for the matching xmlns declaration
+	let wanted = if prefix.is_empty() { "xmlns".to_string() } else { format!("xmlns:{}", prefix) };
+	let mut namespace = String::new();
+	while let Some(eq) = attrs.find('=') {
+		let key = attrs[..eq].trim();
+		let rest = attrs[eq + 1..].trim_start();
+		let q = rest.chars().next()?;
+		let close = rest[1..].find(q)? + 1;
+		if key == wanted {
+			namespace = rest[1..close].to_string();
+		}
+		attrs = &rest[close + 1..];
+	}
+
+	Some((namespace, local.to_string()))
+}
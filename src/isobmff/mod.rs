@@ -0,0 +1,227 @@
+//! Handles ISO base media files: MP4, QuickTime, 3GPP, HEIF and AVIF
+//!
+//! These start with an `ftyp` box that names a major brand and a list of
+//! compatible brands, and the brands say what the file really is. The most
+//! specific brand wins, wherever it is in the list, so a HEIC photo whose
+//! major brand is the generic `mif1` is still HEIC.
+//!
+//! When the brands don't say enough, the handlers of the `meta` box and of
+//! the tracks in `moov` are looked up, as far as they fit in the header:
+//! `pict` makes a HEIF image, whose first coded item tells HEIC from AVIF,
+//! and a movie of only `soun` tracks is audio. Old QuickTime movies have no
+//! `ftyp`, and are recognized by their first boxes instead.
+
+pub mod check;
+
+use crate::typetable::TypeTable;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("video/mp4",           &[]),
+	("video/3gpp",          &["video/mp4"]),
+	("video/3gpp2",         &["video/mp4"]),
+	("audio/mp4",           &[]),
+	("audio/x-m4b",         &["audio/mp4"]),
+	("video/quicktime",     &[]),
+	("image/heif",          &[]),
+	("image/heic",          &["image/heif"]),
+	("image/heif-sequence", &["image/heif"]),
+	("image/heic-sequence", &["image/heif-sequence"]),
+	("image/avif",          &[]),
+	("image/avif-sequence", &["image/avif"])
+];
+
+/// How specific a brand is. Lower wins.
+const SPECIFIC: u8 = 0;
+const FAMILY: u8 = 1;
+const GENERIC: u8 = 2;
+
+/// What a brand says about a file, and how specific it is
+fn brand_type(brand: &[u8]) -> Option<(&'static str, u8)> {
+	Some(match brand {
+		b"heic" | b"heix" | b"heim" | b"heis" => ("image/heic", SPECIFIC),
+		b"hevc" | b"hevx" | b"hevm" | b"hevs" => ("image/heic-sequence", SPECIFIC),
+		b"avif" => ("image/avif", SPECIFIC),
+		b"avis" => ("image/avif-sequence", SPECIFIC),
+		b"mif1" => ("image/heif", FAMILY),
+		b"msf1" => ("image/heif-sequence", FAMILY),
+		b"qt  " => ("video/quicktime", SPECIFIC),
+		b"M4A " | b"M4P " | b"F4A " => ("audio/mp4", SPECIFIC),
+		b"M4B " | b"F4B " => ("audio/x-m4b", SPECIFIC),
+		b"M4V " | b"M4VH" | b"M4VP" | b"F4V " | b"F4P " | b"MSNV" => ("video/mp4", SPECIFIC),
+		[b'3', b'g', b'2', _] => ("video/3gpp2", SPECIFIC),
+		[b'3', b'g', x, _] if b"egprs".contains(x) => ("video/3gpp", SPECIFIC),
+		[b'i', b's', b'o', _] | b"mp41" | b"mp42" | b"avc1" | b"dash" | b"mmp4" => ("video/mp4", GENERIC),
+		_ => return None
+	})
+}
+
+fn u32_be(b: &[u8], offset: usize) -> Option<u32> {
+	let x = b.get(offset..offset + 4)?;
+	Some(u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+}
+
+/// Iterates over a list of boxes, as (type, contents)
+///
+/// A box that runs past the end of the input is cut short and ends the list.
+struct Boxes<'a> {
+	rest: &'a [u8]
+}
+
+impl<'a> Iterator for Boxes<'a> {
+	type Item = (&'a [u8], &'a [u8]);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let b = self.rest;
+		let kind = b.get(4..8)?;
+		let (header_len, size) = match u32_be(b, 0)? {
+			// The box runs to the end of the file
+			0 => (8, b.len() as u64),
+			// The size is a 64-bit number after the type
+			1 => (16, (u64::from(u32_be(b, 8)?) << 32) | u64::from(u32_be(b, 12)?)),
+			x => (8, u64::from(x))
+		};
+		if size < header_len as u64 {
+			self.rest = &[];
+			return None;
+		}
+
+		let end = std::cmp::min(size, b.len() as u64) as usize;
+		let contents = b.get(header_len..end)?;
+		self.rest = &b[end..];
+		Some( (kind, contents) )
+	}
+}
+
+fn boxes(b: &[u8]) -> Boxes<'_> {
+	Boxes{rest: b}
+}
+
+/// Finds the contents of the first child box of a type
+fn child<'a>(b: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+	boxes(b).find(|x| x.0 == kind).map(|x| x.1)
+}
+
+/// Gets the children of a `meta` box
+///
+/// In ISO files it is a full box, with a version and flags before its
+/// children. In QuickTime files it isn't.
+fn meta_children(meta: &[u8]) -> &[u8] {
+	match meta.get(4..8) {
+		Some(b"hdlr") => meta,
+		_ => meta.get(4..).unwrap_or(&[])
+	}
+}
+
+/// Gets the handler type of an `hdlr` box
+fn handler(hdlr: &[u8]) -> Option<&[u8]> {
+	hdlr.get(8..12)
+}
+
+/// Finds the type of the first coded image item, from the `iinf` box of a `meta` box
+fn first_item_type(meta: &[u8]) -> Option<&'static str> {
+	let iinf = child(meta, b"iinf")?;
+	let entries = match iinf.first()? {
+		0 => iinf.get(6..)?,
+		_ => iinf.get(8..)?
+	};
+
+	boxes(entries)
+		.filter(|x| x.0 == b"infe")
+		.filter_map(|(_, infe)| {
+			// Only versions 2 and up have an item type
+			let id_len = match infe.first()? {
+				2 => 2,
+				3 => 4,
+				_ => return None
+			};
+			match infe.get(4 + id_len + 2..4 + id_len + 6)? {
+				b"hvc1" => Some("image/heic"),
+				b"av01" => Some("image/avif"),
+				_ => None
+			}
+		})
+		.next()
+}
+
+/// Works out the type of a HEIF image from its `meta` box
+fn image_type(b: &[u8], default: &'static str) -> &'static str {
+	let meta = match boxes(b).find(|x| x.0 == b"meta") {
+		Some(x) => meta_children(x.1),
+		None => return default
+	};
+	match (default, first_item_type(meta)) {
+		("image/heif", Some(x)) => x,
+		_ => default
+	}
+}
+
+/// Checks if the `meta` box holds an image
+fn has_image_handler(b: &[u8]) -> bool {
+	boxes(b)
+		.filter(|x| x.0 == b"meta")
+		.filter_map(|x| child(meta_children(x.1), b"hdlr"))
+		.any(|x| handler(x) == Some(b"pict"))
+}
+
+/// Finds the handler of every track in the `moov` box
+fn track_handlers(b: &[u8]) -> Vec<&[u8]> {
+	let moov = match child(b, b"moov") {
+		Some(x) => x,
+		None => return Vec::new()
+	};
+	boxes(moov)
+		.filter(|x| x.0 == b"trak")
+		.filter_map(|x| child(x.1, b"mdia"))
+		.filter_map(|x| child(x, b"hdlr"))
+		.filter_map(handler)
+		.collect()
+}
+
+/// Recognizes a QuickTime movie without an `ftyp` box by its first boxes
+fn is_old_quicktime(b: &[u8]) -> bool {
+	let mut kinds = boxes(b).map(|x| x.0);
+	match kinds.next() {
+		Some(b"moov") | Some(b"mdat") => true,
+		// Padding before the movie
+		Some(b"wide") | Some(b"free") | Some(b"skip") => {
+			matches!(kinds.next(), Some(b"moov") | Some(b"mdat"))
+		}
+		_ => false
+	}
+}
+
+/// Works out the type of an ISO base media file from its start
+///
+/// Returns None if it isn't one, or its brands are unknown.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	let ftyp = match boxes(b).next() {
+		Some((b"ftyp", x)) => x,
+		_ => return match is_old_quicktime(b) {
+			true => Some("video/quicktime"),
+			false => None
+		}
+	};
+
+	// The major brand, then the compatible brands after the minor version
+	let major = ftyp.get(..4)?;
+	let compatible = ftyp.get(8..).unwrap_or(&[]).chunks_exact(4);
+	let (mimetype, rank) = std::iter::once(major)
+		.chain(compatible)
+		.filter_map(brand_type)
+		.min_by_key(|x| x.1)?;
+
+	Some(match mimetype {
+		"image/heif" | "image/heif-sequence" => image_type(b, mimetype),
+		_ if rank < GENERIC => mimetype,
+		// Only generic MP4 brands, so look at what's inside
+		_ if has_image_handler(b) => image_type(b, "image/heif"),
+		_ => {
+			let handlers = track_handlers(b);
+			match !handlers.is_empty() && handlers.iter().all(|&x| x == b"soun") {
+				true => "audio/mp4",
+				false => "video/mp4"
+			}
+		}
+	})
+}
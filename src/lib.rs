@@ -12,12 +12,43 @@
 //! - Very fast perfomance (~150ns to check one file against one type,
 //!   between 5,000ns and 100,000ns to find a MIME type.)
 //! - Check if a file *is* a certain type.
+//! - Guess a type from a file name alone, when there are no contents to read
 //! - Handles aliases (ex: `application/zip` vs `application/x-zip-compressed`)
 //! - Can delegate different file types to different "checkers", reducing false positives
 //!   by choosing a different method of attack.
+//! - Look up descriptions, icons and file extensions of a MIME type (see `metadata`)
+//! - Catch files whose extension doesn't match their contents (see `extension`)
+//! - Recognize tar archives by their header checksums, and list their members (see `tar`)
+//! - Tell MP4, QuickTime, 3GPP, HEIC and AVIF files apart by their brands
+//! - Tell Matroska from WebM, and audio from video, by parsing their EBML headers
+//! - Identify the codecs of every stream in an Ogg file
+//! - Find MP3 and AAC frames behind ID3 and APE tags of any length
+//! - Tell programs, libraries, object files and core dumps apart in ELF, PE and Mach-O
+//! - Recognize SQLite databases of known applications, and Berkeley DB, LMDB and DuckDB files
+//! - Tell Office documents, Java archives and Android packages from other ZIP files
+//!   by the names of their members
+//! - Tell a gzipped tarball from any other gzip file (see `compressed`)
+//! - Predict the type a browser gives a web resource, per the WHATWG MIME Sniffing Standard
+//!   (see `sniff::web`)
+//! - Checkers share one cached view of each file instead of rereading it (see `probe`)
 //!
 //! # Feature flags
-//! `cli`:        Enable building of `tmagic` binary
+//! `cli`:            Enable building of `tmagic` binary
+//! `sys_fdo_magic`:  Also load shared-mime-info XML packages from
+//!                   `$XDG_DATA_DIRS/mime/packages` and `$XDG_DATA_HOME/mime/packages`
+//! `sys_mime_cache`: Use the system's `mime.cache` files instead of the bundled database
+//!                   for magic, aliases, subclasses and globs, falling back to the
+//!                   bundled one if none are found
+//! `parallel`:       Classify batches of files on worker threads (see `parallel`)
+//! `async`:          Detect types from tokio `AsyncRead`s and files without blocking
+//!                   (see `asynchronous`)
+//! `mmap`:           Serve reads past the header of regular files from a memory map.
+//!                   A file truncated by another process while it is read can
+//!                   still crash the process with SIGBUS (see `probe`)
+//! `gzip`, `bzip2`, `xz`, `zstd`, `lz4`:
+//!                   Decompress the start of files in these formats and detect the
+//!                   type of their contents (see `compressed`)
+//! `compressed`:     All of the compression formats above
 //!
 //! # Example
 //! ```rust
@@ -37,7 +68,6 @@
 
 #![allow(unused_doc_comments)]
 #![allow(dead_code)]
-#[macro_use] extern crate nom;
 #[macro_use] extern crate lazy_static;
 
 use petgraph::prelude::*;
@@ -48,6 +78,26 @@ use mime::Mime as MIME;
 
 mod fdo_magic;
 mod basetype;
+mod isobmff;
+mod riff;
+mod ebml;
+mod ogg;
+mod mpeg_audio;
+mod executable;
+mod database;
+mod zip;
+mod typetable;
+pub mod metadata;
+pub mod probe;
+pub mod extension;
+pub mod tar;
+pub mod sniff;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "decompress")]
+pub mod compressed;
 
 /// Check these types first
 /// TODO: Poll these from the checkers? Feels a bit arbitrary
@@ -62,34 +112,166 @@ const TYPEORDER: [MIME; 4] =
 /// Struct used to define checker functions for the sake of boilerplate reduction
 struct CheckerStruct {
     from_u8: fn(&[u8], MIME) -> bool,
-    from_filepath: fn(&Path, MIME) -> bool,
+    from_probe: fn(&probe::Probe, MIME) -> bool,
     get_supported: fn() -> Vec<MIME>,
     get_subclasses: fn() -> Vec<(MIME, MIME)>,
-    get_aliaslist: fn() -> FnvHashMap<MIME, MIME>
+    get_aliaslist: fn() -> FnvHashMap<MIME, MIME>,
+    get_prefilter: Option<fn() -> &'static fdo_magic::prefilter::Prefilter>,
+    get_priorities: Option<fn() -> FnvHashMap<MIME, u32>>
 }
 
 /// Maximum number of checkers supported with build config.
 /// TODO: Find any better way to do this!
-const CHECKERCOUNT: usize = 2;
+const CHECKERCOUNT: usize = 11 +
+    cfg!(feature = "sys_fdo_magic") as usize +
+    cfg!(feature = "decompress") as usize;
 
 /// List of checker functions
 const CHECKERS: [CheckerStruct; CHECKERCOUNT] = 
 [
     // fdo_magic
+    #[cfg(not(feature = "sys_mime_cache"))]
     CheckerStruct{
         from_u8: fdo_magic::builtin::check::from_u8,
-        from_filepath: fdo_magic::builtin::check::from_filepath,
+        from_probe: fdo_magic::builtin::check::from_probe,
         get_supported: fdo_magic::builtin::init::get_supported,
         get_subclasses: fdo_magic::builtin::init::get_subclasses,
-        get_aliaslist: fdo_magic::builtin::init::get_aliaslist
+        get_aliaslist: fdo_magic::builtin::init::get_aliaslist,
+        get_prefilter: Some(fdo_magic::builtin::init::get_prefilter),
+        get_priorities: Some(fdo_magic::builtin::init::get_priorities)
+    },
+    // fdo_magic::sys_cache, which replaces fdo_magic::builtin
+    #[cfg(feature = "sys_mime_cache")]
+    CheckerStruct{
+        from_u8: fdo_magic::sys_cache::check::from_u8,
+        from_probe: fdo_magic::sys_cache::check::from_probe,
+        get_supported: fdo_magic::sys_cache::init::get_supported,
+        get_subclasses: fdo_magic::sys_cache::init::get_subclasses,
+        get_aliaslist: fdo_magic::sys_cache::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: Some(fdo_magic::sys_cache::init::get_priorities)
+    },
+    // fdo_magic::sys
+    #[cfg(feature = "sys_fdo_magic")]
+    CheckerStruct{
+        from_u8: fdo_magic::sys::check::from_u8,
+        from_probe: fdo_magic::sys::check::from_probe,
+        get_supported: fdo_magic::sys::init::get_supported,
+        get_subclasses: fdo_magic::sys::init::get_subclasses,
+        get_aliaslist: fdo_magic::sys::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: Some(fdo_magic::sys::init::get_priorities)
+    },
+    // tar, which replaces the magic rules for application/x-tar
+    CheckerStruct{
+        from_u8: tar::check::from_u8,
+        from_probe: tar::check::from_probe,
+        get_supported: tar::init::get_supported,
+        get_subclasses: tar::init::get_subclasses,
+        get_aliaslist: tar::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // isobmff, which replaces the magic rules for MP4, QuickTime and 3GPP
+    CheckerStruct{
+        from_u8: isobmff::check::from_u8,
+        from_probe: isobmff::check::from_probe,
+        get_supported: || typetable::supported(isobmff::TYPES),
+        get_subclasses: || typetable::subclasses(isobmff::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // riff, which adds the variants of RIFF formats
+    CheckerStruct{
+        from_u8: riff::check::from_u8,
+        from_probe: riff::check::from_probe,
+        get_supported: || typetable::supported(riff::TYPES),
+        get_subclasses: riff::init::get_subclasses,
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // ebml, which replaces the magic rules for Matroska and WebM
+    CheckerStruct{
+        from_u8: ebml::check::from_u8,
+        from_probe: ebml::check::from_probe,
+        get_supported: || typetable::supported(ebml::TYPES),
+        get_subclasses: || typetable::subclasses(ebml::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // ogg, which replaces the magic rules for Ogg files
+    CheckerStruct{
+        from_u8: ogg::check::from_u8,
+        from_probe: ogg::check::from_probe,
+        get_supported: || typetable::supported(ogg::TYPES),
+        get_subclasses: || typetable::subclasses(ogg::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // mpeg_audio, which replaces the magic rules for MP3 and AAC streams
+    CheckerStruct{
+        from_u8: mpeg_audio::check::from_u8,
+        from_probe: mpeg_audio::check::from_probe,
+        get_supported: || typetable::supported(mpeg_audio::TYPES),
+        get_subclasses: || typetable::subclasses(mpeg_audio::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // executable, which replaces the magic rules for ELF, PE and Mach-O files
+    CheckerStruct{
+        from_u8: executable::check::from_u8,
+        from_probe: executable::check::from_probe,
+        get_supported: || typetable::supported(executable::TYPES),
+        get_subclasses: || typetable::subclasses(executable::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // database, which replaces the magic rules for SQLite
+    CheckerStruct{
+        from_u8: database::check::from_u8,
+        from_probe: database::check::from_probe,
+        get_supported: || typetable::supported(database::TYPES),
+        get_subclasses: || typetable::subclasses(database::TYPES),
+        get_aliaslist: database::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // zip, which tells ZIP-based formats apart by their member names
+    CheckerStruct{
+        from_u8: zip::check::from_u8,
+        from_probe: zip::check::from_probe,
+        get_supported: || typetable::supported(zip::TYPES),
+        get_subclasses: || typetable::subclasses(zip::TYPES),
+        get_aliaslist: FnvHashMap::default,
+        get_prefilter: None,
+        get_priorities: None
+    },
+    // compressed
+    #[cfg(feature = "decompress")]
+    CheckerStruct{
+        from_u8: compressed::check::from_u8,
+        from_probe: compressed::check::from_probe,
+        get_supported: compressed::init::get_supported,
+        get_subclasses: compressed::init::get_subclasses,
+        get_aliaslist: compressed::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: None
     },
     // basetype
     CheckerStruct{
         from_u8: basetype::check::from_u8,
-        from_filepath: basetype::check::from_filepath,
+        from_probe: basetype::check::from_probe,
         get_supported: basetype::init::get_supported,
         get_subclasses: basetype::init::get_subclasses,
-        get_aliaslist: basetype::init::get_aliaslist
+        get_aliaslist: basetype::init::get_aliaslist,
+        get_prefilter: None,
+        get_priorities: None
     }
 ];
 
@@ -133,16 +315,62 @@ pub struct TypeStruct {
 lazy_static! {
     /// The TypeStruct autogenerated at library init, and used by the library.
     pub static ref TYPE: TypeStruct = {
-        graph_init().unwrap_or( 
-            TypeStruct{
-                graph: DiGraph::new(),
-                hash: FnvHashMap::default()
-            } )
+        graph_init()
+    };
+}
+
+/// Prefilter ID of each node in the type graph, as (checker, ID),
+/// if the type's checker has a prefilter
+lazy_static! {
+    static ref NODE_PREFILTER: Vec<Option<(usize, usize)>> = {
+        TYPE.graph.node_indices().map(|node| {
+            let mimetype = &TYPE.graph[node];
+            let checker = *CHECKER_SUPPORT.get(mimetype)?;
+            let prefilter = (CHECKERS[checker].get_prefilter)?();
+            Some( (checker, prefilter.id(mimetype)?) )
+        }).collect()
+    };
+}
+
+/// Nodes of the types in `TYPEORDER`, so the walker doesn't compare MIME strings
+lazy_static! {
+    static ref TYPEORDER_NODES: Vec<NodeIndex> = {
+        TYPEORDER.iter().filter_map(|x| TYPE.hash.get(x).copied()).collect()
+    };
+}
+
+/// Magic priority of each type, the highest any checker gives it
+lazy_static! {
+    static ref PRIORITIES: FnvHashMap<MIME, u32> = {
+        let mut out = FnvHashMap::<MIME, u32>::default();
+        for f in CHECKERS.iter().filter_map(|x| x.get_priorities) {
+            for (mimetype, priority) in f() {
+                let x = out.entry(mimetype).or_insert(priority);
+                *x = std::cmp::max(*x, priority);
+            }
+        }
+        out
+    };
+}
+
+/// Children of each node in the type graph, in the order the walker tries them:
+/// the types in `TYPEORDER` first, then the others by descending magic priority,
+/// so the highest priority wins when siblings match the same file.
+lazy_static! {
+    static ref NODE_CHILDREN: Vec<Vec<NodeIndex>> = {
+        let priority = |x: &NodeIndex| {
+            PRIORITIES.get(&TYPE.graph[*x]).copied().unwrap_or(fdo_magic::xml::DEFAULT_PRIORITY)
+        };
+        TYPE.graph.node_indices().map(|node| {
+            let mut children: Vec<NodeIndex> = TYPE.graph.neighbors_directed(node, Outgoing).collect();
+            children.sort_by_key(|x| (!TYPEORDER_NODES.contains(x), std::cmp::Reverse(priority(x))));
+            children
+        }).collect()
     };
 }
 
 // Initialize filetype graph
-fn graph_init() -> Result<TypeStruct, std::io::Error> {
+fn graph_init() -> TypeStruct {
     
     let mut graph = DiGraph::<MIME, u32>::new();
     let mut added_mimes = FnvHashMap::<MIME, NodeIndex>::default();
@@ -254,37 +482,44 @@ fn graph_init() -> Result<TypeStruct, std::io::Error> {
     let added_mimes = added_mimes;
     //println!("{:?}", Dot::with_config(&graph, &[Config::EdgeNoLabel]));
 
-    Ok( TypeStruct{graph: graph, hash: added_mimes} )
+    TypeStruct{graph, hash: added_mimes}
+}
+
+/// Checks if a node's checker has a prefilter, and that prefilter ruled it out
+fn prefilter_rejects(node: NodeIndex, candidates: &[Option<fdo_magic::prefilter::Candidates>]) -> bool
+{
+    match NODE_PREFILTER[node.index()] {
+        Some((checker, id)) => match candidates.get(checker) {
+            Some(Some(x)) => !x.contains(id),
+            _ => false
+        },
+        None => false
+    }
 }
 
 /// Just the part of from_*_node that walks the graph
+///
+/// `candidates` holds the prefilter result of each checker, if there is one.
+/// Types ruled out by their checker's prefilter are skipped.
 fn typegraph_walker<T: Clone>(
     parentnode: NodeIndex,
     input: T,
-    matchfn: fn(MIME, T) -> bool
+    matchfn: fn(MIME, T) -> bool,
+    candidates: &[Option<fdo_magic::prefilter::Candidates>]
 ) -> Option<MIME> {
 
-    // Pull most common types towards top
-    let mut children: Vec<NodeIndex> = TYPE.graph
-        .neighbors_directed(parentnode, Outgoing)
-        .collect();
-        
-    for i in 0..children.len() {
-        let x = children[i];
-        if TYPEORDER.contains(&TYPE.graph[x]) {
-            children.remove(i);
-            children.insert(0, x);
+    // Walk graph, most common and highest priority types first
+    for &childnode in NODE_CHILDREN[parentnode.index()].iter() {
+        if prefilter_rejects(childnode, candidates) {
+            continue;
         }
-    }
 
-    // Walk graph
-    for childnode in children {
         let mimetype = TYPE.graph[childnode];
         
         let result = (matchfn)(mimetype, input.clone());
         match result {
             true => {
-                match typegraph_walker(childnode, input, matchfn) {
+                match typegraph_walker(childnode, input, matchfn, candidates) {
                     Some(foundtype) => return Some(foundtype),
                     None => return Some(mimetype),
                 }
@@ -365,7 +600,12 @@ pub fn match_u8(mimetype: MIME, bytes: &[u8]) -> bool
 /// ```
 pub fn from_u8_node(parentnode: NodeIndex, bytes: &[u8]) -> Option<MIME>
 {
-	typegraph_walker(parentnode, bytes, match_u8_noalias)
+    // Run each checker's prefilter once for the whole walk
+    let candidates: Vec<Option<fdo_magic::prefilter::Candidates>> = CHECKERS.iter()
+        .map(|x| x.get_prefilter.map(|f| f().candidates(bytes, true)))
+        .collect();
+
+	typegraph_walker(parentnode, bytes, match_u8_noalias, &candidates)
 }
 
 /// Gets the type of a file from a byte stream.
@@ -391,17 +631,36 @@ pub fn from_u8(bytes: &[u8]) -> MIME
 }
 
 /// Internal function. Checks if an alias exists, and if it does,
-/// then runs `match_u8`.
-fn match_filepath_noalias(mimetype: MIME, filepath: &Path) -> bool
+/// then runs `match_probe`.
+fn match_probe_noalias(mimetype: MIME, probe: &probe::Probe) -> bool
 {
     match CHECKER_SUPPORT.get(&mimetype) {
         None => {false},
         Some(y) => {
-            (CHECKERS[*y].from_filepath)(filepath, mimetype)
+            (CHECKERS[*y].from_probe)(probe, mimetype)
         }
     }
 }
 
+/// Checks if a probed file or bytestream matches the given MIME type.
+///
+/// Works like `match_filepath`, but anything the checkers read or parse is
+/// kept in the probe for later calls.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+/// use tree_magic::probe::Probe;
+///
+/// let probe = Probe::from_filepath(Path::new("tests/image/gif"));
+/// assert!(tree_magic::match_probe("image/gif".parse().unwrap(), &probe));
+/// assert!(!tree_magic::match_probe("image/png".parse().unwrap(), &probe));
+/// ```
+pub fn match_probe(mimetype: MIME, probe: &probe::Probe) -> bool
+{
+    match_probe_noalias(get_alias(mimetype), probe)
+}
+
 /// Check if the given filepath matches the given MIME type.
 ///
 /// Returns true or false if it matches or not, or an Error if the file could
@@ -421,9 +680,170 @@ fn match_filepath_noalias(mimetype: MIME, filepath: &Path) -> bool
 pub fn match_filepath(mimetype: MIME, filepath: &Path) -> bool 
 {
     // Transform alias if needed
-    match_filepath_noalias(get_alias(mimetype), filepath)
+    match_probe(mimetype, &probe::Probe::from_filepath(filepath))
+}
+
+/// Gets the type of a probed file or bytestream, starting at a certain node
+/// in the type graph.
+///
+/// Every checker on the walk shares the probe, so the file is read and parsed
+/// once however many types are tried.
+/// Returns MIME as string wrapped in Some if a type matches, or
+/// None if the file is not found or cannot be opened.
+///
+/// # Panics
+/// Will panic if the given node is not found in the graph.
+/// As the graph is immutable, this should not happen if the node index comes from
+/// `TYPE.hash`.
+pub fn from_probe_node(parentnode: NodeIndex, probe: &probe::Probe) -> Option<MIME>
+{
+    // Only regular files and bytestreams have contents to prefilter
+    if !match_probe_noalias(mime::APPLICATION_OCTET_STREAM, probe) {
+        // Check the other base types
+        return typegraph_walker(parentnode, probe, match_probe_noalias, &[]);
+    }
+
+    // A file that can't be read has no type
+    probe.header()?;
+    typegraph_walker(parentnode, probe, match_probe_noalias, &probe_candidates(probe))
+}
+
+/// Runs each checker's prefilter on the probe's header once,
+/// or returns no results if there is no header
+fn probe_candidates(probe: &probe::Probe) -> Vec<Option<fdo_magic::prefilter::Candidates>>
+{
+    let header = match probe.header() {
+        Some(x) => x,
+        None => return Vec::new()
+    };
+    let complete = probe.header_is_complete();
+    CHECKERS.iter()
+        .map(|x| x.get_prefilter.map(|f| f().candidates(header, complete)))
+        .collect()
 }
 
+/// Checks a node of the type graph against a probe, as walking down to it
+/// would: a type only matches if one of its parents does too. Every result
+/// is remembered, so ancestors shared by a set of types are only checked once.
+fn match_node_memo(
+    node: NodeIndex,
+    probe: &probe::Probe,
+    candidates: &[Option<fdo_magic::prefilter::Candidates>],
+    checked: &mut FnvHashMap<NodeIndex, bool>
+) -> bool {
+    if let Some(&result) = checked.get(&node) {
+        return result;
+    }
+    // Guards against a loop in the subclass tree
+    checked.insert(node, false);
+
+    // The walk starts below the root, so the root is taken as matched
+    let mut parents = TYPE.graph.neighbors_directed(node, Incoming).peekable();
+    let result = parents.peek().is_none() || (
+        parents.any(|x| match_node_memo(x, probe, candidates, checked)) &&
+        !prefilter_rejects(node, candidates) &&
+        match_probe_noalias(TYPE.graph[node].clone(), probe)
+    );
+    checked.insert(node, result);
+    result
+}
+
+fn is_root(node: NodeIndex) -> bool {
+    TYPE.graph.neighbors_directed(node, Incoming).next().is_none()
+}
+
+/// Checks one of a set of types against a probe, with its ancestors.
+/// Aliases resolve to the node of the real type, so they are checked once too.
+fn match_probe_memo(
+    mimetype: &MIME,
+    probe: &probe::Probe,
+    candidates: &[Option<fdo_magic::prefilter::Candidates>],
+    checked: &mut FnvHashMap<NodeIndex, bool>
+) -> bool {
+    let mimetype = get_alias(mimetype.clone());
+    match TYPE.hash.get(&mimetype) {
+        // Nothing is above the root to walk down from
+        Some(&node) if is_root(node) => match_probe_noalias(mimetype, probe),
+        Some(&node) => match_node_memo(node, probe, candidates, checked),
+        None => match_probe_noalias(mimetype, probe)
+    }
+}
+
+/// Finds the first of a set of MIME types that matches a probed file or bytestream.
+///
+/// A type matches if it and a chain of its ancestors do, as when walking the
+/// type graph. The input is read once, the prefilter runs once for the whole
+/// set, and every type, alias and shared ancestor is only checked once.
+/// Returns the matching type as it was given, or None.
+///
+/// # Examples
+/// ```rust
+/// use tree_magic::probe::Probe;
+///
+/// let allowed: Vec<mime::Mime> = ["image/png", "image/gif", "application/pdf"]
+///     .iter().map(|x| x.parse().unwrap()).collect();
+///
+/// let input: &[u8] = include_bytes!("../tests/image/gif");
+/// let result = tree_magic::match_any(&allowed, &Probe::from_u8(input));
+/// assert_eq!(result, Some("image/gif".parse().unwrap()));
+/// ```
+pub fn match_any(mimetypes: &[MIME], probe: &probe::Probe) -> Option<MIME>
+{
+    let candidates = probe_candidates(probe);
+    let mut checked = FnvHashMap::<NodeIndex, bool>::default();
+    mimetypes.iter()
+        .find(|x| match_probe_memo(x, probe, &candidates, &mut checked))
+        .cloned()
+}
+
+/// Finds every one of a set of MIME types that matches a probed file or bytestream.
+///
+/// Works like `match_any`, but returns all the matching types, in the order given.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+/// use tree_magic::probe::Probe;
+///
+/// let types: Vec<mime::Mime> = ["application/zip", "text/plain", "application/x-zip-compressed"]
+///     .iter().map(|x| x.parse().unwrap()).collect();
+///
+/// let probe = Probe::from_filepath(Path::new("tests/application/zip"));
+/// let result = tree_magic::match_all(&types, &probe);
+/// assert_eq!(result, vec![types[0].clone(), types[2].clone()]);
+/// ```
+pub fn match_all(mimetypes: &[MIME], probe: &probe::Probe) -> Vec<MIME>
+{
+    let candidates = probe_candidates(probe);
+    let mut checked = FnvHashMap::<NodeIndex, bool>::default();
+    mimetypes.iter()
+        .filter(|x| match_probe_memo(x, probe, &candidates, &mut checked))
+        .cloned()
+        .collect()
+}
+
+/// Gets the type of a probed file or bytestream.
+///
+/// Returns MIME as string wrapped in Some if a type matches, or
+/// None if the file is not found or cannot be opened.
+///
+/// # Examples
+/// ```rust
+/// use tree_magic::probe::Probe;
+///
+/// let input: &[u8] = include_bytes!("../tests/image/gif");
+/// let result = tree_magic::from_probe(&Probe::from_u8(input));
+/// assert_eq!(result, Some("image/gif".parse().unwrap()));
+/// ```
+pub fn from_probe(probe: &probe::Probe) -> Option<MIME> {
+
+    let node = match TYPE.graph.externals(Incoming).next() {
+        Some(foundnode) => foundnode,
+        None => panic!("No filetype definitions are loaded.")
+    };
+
+    from_probe_node(node, probe)
+}
 
 /// Gets the type of a file from a filepath, starting at a certain node
 /// in the type graph.
@@ -456,24 +876,7 @@ pub fn match_filepath(mimetype: MIME, filepath: &Path) -> bool
 /// ```
 pub fn from_filepath_node(parentnode: NodeIndex, filepath: &Path) -> Option<MIME> 
 {
-    // We're actually just going to thunk this down to a u8
-    // unless we're checking via basetype for speed reasons.
-    
-    // Ensure it's at least a application/octet-stream
-    if !match_filepath(mime::APPLICATION_OCTET_STREAM, filepath){
-        // Check the other base types
-        return typegraph_walker(parentnode, filepath, match_filepath_noalias);
-    }
-    
-    // Load the first 2K of file and parse as u8
-    // for batch processing like this
-
-    let b = match read_bytes(filepath, 2048) {
-        Ok(x) => x,
-        Err(_) => return None
-    };
-    
-    from_u8_node(parentnode, b.as_slice())
+    from_probe_node(parentnode, &probe::Probe::from_filepath(filepath))
 }
 
 /// Gets the type of a file from a filepath.
@@ -503,6 +906,66 @@ pub fn from_filepath(filepath: &Path) -> Option<MIME> {
     from_filepath_node(node, filepath)
 }
 
+/// Gets the type of a file from its name alone, without reading it.
+///
+/// Follows the shared-mime-info glob rules: literal names first, then the
+/// longest suffix, so `archive.tar.gz` is a compressed tarball and not just
+/// gzip. Globs are matched without case unless they say otherwise.
+/// The result is resolved through the same aliases as content detection.
+/// The globs come from the same database as the magic rules: the system's
+/// `mime.cache` files with `sys_mime_cache`, the installed packages over the
+/// bundled ones with `sys_fdo_magic`, and the bundled ones otherwise.
+/// Returns None if no glob matches.
+///
+/// # Examples
+/// ```rust
+/// let result = tree_magic::from_filename("backups/Archive.TAR.GZ");
+/// assert_eq!(result, Some("application/x-compressed-tar".parse().unwrap()));
+/// ```
+pub fn from_filename(filename: &str) -> Option<MIME> {
+    #[cfg(feature = "sys_mime_cache")]
+    let found = fdo_magic::sys_cache::glob::from_filename(filename);
+    #[cfg(all(feature = "sys_fdo_magic", not(feature = "sys_mime_cache")))]
+    let found = fdo_magic::sys::GLOBS.from_filename(filename);
+    #[cfg(not(any(feature = "sys_fdo_magic", feature = "sys_mime_cache")))]
+    let found = metadata::BUILTIN_GLOBS.from_filename(filename);
+
+    found.map(get_alias)
+}
+
+/// Gets the type that uses a file extension, without the dot.
+///
+/// Works like `from_filename` on a name with that extension.
+///
+/// # Examples
+/// ```rust
+/// let result = tree_magic::from_extension("docx");
+/// assert_eq!(
+///     result,
+///     Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document".parse().unwrap())
+/// );
+/// ```
+pub fn from_extension(extension: &str) -> Option<MIME> {
+    from_filename(&format!(".{}", extension))
+}
+
+/// Gets the problems found while loading the system's XML packages
+///
+/// Only available with the `sys_fdo_magic` feature. Package files that can't
+/// be read or parsed are skipped, as are malformed `<mime-type>` and `<match>`
+/// elements in the others. Each skip is listed here, starting with the file's path.
+///
+/// # Examples
+/// ```rust
+/// for error in tree_magic::package_errors() {
+///     eprintln!("Skipped: {}", error);
+/// }
+/// ```
+#[cfg(feature = "sys_fdo_magic")]
+pub fn package_errors() -> &'static [String] {
+    &fdo_magic::sys::PACKAGES.errors
+}
+
 /// Determines if a MIME is an alias of another MIME
 ///
 /// If this returns true, that means the two MIME types are equivalent.
@@ -520,14 +983,3 @@ pub fn is_alias(mime1: MIME, mime2: MIME) -> bool {
     
     return x == mime2 || y == mime1;
 }
-
-/// Reads the given number of bytes from a file
-fn read_bytes(filepath: &Path, bytecount: usize) -> Result<Vec<u8>, std::io::Error> {
-    use std::io::prelude::*;
-    use std::fs::File;
-
-    let mut b = Vec::<u8>::with_capacity(bytecount);
-    let f = File::open(filepath)?;
-    f.take(bytecount as u64).read_to_end(&mut b)?;
-    Ok(b)
-}
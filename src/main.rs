@@ -4,6 +4,8 @@
 //! - Find MIME of a file
 //! - Match file against a set of MIMEs (significantly faster)
 //! - Search a folder recursively
+//! - Check that file extensions match file contents
+//! - Rename files to the extension of their type
 //!
 //! # Usage
 //!
@@ -11,13 +13,20 @@
 //! tmagic [FLAGS] [OPTIONS] <file>...
 //!
 //! FLAGS:
+//!         --check-extensions Report files whose extension doesn't match their contents.
+//!                            Exits with status 1 if any do.
+//!         --dry-run          With --rename, only print what would be renamed
 //!     -h, --help             Prints help information
 //!     -r, --recursive        Search directories recursively
+//!         --rename           Give files without a fitting extension the one of their type.
+//!                            Files only known to be application/octet-stream are skipped.
 //!         --ugly             Print results as they come in, at expense of tab alignment
 //!     -V, --version          Prints version information
 //!
 //! OPTIONS:
-//!     -m, --match=<match>    Print only files that match given MIMEs, seperated by commas
+//!     -m, --match=<match>    Print only files that match given MIMEs, seperated by commas.
+//!                            A type only matches if its parent types do too. Each file
+//!                            is printed with the first type it matches, in the order given.
 //!
 //! ARGS:
 //!     <file>...              List of files or folders to check. Wildcards supported. 
@@ -25,14 +34,12 @@
 use tabwriter::TabWriter;
 use std::io::prelude::*;
 use std::sync::mpsc;
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
+use std::collections::HashSet;
 use walkdir::{WalkDir};
 use scoped_threadpool::Pool;
 
-macro_rules! convmime {
-    ($x:expr) => {$x.to_string()}
-}
-
 fn main() {
 
     use clap::{Arg, App};
@@ -57,7 +64,24 @@ fn main() {
             .use_delimiter(true)
             .takes_value(true)
             .require_equals(true)
-            .help("Print only files that match given MIMEs, seperated by commas")
+            .help("Print only files that match given MIMEs, seperated by commas. \
+                   A type only matches if its parent types do too. Each file \
+                   is printed with the first type it matches, in the order given.")
+        )
+        .arg(Arg::with_name("check-extensions")
+            .long("check-extensions")
+            .conflicts_with("match")
+            .help("Report files whose extension doesn't match their contents")
+        )
+        .arg(Arg::with_name("rename")
+            .long("rename")
+            .conflicts_with_all(&["match", "check-extensions"])
+            .help("Give files without a fitting extension the one of their type")
+        )
+        .arg(Arg::with_name("dry-run")
+            .long("dry-run")
+            .requires("rename")
+            .help("With --rename, only print what would be renamed")
         )
         .arg(Arg::with_name("ugly")
             .long("ugly")
@@ -71,9 +95,15 @@ fn main() {
         .collect();
     let is_ugly = args.is_present("ugly");
     let is_recursive = args.is_present("recursive");
-    let check_against: Vec<String> = match args.values_of("match") {
-        Some(y) => {y.map(|x| x.to_string()).collect()}
-        None => Vec::<String>::new()
+    let is_checking_extensions = args.is_present("check-extensions");
+    let is_renaming = args.is_present("rename");
+    let is_dry_run = args.is_present("dry-run");
+    let check_against: Vec<mime::Mime> = match args.values_of("match") {
+        Some(y) => {y.map(|x| x.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid MIME type: {}", x);
+            std::process::exit(1);
+        })).collect()}
+        None => Vec::<mime::Mime>::new()
     };
     
     let mut tw = TabWriter::new(vec![]);
@@ -91,8 +121,77 @@ fn main() {
     let files = files;
     
     let mut pool = Pool::new(num_cpus::get() as u32);
+    let found_mismatch = AtomicBool::new(false);
+    // Rename files, one at a time so new names can't collide
+    if is_renaming {
+        let (rename_tx, rename_rx) = mpsc::channel();
+        pool.scoped(|scope| {
+            for file in files {
+                let rename_tx = rename_tx.clone();
+                scope.execute(move || {
+                    if !file.is_file() { return; }
+                    if let Some(x) = tree_magic::extension::suggest_name(file.as_path()) {
+                        rename_tx.send( (file, x) ).unwrap_or_default();
+                    }
+                });
+            }
+        });
+        drop(rename_tx);
+
+        let mut renames: Vec<(PathBuf, PathBuf)> = rename_rx.iter().collect();
+        renames.sort();
+        renames.dedup();
+        let mut taken = HashSet::<PathBuf>::new();
+        for (file, target) in renames {
+            let target = free_path(&target, &taken);
+            taken.insert(target.clone());
+
+            let result = match is_dry_run {
+                true => format!("{:?}:\t{:?}", file, target),
+                false => match std::fs::rename(&file, &target) {
+                    Ok(_) => format!("{:?}:\t{:?}", file, target),
+                    Err(e) => format!("{:?}:\tnot renamed, {}", file, e)
+                }
+            };
+            tx.send(result + "\n").unwrap_or_default();
+        }
+    // Compare extensions with contents
+    } else if is_checking_extensions {
+        pool.scoped(|scope| {
+            for file in files {
+                let tx = tx.clone();
+                let found_mismatch = &found_mismatch;
+
+                scope.execute(move || {
+                    // Directories and special files don't need an extension
+                    if !file.is_file() { return; }
+
+                    use tree_magic::extension::Verdict;
+                    let check = match tree_magic::extension::check_extension(file.as_path()) {
+                        Some(x) => x,
+                        None => return
+                    };
+                    let verdict = match check.verdict {
+                        Verdict::Consistent => "ok".to_string(),
+                        Verdict::UnknownExtension => "unknown extension".to_string(),
+                        Verdict::Mismatch(expected) => {
+                            found_mismatch.store(true, Ordering::SeqCst);
+                            let expected: Vec<String> = expected.iter().map(|x| x.to_string()).collect();
+                            format!("MISMATCH, extension expects {}", expected.join(" or "))
+                        }
+                    };
+
+                    let result = format!("{:?}:\t{:?}\t{}", file, check.detected.to_string(), verdict);
+                    if is_ugly {
+                        println!("{}", result);
+                    } else {
+                        tx.send(result + "\n").unwrap_or_default();
+                    }
+                });
+            }
+        });
     // Acquire results for non-match
-    if check_against.is_empty(){
+    } else if check_against.is_empty(){
         pool.scoped(|scope| {
             for file in files {
                 //let file = file.as_str();
@@ -116,22 +215,16 @@ fn main() {
                 //let file = file.as_str();
                 //let file = Path::new(file);
                 let tx = tx.clone();
-                let check_against = check_against.clone();
+                let check_against = &check_against;
                 
                 scope.execute(move || {
-                    let mut result: Option<String> = None;
-                
-                    for mime in check_against {
-                        let out = tree_magic::match_filepath(mime.as_str(), file.as_path());
-                        if out {
-                            result = Some(mime);
-                            break;
-                        }
-                    }
-                    
-                    if result.is_none() { return; }
+                    // Read the file once for the whole list
+                    let probe = tree_magic::probe::Probe::from_filepath(file.as_path());
+                    let result = match tree_magic::match_any(check_against, &probe) {
+                        Some(x) => x.to_string(),
+                        None => return
+                    };
                     
-                    let result = result.unwrap();
                     let result = format!("{:?}:\t{:?}", file, result);
                     if is_ugly {
                         println!("{}", result);
@@ -157,5 +250,25 @@ fn main() {
         let out = String::from_utf8(tw.into_inner().unwrap()).unwrap_or("".to_string());
         println!("{}", out);
     }
+
+    if found_mismatch.load(Ordering::SeqCst) {
+        std::process::exit(1);
+    }
     
 }
+
+/// Finds a name like `target` that is neither on disk nor taken by an earlier rename,
+/// by adding `-1`, `-2`... before the extension
+fn free_path(target: &Path, taken: &HashSet<PathBuf>) -> PathBuf {
+    let is_free = |x: &Path| !taken.contains(x) && std::fs::symlink_metadata(x).is_err();
+    if is_free(target) {
+        return target.to_path_buf();
+    }
+
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = target.extension().unwrap_or_default().to_string_lossy().to_string();
+    (1..)
+        .map(|i| target.with_file_name(format!("{}-{}.{}", stem, i, ext)))
+        .find(|x| is_free(x))
+        .unwrap()
+}
@@ -0,0 +1,113 @@
+//! Look up types by file name, following the shared-mime-info glob rules
+//!
+//! Literal names (`Makefile`) are tried first, then suffixes (`*.tar.gz`),
+//! then every other pattern. Within the first of those steps that matches,
+//! the highest glob weight wins, then the longest pattern, so `archive.tar.gz`
+//! is a compressed tarball rather than a plain gzip file. Case-sensitive globs
+//! must match exactly, and the rest ignore case.
+
+use fnv::FnvHashMap;
+use crate::MIME;
+use super::{Glob, MimeInfo};
+
+/// A glob that is a literal name or a suffix
+struct Entry {
+	mimetype: MIME,
+	/// The name, or the suffix without the leading `*`, as written
+	text: String,
+	weight: u32,
+	case_sensitive: bool
+}
+
+/// Index over the globs of a `MimeInfo`
+pub struct GlobIndex {
+	/// Literal names, by lowercased name
+	literal: FnvHashMap<String, Vec<Entry>>,
+	/// Suffix globs, by lowercased suffix
+	suffix: FnvHashMap<String, Vec<Entry>>,
+	/// Globs with other wildcards
+	other: Vec<(MIME, Glob)>
+}
+
+/// Checks if a pattern has any wildcards
+fn has_wildcards(pattern: &str) -> bool {
+	pattern.contains(&['*', '?', '['][..])
+}
+
+/// Picks the best entry: highest weight, then longest, then first by type name
+fn best_entry<'a, I>(entries: I) -> Option<&'a Entry>
+	where I: IntoIterator<Item = &'a Entry>
+{
+	entries.into_iter()
+		.min_by(|a, b| b.weight.cmp(&a.weight)
+			.then_with(|| b.text.chars().count().cmp(&a.text.chars().count()))
+			.then_with(|| a.mimetype.essence_str().cmp(b.mimetype.essence_str())))
+}
+
+impl GlobIndex {
+	/// Builds the index from every glob in `info`
+	pub fn new(info: &MimeInfo) -> GlobIndex {
+		let mut literal = FnvHashMap::<String, Vec<Entry>>::default();
+		let mut suffix = FnvHashMap::<String, Vec<Entry>>::default();
+		let mut other = Vec::<(MIME, Glob)>::new();
+
+		for (mimetype, typeinfo) in info.types.iter() {
+			for glob in typeinfo.globs.iter() {
+				let (map, text) = if !has_wildcards(&glob.pattern) {
+					(&mut literal, glob.pattern.as_str())
+				} else if glob.pattern.starts_with('*') && !has_wildcards(&glob.pattern[1..]) {
+					(&mut suffix, &glob.pattern[1..])
+				} else {
+					other.push((mimetype.clone(), glob.clone()));
+					continue;
+				};
+				map.entry(text.to_lowercase()).or_default().push(Entry{
+					mimetype: mimetype.clone(),
+					text: text.to_string(),
+					weight: glob.weight,
+					case_sensitive: glob.case_sensitive
+				});
+			}
+		}
+
+		GlobIndex{literal, suffix, other}
+	}
+
+	/// Finds the type of a file from its name alone
+	///
+	/// Only the last component of a path is looked at.
+	/// Returns None if no glob matches.
+	pub fn from_filename(&self, filename: &str) -> Option<MIME> {
+		let name = match filename.rfind('/') {
+			Some(i) => &filename[i + 1..],
+			None => filename
+		};
+		if name.is_empty() {
+			return None;
+		}
+
+		if let Some(entries) = self.literal.get(&name.to_lowercase()) {
+			let entries = entries.iter().filter(|x| !x.case_sensitive || x.text == name);
+			if let Some(x) = best_entry(entries) {
+				return Some(x.mimetype.clone());
+			}
+		}
+
+		// Look up every suffix of the name, so longer ones get a chance to win
+		let suffixes = name.char_indices()
+			.filter_map(|(i, _)| self.suffix.get(&name[i..].to_lowercase()).map(|x| (i, x)))
+			.flat_map(|(i, entries)| {
+				entries.iter().filter(move |x| !x.case_sensitive || x.text == name[i..])
+			});
+		if let Some(x) = best_entry(suffixes) {
+			return Some(x.mimetype.clone());
+		}
+
+		self.other.iter()
+			.filter(|x| x.1.matches(name))
+			.min_by(|a, b| b.1.weight.cmp(&a.1.weight)
+				.then_with(|| b.1.pattern.len().cmp(&a.1.pattern.len()))
+				.then_with(|| a.0.essence_str().cmp(b.0.essence_str())))
+			.map(|x| x.0.clone())
+	}
+}
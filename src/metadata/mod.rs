@@ -0,0 +1,538 @@
+//! Human-readable information about MIME types
+//!
+//! Reads the `<comment>`, `<acronym>`, `<icon>`, `<generic-icon>`, `<glob>`,
+//! `<sub-class-of>` and `<alias>` elements from shared-mime-info package files
+//! (`/usr/share/mime/packages/*.xml`, the format of `freedesktop.org.xml`).
+//!
+//! A copy of `freedesktop.org.xml` is bundled with the crate and backs the
+//! free functions in this module, along with the few types `tree_magic`
+//! detects that it doesn't define. The copy only carries the untranslated
+//! comments, so load the system packages with `MimeInfo::from_dir` if you
+//! need localised descriptions.
+
+use std::path::Path;
+use fnv::{FnvHashMap, FnvHashSet};
+use crate::MIME;
+
+pub mod package;
+pub mod globs;
+
+/// Default glob weight, as given in the shared-mime-info spec
+pub const DEFAULT_GLOB_WEIGHT: u32 = 50;
+
+/// A filename pattern associated with a MIME type
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+	pub pattern: String,
+	pub weight: u32,
+	pub case_sensitive: bool
+}
+
+impl Glob {
+	/// Returns the extension if this is a plain `*.ext` pattern
+	pub fn extension(&self) -> Option<&str> {
+		if !self.pattern.starts_with("*.") {
+			return None;
+		}
+		let ext = &self.pattern[2..];
+		if ext.is_empty() || ext.contains(&['*', '?', '['][..]) {
+			return None;
+		}
+		Some(ext)
+	}
+
+	/// Checks if a file name (not a full path) matches this pattern
+	pub fn matches(&self, filename: &str) -> bool {
+		if self.case_sensitive {
+			fnmatch(&self.pattern, filename)
+		} else {
+			fnmatch(&self.pattern.to_lowercase(), &filename.to_lowercase())
+		}
+	}
+}
+
+/// Matches a name against a shell-style pattern (`*`, `?` and `[...]`)
+pub fn fnmatch(pattern: &str, name: &str) -> bool {
+	let p: Vec<char> = pattern.chars().collect();
+	let n: Vec<char> = name.chars().collect();
+
+	// Returns the length of the bracket expression at p[i..] if it matches c
+	fn bracket(p: &[char], c: char) -> Option<(bool, usize)> {
+		let mut i = 1;
+		let negate = i < p.len() && (p[i] == '!' || p[i] == '^');
+		if negate {
+			i += 1;
+		}
+		let mut found = false;
+		let mut first = true;
+		while i < p.len() && (first || p[i] != ']') {
+			first = false;
+			if i + 2 < p.len() && p[i + 1] == '-' && p[i + 2] != ']' {
+				found |= p[i] <= c && c <= p[i + 2];
+				i += 3;
+			} else {
+				found |= p[i] == c;
+				i += 1;
+			}
+		}
+		if i >= p.len() {
+			return None;
+		}
+		Some((found != negate, i + 1))
+	}
+
+	let (mut pi, mut ni) = (0, 0);
+	let mut backtrack: Option<(usize, usize)> = None;
+	while ni < n.len() {
+		let step = match p.get(pi) {
+			Some('*') => {
+				backtrack = Some((pi, ni));
+				pi += 1;
+				continue;
+			},
+			Some('?') => Some(1),
+			Some('[') => match bracket(&p[pi..], n[ni]) {
+				Some((true, len)) => Some(len),
+				Some((false, _)) => None,
+				// Unterminated bracket is a literal '['
+				None if n[ni] == '[' => Some(1),
+				None => None
+			},
+			Some(&c) if c == n[ni] => Some(1),
+			_ => None
+		};
+
+		match step {
+			Some(len) => {
+				pi += len;
+				ni += 1;
+			},
+			None => match backtrack {
+				// Let the last '*' eat one more character
+				Some((bp, bn)) => {
+					backtrack = Some((bp, bn + 1));
+					pi = bp + 1;
+					ni = bn + 1;
+				},
+				None => return false
+			}
+		}
+	}
+
+	p[pi..].iter().all(|&x| x == '*')
+}
+
+/// Everything a package file says about a single MIME type
+#[derive(Debug, Clone, Default)]
+pub struct TypeInfo {
+	/// Comments keyed by their `xml:lang`. The untranslated comment is under "".
+	pub comments: FnvHashMap<String, String>,
+	pub acronym: Option<String>,
+	pub expanded_acronym: Option<String>,
+	pub icon: Option<String>,
+	pub generic_icon: Option<String>,
+	pub globs: Vec<Glob>,
+	/// Types this one is a subclass of
+	pub parents: Vec<MIME>
+}
+
+/// A collection of type descriptions loaded from one or more package files
+#[derive(Debug, Clone, Default)]
+pub struct MimeInfo {
+	pub types: FnvHashMap<MIME, TypeInfo>,
+	pub aliases: FnvHashMap<MIME, MIME>
+}
+
+impl MimeInfo {
+	/// Creates an empty collection
+	pub fn new() -> MimeInfo {
+		MimeInfo::default()
+	}
+
+	/// Parses a single package file given as bytes
+	pub fn from_u8(b: &[u8]) -> Result<MimeInfo, String> {
+		package::from_u8(b)
+	}
+
+	/// Parses a single package file
+	pub fn from_filepath(filepath: &Path) -> Result<MimeInfo, String> {
+		package::from_filepath(filepath)
+	}
+
+	/// Parses every `*.xml` file in a `packages` directory
+	///
+	/// Files are read in name order, so later packages take precedence
+	/// over earlier ones the same way `update-mime-database` treats them.
+	pub fn from_dir(dirpath: &Path) -> Result<MimeInfo, String> {
+		let mut paths: Vec<_> = std::fs::read_dir(dirpath)
+			.map_err(|e| e.to_string())?
+			.filter_map(|e| e.ok())
+			.map(|e| e.path())
+			.filter(|p| p.extension().is_some_and(|x| x == "xml"))
+			.collect();
+		paths.sort();
+
+		let mut out = MimeInfo::new();
+		for path in paths {
+			out.merge(package::from_filepath(&path)?);
+		}
+		Ok(out)
+	}
+
+	/// Adds the contents of another collection to this one
+	///
+	/// Comments and globs are combined. Single-valued fields such as the
+	/// icon are replaced if `other` sets them.
+	pub fn merge(&mut self, other: MimeInfo) {
+		for (mimetype, info) in other.types {
+			let entry = self.types.entry(mimetype).or_default();
+			entry.comments.extend(info.comments);
+			if info.acronym.is_some() {
+				entry.acronym = info.acronym;
+			}
+			if info.expanded_acronym.is_some() {
+				entry.expanded_acronym = info.expanded_acronym;
+			}
+			if info.icon.is_some() {
+				entry.icon = info.icon;
+			}
+			if info.generic_icon.is_some() {
+				entry.generic_icon = info.generic_icon;
+			}
+			for glob in info.globs {
+				if !entry.globs.contains(&glob) {
+					entry.globs.push(glob);
+				}
+			}
+			for parent in info.parents {
+				if !entry.parents.contains(&parent) {
+					entry.parents.push(parent);
+				}
+			}
+		}
+		self.aliases.extend(other.aliases);
+	}
+
+	/// Transforms an alias into its real type, if this collection knows it
+	pub fn get_alias(&self, mimetype: MIME) -> MIME {
+		match self.aliases.get(&mimetype) {
+			Some(x) => x.clone(),
+			None => mimetype
+		}
+	}
+
+	/// Gets the information for a type, resolving aliases
+	pub fn get(&self, mimetype: MIME) -> Option<&TypeInfo> {
+		self.types.get(&self.get_alias(mimetype))
+	}
+
+	/// Gets the description of a type in the given locale
+	///
+	/// Locales are given the POSIX way (ex: `pt_BR.UTF-8`) and fall back
+	/// through `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER`, `lang`,
+	/// and finally the untranslated comment.
+	pub fn description(&self, mimetype: MIME, locale: Option<&str>) -> Option<String> {
+		let info = self.get(mimetype)?;
+		for lang in locale_candidates(locale.unwrap_or("")) {
+			if let Some(x) = info.comments.get(&lang) {
+				return Some(x.clone());
+			}
+		}
+		info.comments.get("").cloned()
+	}
+
+	/// Gets the icon name of a type
+	///
+	/// If the package does not give one, this is the MIME with `/`
+	/// replaced by `-`, as described in the shared-mime-info spec.
+	pub fn icon_name(&self, mimetype: MIME) -> Option<String> {
+		let mimetype = self.get_alias(mimetype);
+		let info = self.types.get(&mimetype)?;
+		match info.icon {
+			Some(ref x) => Some(x.clone()),
+			None => Some(mimetype.essence_str().replace('/', "-"))
+		}
+	}
+
+	/// Gets the generic icon name of a type
+	///
+	/// If the package does not give one, this is `<media>-x-generic`.
+	pub fn generic_icon_name(&self, mimetype: MIME) -> Option<String> {
+		let mimetype = self.get_alias(mimetype);
+		let info = self.types.get(&mimetype)?;
+		match info.generic_icon {
+			Some(ref x) => Some(x.clone()),
+			None => Some(format!("{}-x-generic", mimetype.type_()))
+		}
+	}
+
+	/// Gets the file extensions of a type, highest glob weight first
+	///
+	/// Only plain `*.ext` globs are considered. Extensions do not include the dot.
+	/// A type without extensions of its own gets those of its nearest
+	/// ancestors, so a `text/*` type falls back to `txt`.
+	pub fn extensions(&self, mimetype: MIME) -> Vec<String> {
+		let mut level = vec![self.get_alias(mimetype)];
+		let mut seen = FnvHashSet::<MIME>::default();
+
+		while !level.is_empty() {
+			let mut out = Vec::<String>::new();
+			let mut next = Vec::<MIME>::new();
+			for mimetype in level {
+				if !seen.insert(mimetype.clone()) {
+					continue;
+				}
+				for ext in self.own_extensions(&mimetype) {
+					if !out.contains(&ext) {
+						out.push(ext);
+					}
+				}
+				next.extend(self.parents(&mimetype));
+			}
+			if !out.is_empty() {
+				return out;
+			}
+			level = next;
+		}
+		Vec::new()
+	}
+
+	/// Gets the extensions from a type's own globs, highest weight first
+	fn own_extensions(&self, mimetype: &MIME) -> Vec<String> {
+		let info = match self.types.get(mimetype) {
+			Some(x) => x,
+			None => return Vec::new()
+		};
+
+		let mut globs: Vec<&Glob> = info.globs.iter()
+			.filter(|x| x.extension().is_some())
+			.collect();
+		// Stable sort, so equal weights keep package order
+		globs.sort_by_key(|x| std::cmp::Reverse(x.weight));
+
+		let mut out = Vec::<String>::new();
+		for glob in globs {
+			let ext = glob.extension().unwrap_or("").to_string();
+			if !out.contains(&ext) {
+				out.push(ext);
+			}
+		}
+		out
+	}
+
+	/// Gets the direct parents of a type, with `text/plain` for `text/*` types
+	///
+	/// `application/octet-stream` is left out, as it says nothing about a format.
+	fn parents(&self, mimetype: &MIME) -> Vec<MIME> {
+		let mut out: Vec<MIME> = match self.types.get(mimetype) {
+			Some(info) => info.parents.iter().map(|x| self.get_alias(x.clone())).collect(),
+			None => Vec::new()
+		};
+		if mimetype.type_() == "text" && *mimetype != "text/plain" {
+			out.push(mime::TEXT_PLAIN);
+		}
+		out
+	}
+
+	/// Gets the extension most commonly used for a type
+	pub fn preferred_extension(&self, mimetype: MIME) -> Option<String> {
+		self.extensions(mimetype).into_iter().next()
+	}
+
+	/// Gets the types with a plain `*.ext` glob for an extension, highest glob weight first
+	///
+	/// The extension does not include the dot.
+	pub fn types_with_extension(&self, extension: &str) -> Vec<MIME> {
+		let lowercase = extension.to_lowercase();
+		let mut found = Vec::<(u32, &MIME)>::new();
+		for (mimetype, info) in self.types.iter() {
+			for glob in info.globs.iter() {
+				let hit = match glob.extension() {
+					Some(x) if glob.case_sensitive => x == extension,
+					Some(x) => x.to_lowercase() == lowercase,
+					None => false
+				};
+				if hit {
+					found.push((glob.weight, mimetype));
+				}
+			}
+		}
+		// Break ties by name, as the types are stored in no particular order
+		found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.essence_str().cmp(b.1.essence_str())));
+
+		let mut out = Vec::<MIME>::new();
+		for (_, mimetype) in found {
+			if !out.contains(mimetype) {
+				out.push(mimetype.clone());
+			}
+		}
+		out
+	}
+
+	/// Checks if a type is the same as, or a subclass of, another type
+	///
+	/// Follows `<sub-class-of>` and aliases. As in the shared-mime-info spec,
+	/// every `text/*` type is also a subclass of `text/plain`, and every type
+	/// outside `inode/*` is a subclass of `application/octet-stream`.
+	pub fn is_subclass(&self, mimetype: MIME, parent: MIME) -> bool {
+		let parent = self.get_alias(parent);
+		let mut stack = vec![self.get_alias(mimetype)];
+		let mut seen = FnvHashSet::<MIME>::default();
+
+		while let Some(x) = stack.pop() {
+			if x == parent ||
+			   (parent == "text/plain" && x.type_() == "text") ||
+			   (parent == "application/octet-stream" && x.type_() != "inode")
+			{
+				return true;
+			}
+			if let Some(info) = self.types.get(&x) {
+				stack.extend(info.parents.iter().map(|p| self.get_alias(p.clone())));
+			}
+			seen.insert(x);
+			stack.retain(|p| !seen.contains(p));
+		}
+		false
+	}
+}
+
+/// Lists the `xml:lang` values to try for a POSIX locale, most specific first
+fn locale_candidates(locale: &str) -> Vec<String> {
+	// Format: lang_COUNTRY.ENCODING@MODIFIER
+	let (rest, modifier) = match locale.find('@') {
+		Some(i) => (&locale[..i], Some(&locale[i + 1..])),
+		None => (locale, None)
+	};
+	let rest = match rest.find('.') {
+		Some(i) => &rest[..i],
+		None => rest
+	};
+	let (lang, country) = match rest.find('_') {
+		Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+		None => (rest, None)
+	};
+
+	if lang.is_empty() || lang == "C" || lang == "POSIX" {
+		return Vec::new();
+	}
+
+	let mut out = Vec::<String>::new();
+	if let (Some(c), Some(m)) = (country, modifier) {
+		out.push(format!("{}_{}@{}", lang, c, m));
+	}
+	if let Some(c) = country {
+		out.push(format!("{}_{}", lang, c));
+	}
+	if let Some(m) = modifier {
+		out.push(format!("{}@{}", lang, m));
+	}
+	out.push(lang.to_string());
+	out
+}
+
+/// Bundled type information, loaded on first use
+///
+/// `tree_magic.xml` describes the types this crate detects that
+/// shared-mime-info doesn't define.
+lazy_static! {
+	pub static ref BUILTIN: MimeInfo = {
+		// The files are bundled, so failing to parse them is a bug in this crate
+		let mut info = package::from_u8(include_bytes!("builtin/freedesktop.org.xml"))
+			.expect("bundled freedesktop.org.xml should parse");
+		info.merge(package::from_u8(include_bytes!("builtin/tree_magic.xml"))
+			.expect("bundled tree_magic.xml should parse"));
+		info
+	};
+}
+
+/// Index over the bundled globs, built on first use
+lazy_static! {
+	pub static ref BUILTIN_GLOBS: globs::GlobIndex = globs::GlobIndex::new(&BUILTIN);
+}
+
+/// Gets the description of a type from the bundled database
+///
+/// # Examples
+/// ```rust
+/// let png = "image/png".parse().unwrap();
+/// let result = tree_magic::metadata::description(png, None);
+/// assert_eq!(result, Some("PNG image".to_string()));
+/// ```
+pub fn description(mimetype: MIME, locale: Option<&str>) -> Option<String> {
+	BUILTIN.description(mimetype, locale)
+}
+
+/// Gets the icon name of a type from the bundled database
+pub fn icon_name(mimetype: MIME) -> Option<String> {
+	BUILTIN.icon_name(mimetype)
+}
+
+/// Gets the generic icon name of a type from the bundled database
+pub fn generic_icon_name(mimetype: MIME) -> Option<String> {
+	BUILTIN.generic_icon_name(mimetype)
+}
+
+/// Gets the file extensions of a type from the bundled database
+///
+/// # Examples
+/// ```rust
+/// let jpeg = "image/jpeg".parse().unwrap();
+/// let result = tree_magic::metadata::extensions(jpeg);
+/// assert!(result.contains(&"jpg".to_string()));
+/// ```
+pub fn extensions(mimetype: MIME) -> Vec<String> {
+	BUILTIN.extensions(mimetype)
+}
+
+/// Gets the preferred file extension of a type from the bundled database
+pub fn preferred_extension(mimetype: MIME) -> Option<String> {
+	BUILTIN.preferred_extension(mimetype)
+}
+
+/// Gets the types that use an extension from the bundled database
+///
+/// # Examples
+/// ```rust
+/// let result = tree_magic::metadata::types_with_extension("PNG");
+/// assert_eq!(result, vec!["image/png".parse::<mime::Mime>().unwrap()]);
+/// ```
+pub fn types_with_extension(extension: &str) -> Vec<MIME> {
+	BUILTIN.types_with_extension(extension)
+}
+
+/// Checks if a type is the same as, or a subclass of, another type
+///
+/// Uses the type graph that detection walks, so it agrees with the checkers
+/// that are enabled. Types detection doesn't know, such as those matched by
+/// name only, climb the bundled database's tree until they reach one it does.
+/// Aliases are resolved first.
+pub fn is_subclass(mimetype: MIME, parent: MIME) -> bool {
+	let parent = crate::get_alias(BUILTIN.get_alias(parent));
+	let parent_node = crate::TYPE.hash.get(&parent);
+	let mut stack = vec![crate::get_alias(BUILTIN.get_alias(mimetype))];
+	let mut seen = FnvHashSet::<MIME>::default();
+
+	while let Some(x) = stack.pop() {
+		if x == parent {
+			return true;
+		}
+		match (crate::TYPE.hash.get(&x), parent_node) {
+			(Some(&node), Some(&parent_node)) => {
+				if petgraph::algo::has_path_connecting(&crate::TYPE.graph, parent_node, node, None) {
+					return true;
+				}
+			},
+			// Every ancestor of a detected type is in the graph too
+			(Some(_), None) => {},
+			(None, _) => {
+				stack.extend(BUILTIN.parents(&x).into_iter().map(crate::get_alias));
+				if x.type_() != "inode" {
+					stack.push(mime::APPLICATION_OCTET_STREAM);
+				}
+			}
+		}
+		seen.insert(x);
+		stack.retain(|p| !seen.contains(p));
+	}
+	false
+}
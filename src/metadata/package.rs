@@ -0,0 +1,93 @@
+//! Parser for shared-mime-info package files
+
+use std::path::Path;
+use crate::MIME;
+use super::{Glob, MimeInfo, TypeInfo, DEFAULT_GLOB_WEIGHT};
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Reads one `<mime-type>` element
+fn parse_mimetype(node: roxmltree::Node) -> TypeInfo {
+	let mut info = TypeInfo::default();
+
+	for child in node.children().filter(|x| x.is_element()) {
+		match child.tag_name().name() {
+			"comment" => {
+				let lang = child.attribute((XML_NS, "lang")).unwrap_or("");
+				let text = child.text().unwrap_or("").trim().to_string();
+				info.comments.insert(lang.to_string(), text);
+			},
+			"acronym" => {
+				info.acronym = child.text().map(|x| x.trim().to_string());
+			},
+			// Only keep the untranslated expanded acronym
+			"expanded-acronym" if child.attribute((XML_NS, "lang")).is_none() => {
+				info.expanded_acronym = child.text().map(|x| x.trim().to_string());
+			},
+			"icon" => {
+				info.icon = child.attribute("name").map(|x| x.to_string());
+			},
+			"generic-icon" => {
+				info.generic_icon = child.attribute("name").map(|x| x.to_string());
+			},
+			"sub-class-of" => {
+				if let Some(x) = child.attribute("type").and_then(|x| x.parse().ok()) {
+					info.parents.push(x);
+				}
+			},
+			"glob" => {
+				let pattern = match child.attribute("pattern") {
+					Some(x) => x.to_string(),
+					None => continue
+				};
+				let weight = child.attribute("weight")
+					.and_then(|x| x.parse().ok())
+					.unwrap_or(DEFAULT_GLOB_WEIGHT);
+				let case_sensitive = child.attribute("case-sensitive") == Some("true");
+				info.globs.push(Glob{pattern, weight, case_sensitive});
+			},
+			_ => {}
+		}
+	}
+
+	info
+}
+
+/// Converts a package file given as a &[u8] array into a MimeInfo
+pub fn from_u8(b: &[u8]) -> Result<MimeInfo, String> {
+	let text = std::str::from_utf8(b).map_err(|e| e.to_string())?;
+	// The packages shipped by shared-mime-info carry an inline DTD
+	let opt = roxmltree::ParsingOptions{allow_dtd: true, ..Default::default()};
+	let doc = roxmltree::Document::parse_with_options(text, opt).map_err(|e| e.to_string())?;
+
+	let root = doc.root_element();
+	if root.tag_name().name() != "mime-info" {
+		return Err(format!("Expected <mime-info>, found <{}>", root.tag_name().name()));
+	}
+
+	let mut res = MimeInfo::new();
+	for node in root.children().filter(|x| x.has_tag_name("mime-type")) {
+		let mimetype: MIME = match node.attribute("type").and_then(|x| x.parse().ok()) {
+			Some(x) => x,
+			None => continue
+		};
+
+		for alias in node.children().filter(|x| x.has_tag_name("alias")) {
+			if let Some(x) = alias.attribute("type").and_then(|x| x.parse().ok()) {
+				res.aliases.insert(x, mimetype.clone());
+			}
+		}
+
+		let mut single = MimeInfo::new();
+		single.types.insert(mimetype, parse_mimetype(node));
+		res.merge(single);
+	}
+
+	Ok(res)
+}
+
+/// Loads the given package file
+pub fn from_filepath(filepath: &Path) -> Result<MimeInfo, String> {
+	let b = std::fs::read(filepath).map_err(|e| e.to_string())?;
+	from_u8(b.as_slice())
+}
@@ -0,0 +1,12 @@
+use crate::MIME;
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	super::detect(b) == Some(mimetype.essence_str())
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	super::detect_probe(probe) == Some(mimetype.essence_str())
+}
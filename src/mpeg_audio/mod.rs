@@ -0,0 +1,240 @@
+//! Handles raw MPEG audio streams: MP3 and AAC
+//!
+//! These have no header of their own. They are a sequence of frames, each
+//! starting with a sync word and giving its own length, often after an ID3v2
+//! or APE tag that can be far longer than the magic rules look. So the tags
+//! are skipped by their sizes, and the file is only taken as MPEG audio if
+//! two frame headers are found one right after the other: an MPEG audio
+//! frame makes `audio/mpeg`, and an ADTS frame `audio/aac`.
+//!
+//! Frames are looked for right after the tags, then past up to
+//! `HEADER_LEN` bytes of junk. A stream found past junk needs three frames
+//! in a row. An ID3v2 tag that runs past the end of the input is taken as
+//! enough for `audio/mpeg`, and AAC files in ADIF are recognized by their
+//! header. A file is read where its tags end, so a long tag costs no more
+//! to skip than a short one.
+
+pub mod check;
+
+use crate::probe::{Probe, HEADER_LEN};
+use crate::typetable::TypeTable;
+
+/// Bytes after the tags searched for frames
+///
+/// Two of the longest ADTS frames fit.
+const WINDOW_LEN: usize = 16 * HEADER_LEN;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("audio/mpeg", &[]),
+	("audio/aac",  &[])
+];
+
+/// Length of an ID3v2 or APE header
+const ID3_HEADER_LEN: usize = 10;
+const APE_HEADER_LEN: usize = 32;
+
+/// Gets the length of the tags at the start of a file, which may be longer
+/// than the input
+fn tags_len(b: &[u8]) -> usize {
+	let mut pos = 0;
+	loop {
+		let rest = &b[std::cmp::min(pos, b.len())..];
+		pos += match tag_len(rest) {
+			Some(x) => x,
+			None => return pos
+		};
+	}
+}
+
+/// Gets the length of the ID3v2 or APE tag at the start of the input
+fn tag_len(b: &[u8]) -> Option<usize> {
+	id3_len(b).or_else(|| ape_len(b))
+}
+
+/// Gets the length of an ID3v2 tag, with its header and footer
+fn id3_len(b: &[u8]) -> Option<usize> {
+	let header = b.get(..ID3_HEADER_LEN).filter(|x| x.starts_with(b"ID3"))?;
+	// The size is a syncsafe integer: 7 bits to a byte
+	let size = header[6..10].iter().try_fold(0usize, |n, &x| match x < 0x80 {
+		true => Some((n << 7) | x as usize),
+		false => None
+	})?;
+	let footer = match header[5] & 0x10 {
+		0 => 0,
+		_ => ID3_HEADER_LEN
+	};
+	Some(ID3_HEADER_LEN + size + footer)
+}
+
+/// Gets the length of an APEv2 tag that starts with a header
+fn ape_len(b: &[u8]) -> Option<usize> {
+	let header = b.get(..APE_HEADER_LEN).filter(|x| x.starts_with(b"APETAGEX"))?;
+	// The size leaves out the header
+	let size = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+	Some(APE_HEADER_LEN + size as usize)
+}
+
+/// The fields of a frame header that stay the same through a stream
+#[derive(Clone, Copy, PartialEq)]
+enum Stream {
+	/// MPEG audio: version and layer bits, and sample rate index
+	Mpeg(u8, u8, u8),
+	/// ADTS: MPEG version bit and sampling frequency index
+	Adts(u8, u8)
+}
+
+/// Bit rates in kbit/s, by version, layer and index
+const BITRATES: [[u16; 15]; 5] =
+[
+	// MPEG-1 layer I, II and III
+	[0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+	[0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+	[0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+	// MPEG-2 and 2.5 layer I, then II and III
+	[0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+	[0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160]
+];
+
+/// Sample rates of MPEG-1 in Hz. MPEG-2 halves them, MPEG-2.5 quarters them.
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Reads the header of an MPEG audio frame, as (stream, frame length)
+///
+/// Free-format frames are rejected, since their length isn't given.
+fn mpeg_frame(b: &[u8]) -> Option<(Stream, usize)> {
+	let h = b.get(..4)?;
+	if h[0] != 0xff || h[1] & 0xe0 != 0xe0 {
+		return None;
+	}
+	let version = (h[1] >> 3) & 0x03;
+	let layer = (h[1] >> 1) & 0x03;
+	let bitrate_index = (h[2] >> 4) as usize;
+	let rate_index = (h[2] >> 2) & 0x03;
+	// Reserved values, and reserved emphasis
+	if version == 1 || layer == 0 || bitrate_index == 0 || bitrate_index == 15 ||
+		rate_index == 3 || h[3] & 0x03 == 2 {
+		return None;
+	}
+
+	let mpeg1 = version == 3;
+	let table = match (mpeg1, layer) {
+		(true, _) => 3 - layer as usize,
+		(false, 3) => 3,
+		(false, _) => 4
+	};
+	let bitrate = u32::from(BITRATES[table][bitrate_index]) * 1000;
+	let sample_rate = SAMPLE_RATES[rate_index as usize] >> match version {
+		3 => 0,
+		2 => 1,
+		_ => 2
+	};
+	let padding = ((h[2] >> 1) & 0x01) as u32;
+
+	let len = match layer {
+		// Layer I counts 4-byte slots
+		3 => (12 * bitrate / sample_rate + padding) * 4,
+		// MPEG-2 layer III frames hold half as many samples
+		1 if !mpeg1 => 72 * bitrate / sample_rate + padding,
+		_ => 144 * bitrate / sample_rate + padding
+	};
+	Some( (Stream::Mpeg(version, layer, rate_index), len as usize) )
+}
+
+/// Reads the header of an ADTS frame, as (stream, frame length)
+fn adts_frame(b: &[u8]) -> Option<(Stream, usize)> {
+	let h = b.get(..7)?;
+	// Sync word, then layer 0
+	if h[0] != 0xff || h[1] & 0xf6 != 0xf0 {
+		return None;
+	}
+	let rate_index = (h[2] >> 2) & 0x0f;
+	if rate_index > 12 {
+		return None;
+	}
+	let len = ((h[3] as usize & 0x03) << 11) | ((h[4] as usize) << 3) | (h[5] as usize >> 5);
+	// The header is 9 bytes long with a CRC
+	let header_len = match h[1] & 0x01 {
+		0 => 9,
+		_ => 7
+	};
+	if len <= header_len {
+		return None;
+	}
+	Some( (Stream::Adts((h[1] >> 3) & 0x01, rate_index), len) )
+}
+
+fn frame(b: &[u8]) -> Option<(Stream, usize)> {
+	adts_frame(b).or_else(|| mpeg_frame(b))
+}
+
+/// Checks if `count` frames of one stream follow each other from the start
+/// of the input
+fn frames_in_a_row(b: &[u8], count: usize) -> Option<Stream> {
+	let (stream, mut len) = frame(b)?;
+	let mut pos = 0;
+	for _ in 1..count {
+		pos += len;
+		let (next, next_len) = frame(b.get(pos..)?)?;
+		if next != stream {
+			return None;
+		}
+		len = next_len;
+	}
+	Some(stream)
+}
+
+/// Works out the type of an MPEG audio stream from its start
+///
+/// Returns None if it isn't one.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	if b.starts_with(b"ADIF") {
+		return Some("audio/aac");
+	}
+	let start = tags_len(b);
+	if start > b.len() {
+		return id3_len(b).map(|_| "audio/mpeg");
+	}
+	detect_frames(&b[start..])
+}
+
+/// Works out the type of a probed stream, reading only the window after its tags
+fn detect_probe(probe: &Probe) -> Option<&'static str> {
+	let input_len = probe.input_len()?;
+	let header = probe.header()?;
+	if header.starts_with(b"ADIF") {
+		return Some("audio/aac");
+	}
+
+	// Tags can run past the header, so each one is read where the last one ends
+	let mut start = 0;
+	while start < input_len {
+		let len = std::cmp::min(APE_HEADER_LEN, input_len - start);
+		match probe.bytes_at(start, len).and_then(|x| tag_len(&x)) {
+			Some(x) => start += x,
+			None => break
+		}
+	}
+	if start > input_len {
+		return id3_len(header).map(|_| "audio/mpeg");
+	}
+
+	let window = probe.bytes_at(start, std::cmp::min(WINDOW_LEN, input_len - start))?;
+	detect_frames(&window)
+}
+
+/// Works out the type of the stream that starts right after the tags
+fn detect_frames(b: &[u8]) -> Option<&'static str> {
+	// Tags are often padded with zeros
+	let first = b.iter().position(|&x| x != 0).unwrap_or(b.len());
+	let stream = frames_in_a_row(&b[first..], 2).or_else(|| {
+		let end = std::cmp::min(first + HEADER_LEN, b.len());
+		(first + 1..end)
+			.filter(|&i| b[i] == 0xff)
+			.find_map(|i| frames_in_a_row(&b[i..], 3))
+	})?;
+	match stream {
+		Stream::Mpeg(..) => Some("audio/mpeg"),
+		Stream::Adts(..) => Some("audio/aac")
+	}
+}
@@ -0,0 +1,131 @@
+//! Handles Ogg files
+//!
+//! An Ogg file interleaves the pages of one or more logical streams. Each
+//! stream starts with a beginning-of-stream page, whose first packet names
+//! the codec, and all of these pages come before any other page. So the
+//! codecs of a file are found by walking its pages until one isn't a
+//! beginning-of-stream page, wherever the magic rules would look.
+//!
+//! A file with a video stream is video, otherwise one with an audio stream
+//! is audio. Skeleton, Kate and other streams don't count. If all streams of
+//! that kind use the same codec the file gets the type of that codec, if not
+//! the generic `video/ogg` or `audio/ogg`. A cut off file is judged on the
+//! streams it has.
+
+pub mod check;
+
+use crate::typetable::TypeTable;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("application/ogg",    &[]),
+	("audio/ogg",          &["application/ogg"]),
+	("audio/x-vorbis+ogg", &["audio/ogg"]),
+	("audio/x-opus+ogg",   &["audio/ogg"]),
+	("audio/x-flac+ogg",   &["audio/ogg"]),
+	("audio/x-speex+ogg",  &["audio/ogg"]),
+	("video/ogg",          &["application/ogg"]),
+	("video/x-theora+ogg", &["video/ogg"]),
+	("video/x-ogm+ogg",    &["video/ogg"])
+];
+
+/// What a stream holds
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+	Audio,
+	Video,
+	Other
+}
+
+/// Codecs by the start of their first packet, with the type of a file of
+/// only that codec
+const CODECS: [(&[u8], &str, Kind); 16] =
+[
+	(b"\x01vorbis",    "audio/x-vorbis+ogg", Kind::Audio),
+	(b"OpusHead",      "audio/x-opus+ogg",   Kind::Audio),
+	(b"\x7fFLAC",      "audio/x-flac+ogg",   Kind::Audio),
+	// FLAC streams from before the Ogg mapping was defined
+	(b"fLaC",          "audio/x-flac+ogg",   Kind::Audio),
+	(b"Speex   ",      "audio/x-speex+ogg",  Kind::Audio),
+	(b"CELT    ",      "audio/ogg",          Kind::Audio),
+	(b"PCM     ",      "audio/ogg",          Kind::Audio),
+	(b"\x80theora",    "video/x-theora+ogg", Kind::Video),
+	(b"\x80daala",     "video/ogg",          Kind::Video),
+	(b"BBCD\0",        "video/ogg",          Kind::Video),
+	(b"OVP80",         "video/ogg",          Kind::Video),
+	// OGM streams hold codecs of other formats
+	(b"\x01video",     "video/x-ogm+ogg",    Kind::Video),
+	(b"\x01audio",     "audio/ogg",          Kind::Audio),
+	(b"\x01text",      "application/ogg",    Kind::Other),
+	(b"fishead\0",     "application/ogg",    Kind::Other),
+	(b"\x80kate\0\0\0", "application/ogg",   Kind::Other)
+];
+
+/// Length of a page header, before its segment table
+const PAGE_HEADER_LEN: usize = 27;
+
+/// Iterates over the first packets of the streams at the start of a file
+///
+/// A packet that runs past the end of the input is cut short and ends the list.
+struct FirstPackets<'a> {
+	rest: &'a [u8]
+}
+
+impl<'a> Iterator for FirstPackets<'a> {
+	type Item = &'a [u8];
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let b = self.rest;
+		let header = b.get(..PAGE_HEADER_LEN)?;
+		// Only beginning-of-stream pages of version 0
+		if &header[..4] != b"OggS" || header[4] != 0 || header[5] & 0x02 == 0 {
+			return None;
+		}
+		let table_end = std::cmp::min(PAGE_HEADER_LEN + header[26] as usize, b.len());
+		let table = &b[PAGE_HEADER_LEN..table_end];
+
+		// The first packet ends at the first lacing value under 255
+		let segments = table.iter().position(|&x| x < 255).map_or(table.len(), |i| i + 1);
+		let packet_len: usize = table[..segments].iter().map(|&x| x as usize).sum();
+		let body_len: usize = table.iter().map(|&x| x as usize).sum();
+		let packet_end = std::cmp::min(table_end + packet_len, b.len());
+		self.rest = &b[std::cmp::min(table_end + body_len, b.len())..];
+		Some(&b[table_end..packet_end])
+	}
+}
+
+/// Gets the type of a file whose streams all have the kind wanted
+///
+/// Returns None if none do.
+fn pick(streams: &[(&'static str, Kind)], kind: Kind, generic: &'static str) -> Option<&'static str> {
+	let mut found = streams.iter().filter(|x| x.1 == kind).map(|x| x.0);
+	let first = found.next()?;
+	match found.all(|x| x == first) {
+		true => Some(first),
+		false => Some(generic)
+	}
+}
+
+/// Works out the type of an Ogg file from its start
+///
+/// Returns None if it isn't one.
+fn detect(b: &[u8]) -> Option<&'static str> {
+	if !b.starts_with(b"OggS") {
+		return None;
+	}
+	let streams: Vec<_> = FirstPackets{rest: b}
+		.filter_map(|packet| {
+			CODECS.iter()
+				.find(|x| packet.starts_with(x.0))
+				.map(|x| (x.1, x.2))
+		})
+		.collect();
+
+	// An OGM video stream makes the whole file OGM
+	if streams.iter().any(|x| x.0 == "video/x-ogm+ogg") {
+		return Some("video/x-ogm+ogg");
+	}
+	pick(&streams, Kind::Video, "video/ogg")
+		.or_else(|| pick(&streams, Kind::Audio, "audio/ogg"))
+		.or(Some("application/ogg"))
+}
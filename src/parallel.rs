@@ -0,0 +1,270 @@
+//! Classify batches of files on a pool of worker threads
+//!
+//! Only available with the `parallel` feature.
+//!
+//! `classify_paths` takes any iterator of paths and returns an iterator of
+//! results. Paths are pulled from the input lazily, and at most
+//! `ClassifyOptions::queue_len` results are held in memory at once, so this
+//! works on directory walks of any size. Each file is checked through a
+//! `Probe`, exactly as `from_filepath` would check it, and each worker reads
+//! every header into the same buffer.
+//!
+//! # Examples
+//! ```rust
+//! use std::path::PathBuf;
+//!
+//! let paths = vec![PathBuf::from("tests/image/gif"), PathBuf::from("tests/image/png")];
+//! let options = tree_magic::parallel::ClassifyOptions{ordered: true, ..Default::default()};
+//!
+//! let results: Vec<_> = tree_magic::parallel::classify_paths_with(paths, options)
+//!     .map(|(path, mime)| (path, mime.unwrap().to_string()))
+//!     .collect();
+//! assert_eq!(results[0].1, "image/gif");
+//! assert_eq!(results[1].1, "image/png");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use crate::MIME;
+use crate::probe::Probe;
+
+/// Settings for `classify_paths_with`
+#[derive(Debug, Clone)]
+pub struct ClassifyOptions {
+	/// Number of worker threads. 0 means one per CPU.
+	pub workers: usize,
+	/// Most results held in memory at once
+	///
+	/// Workers wait when this many results are waiting to be read.
+	/// In ordered mode, this is also how far past the oldest unfinished
+	/// path the workers may go.
+	pub queue_len: usize,
+	/// Return results in input order instead of as they finish
+	pub ordered: bool
+}
+
+impl Default for ClassifyOptions {
+	fn default() -> ClassifyOptions {
+		ClassifyOptions{
+			workers: 0,
+			queue_len: 256,
+			ordered: false
+		}
+	}
+}
+
+/// A handle that stops a running classification from any thread
+#[derive(Clone)]
+pub struct Canceller {
+	shared: Arc<Shared>
+}
+
+impl Canceller {
+	/// Stops the workers after the files they are reading
+	///
+	/// The iterator returns `None` from then on.
+	pub fn cancel(&self) {
+		self.shared.cancel();
+	}
+
+	/// Checks if the classification was cancelled
+	pub fn is_cancelled(&self) -> bool {
+		self.shared.cancelled.load(Ordering::SeqCst)
+	}
+}
+
+/// The paths still to classify
+struct Input {
+	paths: Box<dyn Iterator<Item = PathBuf> + Send>,
+	/// Number of paths handed to workers
+	taken: usize,
+	/// Number of results returned, in ordered mode
+	returned: usize
+}
+
+/// State shared by the workers and the iterator
+struct Shared {
+	input: Mutex<Input>,
+	wake: Condvar,
+	cancelled: AtomicBool,
+	ordered: bool,
+	window: usize
+}
+
+impl Shared {
+	fn cancel(&self) {
+		self.cancelled.store(true, Ordering::SeqCst);
+		// Take the lock so no worker misses the wakeup
+		let _input = self.input.lock();
+		self.wake.notify_all();
+	}
+
+	/// Hands out the next path with its index, or None when done
+	fn next_path(&self) -> Option<(usize, PathBuf)> {
+		let mut input = self.input.lock().ok()?;
+		loop {
+			if self.cancelled.load(Ordering::SeqCst) {
+				return None;
+			}
+			// In ordered mode, don't get too far ahead of the reader
+			if !self.ordered || input.taken < input.returned + self.window {
+				break;
+			}
+			input = self.wake.wait(input).ok()?;
+		}
+
+		let path = input.paths.next()?;
+		input.taken += 1;
+		Some( (input.taken - 1, path) )
+	}
+
+	fn set_returned(&self, returned: usize) {
+		if let Ok(mut input) = self.input.lock() {
+			input.returned = returned;
+		}
+		self.wake.notify_all();
+	}
+}
+
+/// Iterator over (path, result) pairs returned by `classify_paths`
+///
+/// Dropping it cancels the remaining work.
+pub struct Classify {
+	results: Receiver<(usize, PathBuf, io::Result<MIME>)>,
+	shared: Arc<Shared>,
+	/// Results that arrived before their turn, in ordered mode
+	pending: BTreeMap<usize, (PathBuf, io::Result<MIME>)>,
+	returned: usize
+}
+
+impl Classify {
+	/// Gets a handle that can cancel this classification from another thread
+	pub fn canceller(&self) -> Canceller {
+		Canceller{shared: self.shared.clone()}
+	}
+
+	/// Stops the workers after the files they are reading
+	pub fn cancel(&self) {
+		self.shared.cancel();
+	}
+}
+
+impl Iterator for Classify {
+	type Item = (PathBuf, io::Result<MIME>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if self.shared.cancelled.load(Ordering::SeqCst) {
+				return None;
+			}
+
+			if let Some(x) = self.pending.remove(&self.returned) {
+				self.returned += 1;
+				self.shared.set_returned(self.returned);
+				return Some(x);
+			}
+
+			// Fails once every worker has finished
+			let (index, path, result) = self.results.recv().ok()?;
+			if !self.shared.ordered {
+				return Some( (path, result) );
+			}
+			self.pending.insert(index, (path, result));
+		}
+	}
+}
+
+impl Drop for Classify {
+	fn drop(&mut self) {
+		self.shared.cancel();
+	}
+}
+
+/// Classifies one file, reading its header into `buffer`
+fn classify(path: &Path, buffer: &mut Vec<u8>) -> io::Result<MIME> {
+	let probe = Probe::from_filepath_reusing(path, std::mem::take(buffer))?;
+	let result = crate::from_probe(&probe);
+	*buffer = probe.into_buffer();
+
+	result.ok_or_else(|| io::Error::other("Unknown file type"))
+}
+
+fn worker(shared: Arc<Shared>, tx: SyncSender<(usize, PathBuf, io::Result<MIME>)>) {
+	// Every file this worker reads shares one header buffer
+	let mut buffer = Vec::<u8>::new();
+	while let Some((index, path)) = shared.next_path() {
+		let result = classify(&path, &mut buffer);
+		// The iterator was dropped
+		if tx.send( (index, path, result) ).is_err() {
+			return;
+		}
+	}
+}
+
+/// Classifies files on worker threads, with default options
+///
+/// Results come back as they finish, not in input order.
+pub fn classify_paths<I>(paths: I) -> Classify
+	where I: IntoIterator<Item = PathBuf>, I::IntoIter: Send + 'static
+{
+	classify_paths_with(paths, ClassifyOptions::default())
+}
+
+/// Classifies files on worker threads
+///
+/// # Examples
+/// ```rust
+/// use std::path::PathBuf;
+/// use tree_magic::parallel::{classify_paths_with, ClassifyOptions};
+///
+/// let paths = vec![PathBuf::from("tests/image/gif"), PathBuf::from("tests/nonexistent")];
+/// let options = ClassifyOptions{workers: 2, ordered: true, ..Default::default()};
+///
+/// let mut results = classify_paths_with(paths, options);
+/// assert_eq!(results.next().unwrap().1.unwrap(), "image/gif");
+/// assert!(results.next().unwrap().1.is_err());
+/// assert!(results.next().is_none());
+/// ```
+pub fn classify_paths_with<I>(paths: I, options: ClassifyOptions) -> Classify
+	where I: IntoIterator<Item = PathBuf>, I::IntoIter: Send + 'static
+{
+	let workers = match options.workers {
+		0 => thread::available_parallelism().map(|x| x.get()).unwrap_or(1),
+		x => x
+	};
+	let queue_len = std::cmp::max(options.queue_len, 1);
+
+	// Build the type graph once, instead of in every worker at once
+	lazy_static::initialize(&crate::TYPE);
+
+	let shared = Arc::new(Shared{
+		input: Mutex::new(Input{
+			paths: Box::new(paths.into_iter().fuse()),
+			taken: 0,
+			returned: 0
+		}),
+		wake: Condvar::new(),
+		cancelled: AtomicBool::new(false),
+		ordered: options.ordered,
+		window: queue_len
+	});
+
+	let (tx, rx) = mpsc::sync_channel(queue_len);
+	for _ in 0..workers {
+		let shared = shared.clone();
+		let tx = tx.clone();
+		thread::spawn(move || worker(shared, tx));
+	}
+
+	Classify{
+		results: rx,
+		shared,
+		pending: BTreeMap::new(),
+		returned: 0
+	}
+}
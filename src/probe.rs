@@ -0,0 +1,480 @@
+//! Lazily computed facts about one input, shared by every checker
+//!
+//! A `Probe` wraps a file path or a bytestream. Each checker asks it for what
+//! it needs (metadata, the first bytes, a text view, the ZIP directory) and
+//! the probe works it out on first use. Later checkers get the cached copy,
+//! so a file is opened and parsed once per walk instead of once per type.
+//!
+//! With the `mmap` feature, a regular file is mapped into memory the first
+//! time a checker asks for more than the header, and reads past the header
+//! are slices of the mapping. Pipes, special files and files that can't be
+//! mapped fall back to buffered reads. Before each read from the mapping,
+//! the file's length is checked again, and a file that shrank since it was
+//! mapped is read buffered instead.
+//!
+//! That check can't rule out a file being truncated between the check and the
+//! read, or while a checker still holds a slice of the mapping. Reading the
+//! lost pages then raises SIGBUS on Unix, which ends the process. Don't enable
+//! `mmap` for files that other processes may truncate while they are checked.
+//!
+//! # Examples
+//! ```rust
+//! use std::path::Path;
+//! use tree_magic::probe::Probe;
+//!
+//! let probe = Probe::from_filepath(Path::new("tests/application/zip"));
+//! assert_eq!(tree_magic::from_probe(&probe), Some("application/zip".parse().unwrap()));
+//! // The checkers already read the header, so this doesn't touch the disk again
+//! assert_eq!(&probe.header().unwrap()[..2], b"PK");
+//! assert_eq!(probe.zip_entries().unwrap(), ["plain"]);
+//! ```
+
+use std::borrow::Cow;
+#[cfg(feature = "async")]
+use std::cell::RefCell;
+use std::cell::OnceCell;
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes of a file read by `header`, the same amount `from_filepath` always read
+pub const HEADER_LEN: usize = 2048;
+
+/// Number of cached reads of a file, each 4 times longer than the last
+///
+/// Longer requests round up to the next read, so a file is read at most
+/// this many times however the requested lengths grow.
+const READS: usize = 8;
+
+/// Length of the ZIP end of central directory record, without its comment
+const ZIP_EOCD_LEN: usize = 22;
+
+/// How far from the end of a ZIP file its end of central directory record can start
+const ZIP_EOCD_SEARCH: usize = ZIP_EOCD_LEN + 0xffff;
+
+enum Source<'a> {
+	Bytes(Cow<'a, [u8]>),
+	Path(&'a Path),
+	#[cfg(feature = "async")]
+	Ranges(&'a Prefetch)
+}
+
+/// Parts of a file read ahead, for probing it without blocking
+///
+/// Reads the checkers ask for that aren't held are recorded instead of done,
+/// so the caller can fetch them however it likes and probe again.
+#[cfg(feature = "async")]
+pub(crate) struct Prefetch {
+	len: usize,
+	ranges: Vec<(usize, Vec<u8>)>,
+	misses: RefCell<Vec<(usize, usize)>>
+}
+
+#[cfg(feature = "async")]
+impl Prefetch {
+	/// Starts with nothing read of a file `len` bytes long
+	pub(crate) fn new(len: usize) -> Prefetch {
+		Prefetch{len, ranges: Vec::new(), misses: RefCell::new(Vec::new())}
+	}
+
+	/// Adds the bytes read at `offset`
+	pub(crate) fn insert(&mut self, offset: usize, b: Vec<u8>) {
+		self.ranges.push((offset, b));
+	}
+
+	/// Takes the ranges asked for since the last call, as offsets and lengths
+	pub(crate) fn take_misses(&self) -> Vec<(usize, usize)> {
+		self.misses.take()
+	}
+
+	/// Gets `len` bytes at `offset` if they were read, or records the miss
+	fn get(&self, offset: usize, len: usize) -> Option<&[u8]> {
+		let end = offset.checked_add(len)?;
+		let found = self.ranges.iter()
+			.find(|(start, b)| *start <= offset && end <= start + b.len());
+		match found {
+			Some((start, b)) => Some(&b[offset - start..end - start]),
+			None => {
+				let mut misses = self.misses.borrow_mut();
+				if !misses.contains(&(offset, len)) {
+					misses.push((offset, len));
+				}
+				None
+			}
+		}
+	}
+}
+
+/// A file or bytestream being classified, with everything known about it so far
+pub struct Probe<'a> {
+	source: Source<'a>,
+	/// Set if a bytestream is only the start of a longer input
+	truncated: bool,
+	metadata: OnceCell<Option<fs::Metadata>>,
+	/// Reads of the start of a file, of `HEADER_LEN * 4^n` bytes
+	reads: [OnceCell<Option<Vec<u8>>>; READS],
+	#[cfg(feature = "mmap")]
+	mapping: OnceCell<Option<Mapping>>,
+	text: OnceCell<Option<String>>,
+	zip: OnceCell<Option<Vec<String>>>,
+	#[cfg(feature = "decompress")]
+	decompressed: OnceCell<Option<Vec<u8>>>
+}
+
+impl<'a> Probe<'a> {
+	fn new(source: Source<'a>) -> Probe<'a> {
+		Probe{
+			source,
+			truncated: false,
+			metadata: OnceCell::new(),
+			reads: Default::default(),
+			#[cfg(feature = "mmap")]
+			mapping: OnceCell::new(),
+			text: OnceCell::new(),
+			zip: OnceCell::new(),
+			#[cfg(feature = "decompress")]
+			decompressed: OnceCell::new()
+		}
+	}
+
+	/// Probes a bytestream
+	pub fn from_u8(bytes: &'a [u8]) -> Probe<'a> {
+		Probe::new(Source::Bytes(Cow::Borrowed(bytes)))
+	}
+
+	/// Probes the first `HEADER_LEN` bytes of a reader
+	///
+	/// Rules that look past those bytes won't match.
+	/// Returns an error if reading fails.
+	pub fn from_reader<R: Read>(reader: R) -> io::Result<Probe<'static>> {
+		let mut b = Vec::<u8>::with_capacity(HEADER_LEN);
+		reader.take(HEADER_LEN as u64).read_to_end(&mut b)?;
+		Ok(Probe::from_header(b))
+	}
+
+	/// Probes up to `HEADER_LEN` bytes read from the start of a stream
+	///
+	/// A full header is taken to be cut off, as the stream may go on.
+	pub(crate) fn from_header(b: Vec<u8>) -> Probe<'static> {
+		let mut probe = Probe::new(Source::Bytes(Cow::Owned(b)));
+		probe.truncated = probe.input_len() == Some(HEADER_LEN);
+		probe
+	}
+
+	/// Probes the parts of a file read ahead. Nothing is read here.
+	#[cfg(feature = "async")]
+	pub(crate) fn from_prefetch(prefetch: &'a Prefetch) -> Probe<'a> {
+		Probe::new(Source::Ranges(prefetch))
+	}
+
+	/// Probes a file. Nothing is read until a checker asks for it.
+	pub fn from_filepath(filepath: &'a Path) -> Probe<'a> {
+		Probe::new(Source::Path(filepath))
+	}
+
+	/// Probes a file, reading its header right away
+	///
+	/// Unlike `from_filepath`, this reports why a file can't be read.
+	/// Directories and other special files are probed without reading them.
+	pub fn from_filepath_checked(filepath: &'a Path) -> io::Result<Probe<'a>> {
+		Probe::from_filepath_reusing(filepath, Vec::with_capacity(HEADER_LEN))
+	}
+
+	/// Like `from_filepath_checked`, but reads the header into `buffer`
+	///
+	/// Whatever `buffer` holds is replaced. Get it back with `into_buffer`
+	/// to probe the next file without allocating.
+	pub fn from_filepath_reusing(filepath: &'a Path, mut buffer: Vec<u8>) -> io::Result<Probe<'a>> {
+		let probe = Probe::from_filepath(filepath);
+		let metadata = fs::metadata(filepath)?;
+		if metadata.is_file() {
+			buffer.clear();
+			File::open(filepath)?.take(HEADER_LEN as u64).read_to_end(&mut buffer)?;
+			let _ = probe.reads[0].set(Some(buffer));
+		}
+		let _ = probe.metadata.set(Some(metadata));
+		Ok(probe)
+	}
+
+	/// Gives back the buffer holding the header, to reuse for another file
+	///
+	/// Returns an empty buffer if the header wasn't read.
+	pub fn into_buffer(self) -> Vec<u8> {
+		let [header, ..] = self.reads;
+		header.into_inner().flatten().unwrap_or_default()
+	}
+
+	/// Gets the path being probed, or None for a bytestream
+	pub fn path(&self) -> Option<&'a Path> {
+		match self.source {
+			Source::Path(x) => Some(x),
+			_ => None
+		}
+	}
+
+	/// Gets the file's metadata
+	///
+	/// Returns None for a bytestream, or if the file can't be accessed.
+	pub fn metadata(&self) -> Option<&fs::Metadata> {
+		let path = self.path()?;
+		self.metadata.get_or_init(|| fs::metadata(path).ok()).as_ref()
+	}
+
+	/// Gets up to `len` bytes from the start of the input
+	///
+	/// Returns fewer bytes if the input is shorter, or None if the file can't be read.
+	pub fn bytes(&self, len: usize) -> Option<&[u8]> {
+		let path = match self.source {
+			Source::Bytes(ref b) => return Some(&b[..std::cmp::min(len, b.len())]),
+			Source::Path(x) => x,
+			#[cfg(feature = "async")]
+			Source::Ranges(p) => return p.get(0, std::cmp::min(len, p.len))
+		};
+
+		#[cfg(feature = "mmap")]
+		{
+			if len > HEADER_LEN {
+				if let Some(map) = self.mapping() {
+					let end = std::cmp::min(len, map.map.len());
+					if map.holds(end) {
+						return Some(&map.map[..end]);
+					}
+				}
+			}
+		}
+
+		for (i, read) in self.reads.iter().enumerate() {
+			let read_len = HEADER_LEN << (2 * i);
+			if read_len < len && i + 1 < READS {
+				// A shorter read that stopped early already holds the whole file
+				if let Some(Some(b)) = read.get() {
+					if b.len() < read_len {
+						return Some(b);
+					}
+				}
+				continue;
+			}
+
+			let read_len = std::cmp::max(read_len, len);
+			let b = read.get_or_init(|| read_bytes_at(path, 0, read_len).ok()).as_ref()?;
+			return Some(&b[..std::cmp::min(len, b.len())]);
+		}
+		None
+	}
+
+	/// Gets the start of the input: a whole bytestream, or the first `HEADER_LEN` bytes of a file
+	pub fn header(&self) -> Option<&[u8]> {
+		match self.source {
+			Source::Bytes(ref b) => Some(&b[..]),
+			_ => self.bytes(HEADER_LEN)
+		}
+	}
+
+	/// Checks if `header` holds the whole input
+	pub fn header_is_complete(&self) -> bool {
+		match self.source {
+			Source::Bytes(_) => !self.truncated,
+			_ => self.header().is_some_and(|x| x.len() < HEADER_LEN)
+		}
+	}
+
+	/// Gets the header decoded as text
+	///
+	/// The header is read as UTF-16 if it starts with a UTF-16 byte order mark,
+	/// and as UTF-8 otherwise. A character cut off by the end of the header is
+	/// dropped. Returns None if the header isn't valid text.
+	pub fn text(&self) -> Option<&str> {
+		self.text.get_or_init(|| decode_text(self.header()?)).as_deref()
+	}
+
+	/// Gets the names of the entries in a ZIP file's central directory
+	///
+	/// Returns None if the input isn't a ZIP file, or its directory is damaged.
+	pub fn zip_entries(&self) -> Option<&[String]> {
+		self.zip.get_or_init(|| self.read_zip_directory()).as_deref()
+	}
+
+	/// Gets the start of the decompressed contents of a compressed input
+	///
+	/// Up to `compressed::PAYLOAD_LEN` bytes are decompressed. Returns None if
+	/// the input isn't compressed in one of the enabled formats, or nothing
+	/// could be decompressed.
+	#[cfg(feature = "decompress")]
+	pub fn decompressed(&self) -> Option<&[u8]> {
+		self.decompressed.get_or_init(|| crate::compressed::decompress(self)).as_deref()
+	}
+
+	/// Maps the file, or returns None if it should be read buffered
+	#[cfg(feature = "mmap")]
+	fn mapping(&self) -> Option<&Mapping> {
+		let path = self.path()?;
+		self.mapping.get_or_init(|| map_file(path)).as_ref()
+	}
+
+	/// Gets `len` bytes starting at `offset`, wherever they are in the input
+	pub(crate) fn bytes_at(&self, offset: usize, len: usize) -> Option<Cow<'_, [u8]>> {
+		match self.source {
+			Source::Bytes(ref b) => Some(Cow::Borrowed(b.get(offset..offset.checked_add(len)?)?)),
+			Source::Path(path) => {
+				// The header is read anyway, so it is never worth mapping for
+				let end = offset.checked_add(len)?;
+				if end <= HEADER_LEN {
+					return self.header()?.get(offset..end).map(Cow::Borrowed);
+				}
+
+				#[cfg(feature = "mmap")]
+				{
+					if let Some(map) = self.mapping() {
+						if end > map.map.len() {
+							return None;
+						}
+						if map.holds(end) {
+							return Some(Cow::Borrowed(&map.map[offset..end]));
+						}
+					}
+				}
+
+				let b = read_bytes_at(path, offset, len).ok()?;
+				match b.len() == len {
+					true => Some(Cow::Owned(b)),
+					false => None
+				}
+			},
+			#[cfg(feature = "async")]
+			Source::Ranges(p) => {
+				if offset.checked_add(len)? > p.len {
+					return None;
+				}
+				p.get(offset, len).map(Cow::Borrowed)
+			}
+		}
+	}
+
+	/// Gets the length of the whole input, or None if the file can't be accessed
+	pub(crate) fn input_len(&self) -> Option<usize> {
+		match self.source {
+			Source::Bytes(ref b) => Some(b.len()),
+			Source::Path(_) => self.metadata().map(|x| x.len() as usize),
+			#[cfg(feature = "async")]
+			Source::Ranges(p) => Some(p.len)
+		}
+	}
+
+	fn read_zip_directory(&self) -> Option<Vec<String>> {
+		let input_len = self.input_len()?;
+		let tail_start = input_len.saturating_sub(ZIP_EOCD_SEARCH);
+		let tail = self.bytes_at(tail_start, input_len - tail_start)?;
+
+		// The record ends with a variable-length comment, so search from the end
+		let eocd = (0..=tail.len().checked_sub(ZIP_EOCD_LEN)?).rev()
+			.find(|&i| tail[i..i + 4] == *b"PK\x05\x06")?;
+		let eocd = &tail[eocd..];
+		let count = u16_le(eocd, 10)? as usize;
+		let size = u32_le(eocd, 12)? as usize;
+		let offset = u32_le(eocd, 16)? as usize;
+
+		if offset.checked_add(size)? > input_len {
+			return None;
+		}
+		let directory = self.bytes_at(offset, size)?;
+
+		let mut names = Vec::<String>::with_capacity(std::cmp::min(count, 1024));
+		let mut pos = 0;
+		for _ in 0..count {
+			let entry = directory.get(pos..pos + 46)?;
+			if entry[..4] != *b"PK\x01\x02" {
+				return None;
+			}
+			let name_len = u16_le(entry, 28)? as usize;
+			let extra_len = u16_le(entry, 30)? as usize;
+			let comment_len = u16_le(entry, 32)? as usize;
+
+			let name = directory.get(pos + 46..pos + 46 + name_len)?;
+			names.push(String::from_utf8_lossy(name).into_owned());
+			pos += 46 + name_len + extra_len + comment_len;
+		}
+
+		Some(names)
+	}
+}
+
+fn u16_le(b: &[u8], offset: usize) -> Option<u16> {
+	Some(u16::from_le_bytes(b.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_le(b: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_le_bytes(b.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// A read-only mapping of a file, with the file kept open to check its length
+#[cfg(feature = "mmap")]
+struct Mapping {
+	file: File,
+	map: memmap2::Mmap
+}
+
+#[cfg(feature = "mmap")]
+impl Mapping {
+	/// Checks if the file still holds the mapped bytes up to `end`
+	fn holds(&self, end: usize) -> bool {
+		self.file.metadata().is_ok_and(|x| x.len() >= end as u64)
+	}
+}
+
+/// Maps a regular file that is longer than the header
+#[cfg(feature = "mmap")]
+fn map_file(filepath: &Path) -> Option<Mapping> {
+	let file = File::open(filepath).ok()?;
+
+	// Pipes and special files can't be mapped, and short files are cheaper to read
+	let len = file.metadata().ok().filter(|x| x.is_file())?.len();
+	if len <= HEADER_LEN as u64 {
+		return None;
+	}
+
+	// SAFETY: the mapping is only ever read, and the file's length is checked
+	// before each read. A file truncated right after the check can still fault,
+	// see the module docs.
+	let map = unsafe { memmap2::MmapOptions::new().len(len.try_into().ok()?).map(&file) }.ok()?;
+	Some(Mapping{file, map})
+}
+
+/// Reads up to `len` bytes of a file, starting at `offset`
+fn read_bytes_at(filepath: &Path, offset: usize, len: usize) -> io::Result<Vec<u8>> {
+	let mut f = File::open(filepath)?;
+	f.seek(SeekFrom::Start(offset as u64))?;
+
+	let mut b = Vec::<u8>::new();
+	f.take(len as u64).read_to_end(&mut b)?;
+	Ok(b)
+}
+
+fn decode_text(b: &[u8]) -> Option<String> {
+	match b {
+		[0xef, 0xbb, 0xbf, rest @ ..] => decode_utf8(rest),
+		[0xff, 0xfe, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+		[0xfe, 0xff, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+		_ => decode_utf8(b)
+	}
+}
+
+fn decode_utf8(b: &[u8]) -> Option<String> {
+	match std::str::from_utf8(b) {
+		Ok(x) => Some(x.to_string()),
+		// Only the last character is incomplete
+		Err(e) if e.error_len().is_none() => {
+			std::str::from_utf8(&b[..e.valid_up_to()]).ok().map(|x| x.to_string())
+		},
+		Err(_) => None
+	}
+}
+
+fn decode_utf16(b: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+	let mut units: Vec<u16> = b.chunks_exact(2).map(|x| unit([x[0], x[1]])).collect();
+	// Drop the first half of a surrogate pair cut off by the end of the header
+	if units.last().is_some_and(|x| (0xd800..0xdc00).contains(x)) {
+		units.pop();
+	}
+	String::from_utf16(&units).ok()
+}
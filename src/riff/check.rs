@@ -0,0 +1,17 @@
+use crate::MIME;
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	super::is_type(b, mimetype.essence_str())
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	// Only the container needs more than the first few bytes
+	let len = match mimetype == "application/x-riff" {
+		true => 12,
+		false => super::WINDOW_LEN
+	};
+	probe.bytes(len).is_some_and(|b| from_u8(b, mimetype))
+}
@@ -0,0 +1,11 @@
+use crate::{typetable, MIME};
+
+/// Get list of parent -> child subclass links
+///
+/// Every RIFF form is a child of the container as well.
+pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+	let riff: MIME = "application/x-riff".parse().unwrap();
+	typetable::subclasses(super::TYPES).into_iter()
+		.chain(super::FORMS.iter().map(|x| (riff.clone(), x.parse().unwrap())))
+		.collect()
+}
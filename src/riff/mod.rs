@@ -0,0 +1,161 @@
+//! Handles RIFF containers: WAV, AVI, WebP and friends
+//!
+//! A RIFF file starts with a container ID (`RIFF`, big-endian `RIFX`, or
+//! `RF64` and `BW64` for WAV files over 4 GiB), its length and a form type.
+//! The form type says which format it is, and the magic rules already use
+//! it. Telling variants of a format apart takes the chunks after it:
+//!
+//! - WebP: the first chunk is `VP8 ` (lossy), `VP8L` (lossless) or `VP8X`
+//!   (extended, with alpha, animation or metadata)
+//! - WAV: RF64 and BW64 files start with a `ds64` chunk holding the real sizes
+//! - AVI: OpenDML (AVI 2.0) files have an `odml` list in their header list
+//!
+//! These variants are children of the types the magic rules find, which in
+//! turn go under `application/x-riff`, so a generic RIFF match can't shadow
+//! them. Chunks are only walked as far as the input goes, so a cut off file
+//! is judged on the chunks it has.
+
+pub mod check;
+pub mod init;
+
+use crate::typetable::TypeTable;
+
+/// Most bytes of a file whose chunks are walked
+///
+/// AVI header lists can hold an index of each stream before the `odml` list.
+const WINDOW_LEN: usize = 16 * crate::probe::HEADER_LEN;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("application/x-riff",       &[]),
+	("image/x-webp-lossy",       &["image/webp"]),
+	("image/x-webp-lossless",    &["image/webp"]),
+	("image/x-webp-extended",    &["image/webp"]),
+	("audio/x-rf64",             &["audio/x-wav"]),
+	("audio/x-bw64",             &["audio/x-wav"]),
+	("video/x-msvideo-opendml",  &["video/x-msvideo"])
+];
+
+/// Types the magic rules find in RIFF files, which go under `application/x-riff`
+const FORMS: [&str; 5] =
+[
+	"application/vnd.corel-draw",
+	"application/x-navi-animation",
+	"audio/x-wav",
+	"image/webp",
+	"video/x-msvideo"
+];
+
+/// The header of a RIFF file
+struct Riff<'a> {
+	/// Container ID: `RIFF`, `RIFX`, `RF64`, `BW64` or `AVF0`
+	id: &'a [u8],
+	form: &'a [u8],
+	/// The chunks after the form type
+	chunks: &'a [u8],
+	big_endian: bool
+}
+
+/// Reads the header of a RIFF file
+///
+/// Returns None if the input doesn't start with a RIFF header, or its form
+/// type isn't made of printable characters. Some old AVI files start with
+/// `AVF0` instead of `RIFF`.
+fn parse(b: &[u8]) -> Option<Riff<'_>> {
+	let id = b.get(..4)?;
+	if !matches!(id, b"RIFF" | b"RIFX" | b"RF64" | b"BW64" | b"AVF0") {
+		return None;
+	}
+	let form = b.get(8..12)?;
+	if !form.iter().all(|x| (0x20..0x7f).contains(x)) {
+		return None;
+	}
+	Some(Riff{id, form, chunks: &b[12..], big_endian: id == b"RIFX"})
+}
+
+/// Iterates over a list of chunks, as (ID, contents)
+///
+/// A chunk that runs past the end of the input is cut short and ends the list.
+struct Chunks<'a> {
+	rest: &'a [u8],
+	big_endian: bool
+}
+
+impl<'a> Iterator for Chunks<'a> {
+	type Item = (&'a [u8], &'a [u8]);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let b = self.rest;
+		let id = b.get(..4)?;
+		let size = match b.get(4..8) {
+			Some(x) => [x[0], x[1], x[2], x[3]],
+			// Cut off before the length
+			None => {
+				self.rest = &[];
+				return Some( (id, &[]) );
+			}
+		};
+		let size = match self.big_endian {
+			true => u32::from_be_bytes(size),
+			false => u32::from_le_bytes(size)
+		} as usize;
+
+		let end = std::cmp::min(size.saturating_add(8), b.len());
+		let contents = &b[8..end];
+		// Chunks are padded to an even length
+		self.rest = b.get(end + (size & 1)..).unwrap_or(&[]);
+		Some( (id, contents) )
+	}
+}
+
+impl<'a> Riff<'a> {
+	fn chunks(&self) -> Chunks<'a> {
+		Chunks{rest: self.chunks, big_endian: self.big_endian}
+	}
+
+	/// Finds the lists of a type among the chunks, as their contents
+	fn lists(&self, chunks: &'a [u8], kind: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+		Chunks{rest: chunks, big_endian: self.big_endian}
+			.filter(move |x| x.0 == b"LIST" && x.1.get(..4) == Some(kind))
+			.map(|x| &x.1[4..])
+	}
+}
+
+/// Works out the variant of a RIFF file
+///
+/// Returns None if there isn't a more specific type than the form type.
+fn variant(riff: &Riff) -> Option<&'static str> {
+	match riff.form {
+		b"WEBP" => match riff.chunks().next()?.0 {
+			b"VP8 " => Some("image/x-webp-lossy"),
+			b"VP8L" => Some("image/x-webp-lossless"),
+			b"VP8X" => Some("image/x-webp-extended"),
+			_ => None
+		},
+		b"WAVE" if riff.chunks().next()?.0 == b"ds64" => match riff.id {
+			b"RF64" => Some("audio/x-rf64"),
+			b"BW64" => Some("audio/x-bw64"),
+			_ => None
+		},
+		b"AVI " => {
+			let hdrl = riff.lists(riff.chunks, b"hdrl").next()?;
+			match riff.lists(hdrl, b"odml").next() {
+				Some(_) => Some("video/x-msvideo-opendml"),
+				None => None
+			}
+		}
+		_ => None
+	}
+}
+
+/// Checks if a file is the given type
+fn is_type(b: &[u8], mimetype: &str) -> bool {
+	let riff = match parse(b) {
+		Some(x) => x,
+		None => return false
+	};
+	match mimetype {
+		"application/x-riff" => true,
+		_ => variant(&riff) == Some(mimetype)
+	}
+}
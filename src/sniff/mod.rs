@@ -0,0 +1,7 @@
+//! Sniffing algorithms of other standards
+//!
+//! The rest of the crate follows shared-mime-info. Consumers that need to
+//! agree with someone else's idea of a file's type, like a browser's, can use
+//! the algorithms here instead. They don't use the type graph.
+
+pub mod web;
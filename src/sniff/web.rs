@@ -0,0 +1,450 @@
+//! The WHATWG MIME Sniffing Standard, as browsers apply it to web resources
+//!
+//! A browser doesn't trust a server's `Content-Type` blindly, nor does it
+//! run the shared-mime-info rules. It looks at the first
+//! `RESOURCE_HEADER_LEN` bytes of a response, the type the server supplied,
+//! and whether the server sent `X-Content-Type-Options: nosniff`, and
+//! computes a type from fixed tables of patterns:
+//!
+//! - No usable supplied type: the resource is sniffed from scratch. Scriptable
+//!   types (HTML, XML, PDF) are only sniffed without `nosniff`, and HTML tags
+//!   may follow whitespace.
+//! - `text/plain` exactly as old Apache servers send it for everything: the
+//!   resource is only checked for being text or binary.
+//! - `text/html`: it may turn out to be an RSS or Atom feed.
+//! - An image, audio or video type: the resource may turn out to be of
+//!   another type of the same kind.
+//! - Anything else, or `nosniff`: the supplied type stands.
+//!
+//! The results are what the standard gives, which doesn't always agree with
+//! the rest of this crate (an MP4 file is `video/mp4` even if it holds only
+//! audio).
+//!
+//! # Examples
+//! ```rust
+//! use tree_magic::sniff::web;
+//!
+//! let html = b"\n  <!DOCTYPE html><title>Hi</title>";
+//! assert_eq!(web::from_u8(html, None, false), "text/html");
+//! // Served as text by an old Apache
+//! assert_eq!(web::from_u8(html, Some("text/plain; charset=UTF-8"), false), "text/plain");
+//!
+//! let png = include_bytes!("../../tests/image/png");
+//! assert_eq!(web::from_u8(png, Some("image/gif"), false), "image/png");
+//! assert_eq!(web::from_u8(png, Some("image/gif"), true), "image/gif");
+//! ```
+
+use std::path::Path;
+use crate::probe::Probe;
+use crate::MIME;
+
+/// Bytes at the start of a resource that are looked at
+pub const RESOURCE_HEADER_LEN: usize = 1445;
+
+/// `Content-Type` values that Apache sends for any file without a known
+/// extension, in which case the supplied type is only trusted for text
+const APACHE_BUG_TYPES: [&str; 4] =
+[
+	"text/plain",
+	"text/plain; charset=ISO-8859-1",
+	"text/plain; charset=iso-8859-1",
+	"text/plain; charset=UTF-8"
+];
+
+/// A row of a pattern table: pattern, mask and computed type.
+/// An empty mask matches every byte exactly.
+type Row = (&'static [u8], &'static [u8], &'static str);
+
+/// Whitespace bytes, which may come before some patterns
+const WHITESPACE: &[u8] = b"\t\n\x0c\r ";
+
+/// HTML tags that make a resource HTML, in upper case. Each must be
+/// followed by a space or `>`, and letters match in either case.
+const HTML_TAGS: [&[u8]; 17] =
+[
+	b"<!DOCTYPE HTML", b"<HTML", b"<HEAD", b"<SCRIPT", b"<IFRAME", b"<H1", b"<DIV",
+	b"<FONT", b"<TABLE", b"<A", b"<STYLE", b"<TITLE", b"<B", b"<BODY", b"<BR", b"<P",
+	b"<!--"
+];
+
+const IMAGE_TYPES: [Row; 8] =
+[
+	(b"\x00\x00\x01\x00", b"", "image/x-icon"),
+	(b"\x00\x00\x02\x00", b"", "image/x-icon"),
+	(b"BM", b"", "image/bmp"),
+	(b"GIF87a", b"", "image/gif"),
+	(b"GIF89a", b"", "image/gif"),
+	(b"RIFF\0\0\0\0WEBPVP", b"\xff\xff\xff\xff\0\0\0\0\xff\xff\xff\xff\xff\xff", "image/webp"),
+	(b"\x89PNG\r\n\x1a\n", b"", "image/png"),
+	(b"\xff\xd8\xff", b"", "image/jpeg")
+];
+
+const AUDIO_VIDEO_TYPES: [Row; 7] =
+[
+	(b".snd", b"", "audio/basic"),
+	(b"FORM\0\0\0\0AIFF", b"\xff\xff\xff\xff\0\0\0\0\xff\xff\xff\xff", "audio/aiff"),
+	(b"ID3", b"", "audio/mpeg"),
+	(b"OggS\0", b"", "application/ogg"),
+	(b"MThd\x00\x00\x00\x06", b"", "audio/midi"),
+	(b"RIFF\0\0\0\0AVI ", b"\xff\xff\xff\xff\0\0\0\0\xff\xff\xff\xff", "video/avi"),
+	(b"RIFF\0\0\0\0WAVE", b"\xff\xff\xff\xff\0\0\0\0\xff\xff\xff\xff", "audio/wave")
+];
+
+const FONT_TYPES: [Row; 6] =
+[
+	(
+		b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0LP",
+		b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\xff\xff",
+		"application/vnd.ms-fontobject"
+	),
+	(b"\x00\x01\x00\x00", b"", "font/ttf"),
+	(b"OTTO", b"", "font/otf"),
+	(b"ttcf", b"", "font/collection"),
+	(b"wOFF", b"", "font/woff"),
+	(b"wOF2", b"", "font/woff2")
+];
+
+const ARCHIVE_TYPES: [Row; 3] =
+[
+	(b"\x1f\x8b\x08", b"", "application/x-gzip"),
+	(b"PK\x03\x04", b"", "application/zip"),
+	(b"Rar \x1a\x07\x00", b"", "application/x-rar-compressed")
+];
+
+/// Checks if the input starts with a pattern, after skipping bytes in `ignored`
+fn pattern_match(input: &[u8], pattern: &[u8], mask: &[u8], ignored: &[u8]) -> bool {
+	if input.len() < pattern.len() {
+		return false;
+	}
+	let start = input.iter().position(|x| !ignored.contains(x)).unwrap_or(input.len());
+	let input = &input[start..];
+	input.len() >= pattern.len() && pattern.iter().enumerate().all(|(i, &x)| {
+		let mask = mask.get(i).copied().unwrap_or(0xff);
+		input[i] & mask == x
+	})
+}
+
+fn table_match(input: &[u8], table: &[Row]) -> Option<MIME> {
+	table.iter()
+		.find(|x| pattern_match(input, x.0, x.1, b""))
+		.map(|x| x.2.parse().unwrap())
+}
+
+/// Sniffs an image type
+pub fn image_type(input: &[u8]) -> Option<MIME> {
+	table_match(input, &IMAGE_TYPES)
+}
+
+/// Sniffs an audio or video type
+pub fn audio_video_type(input: &[u8]) -> Option<MIME> {
+	let mimetype = match table_match(input, &AUDIO_VIDEO_TYPES) {
+		Some(x) => return Some(x),
+		None if is_mp4(input) => "video/mp4",
+		None if is_webm(input) => "video/webm",
+		None if is_mp3(input) => "audio/mpeg",
+		None => return None
+	};
+	Some(mimetype.parse().unwrap())
+}
+
+/// Sniffs a font type
+pub fn font_type(input: &[u8]) -> Option<MIME> {
+	table_match(input, &FONT_TYPES)
+}
+
+/// Sniffs an archive type
+pub fn archive_type(input: &[u8]) -> Option<MIME> {
+	table_match(input, &ARCHIVE_TYPES)
+}
+
+/// Checks for an `ftyp` box with an `mp4` brand
+fn is_mp4(input: &[u8]) -> bool {
+	if input.len() < 12 {
+		return false;
+	}
+	let box_size = u32::from_be_bytes([input[0], input[1], input[2], input[3]]) as usize;
+	if input.len() < box_size || box_size & 0x03 != 0 || &input[4..8] != b"ftyp" {
+		return false;
+	}
+	if &input[8..11] == b"mp4" {
+		return true;
+	}
+	// Compatible brands, after the minor version
+	(16..box_size).step_by(4).any(|i| input.get(i..i + 3) == Some(b"mp4"))
+}
+
+/// Checks for an EBML header with a `webm` doc type in its first bytes
+fn is_webm(input: &[u8]) -> bool {
+	if !input.starts_with(b"\x1a\x45\xdf\xa3") {
+		return false;
+	}
+	let mut i = 4;
+	while i < input.len() && i < 38 {
+		if input.get(i..i + 2) == Some(b"\x42\x82") {
+			i += 2;
+			if i >= input.len() {
+				return false;
+			}
+			i += vint_len(input[i]);
+			if i + 4 >= input.len() {
+				return false;
+			}
+			// The doc type may be padded with zeros
+			let start = input[i..].iter().position(|&x| x != 0).map_or(input.len(), |x| i + x);
+			return input[start..].starts_with(b"webm");
+		}
+		i += 1;
+	}
+	false
+}
+
+/// Gets the length of an EBML variable-length integer from its first byte
+fn vint_len(first: u8) -> usize {
+	std::cmp::min(first.leading_zeros() as usize + 1, 8)
+}
+
+/// Bit rates of MPEG-1 layer III and MPEG-2.5 frames, in bit/s
+const MP3_RATES: [u32; 15] = [
+	0, 32000, 40000, 48000, 56000, 64000, 80000, 96000, 112000, 128000, 160000, 192000,
+	224000, 256000, 320000
+];
+const MP25_RATES: [u32; 15] = [
+	0, 8000, 16000, 24000, 32000, 40000, 48000, 56000, 64000, 80000, 96000, 112000,
+	128000, 144000, 160000
+];
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Checks for a valid MP3 frame header at the start of the input
+fn is_mp3_header(input: &[u8]) -> bool {
+	if input.len() < 4 || input[0] != 0xff || input[1] & 0xe0 != 0xe0 {
+		return false;
+	}
+	let layer = (input[1] & 0x06) >> 1;
+	let bitrate = (input[2] & 0xf0) >> 4;
+	let sample_rate = (input[2] & 0x0c) >> 2;
+	layer != 0 && bitrate != 15 && sample_rate != 3
+}
+
+/// Gets the length of the MP3 frame at the start of the input, as the
+/// standard computes it
+fn mp3_frame_len(input: &[u8]) -> usize {
+	let version = (input[1] & 0x18) >> 3;
+	let bitrate_index = ((input[2] & 0xf0) >> 4) as usize;
+	let bitrate = match version & 0x01 {
+		0 => MP25_RATES[bitrate_index],
+		_ => MP3_RATES[bitrate_index]
+	};
+	let sample_rate = SAMPLE_RATES[((input[2] & 0x0c) >> 2) as usize];
+	let padding = (input[2] & 0x02) >> 1;
+	let scale = match version {
+		1 => 72,
+		_ => 144
+	};
+	(scale * bitrate / sample_rate) as usize + padding as usize
+}
+
+/// Checks for two MP3 frames in a row, without an ID3 tag
+fn is_mp3(input: &[u8]) -> bool {
+	if !is_mp3_header(input) {
+		return false;
+	}
+	let len = mp3_frame_len(input);
+	len >= 4 && len <= input.len() && is_mp3_header(&input[len..])
+}
+
+/// Checks for an HTML tag after leading whitespace
+fn is_html(input: &[u8]) -> bool {
+	let start = input.iter().position(|x| !WHITESPACE.contains(x)).unwrap_or(input.len());
+	let input = &input[start..];
+	HTML_TAGS.iter().any(|tag| {
+		let matches = input.len() > tag.len() && tag.iter().zip(input).all(|(&x, &y)| {
+			match x.is_ascii_alphabetic() {
+				true => y & 0xdf == x,
+				false => y == x
+			}
+		});
+		matches && matches!(input[tag.len()], b' ' | b'>')
+	})
+}
+
+/// Checks for binary data bytes: control characters other than whitespace
+/// and escape
+fn has_binary_data(input: &[u8]) -> bool {
+	input.iter().any(|&x| matches!(x, 0x00..=0x08 | 0x0b | 0x0e..=0x1a | 0x1c..=0x1f))
+}
+
+/// Identifies a resource whose type isn't known
+fn unknown_type(input: &[u8], sniff_scriptable: bool) -> MIME {
+	let scriptable = match sniff_scriptable {
+		true if is_html(input) => Some("text/html"),
+		true if pattern_match(input, b"<?xml", b"", WHITESPACE) => Some("text/xml"),
+		true if input.starts_with(b"%PDF-") => Some("application/pdf"),
+		_ => None
+	};
+	let mimetype = scriptable.or_else(|| {
+		// Byte order marks need 4 bytes, like the patterns of the standard
+		if input.starts_with(b"%!PS-Adobe-") {
+			Some("application/postscript")
+		} else if input.len() >= 4 &&
+			(input.starts_with(b"\xfe\xff") || input.starts_with(b"\xff\xfe") || input.starts_with(b"\xef\xbb\xbf")) {
+			Some("text/plain")
+		} else {
+			None
+		}
+	});
+	if let Some(x) = mimetype {
+		return x.parse().unwrap();
+	}
+
+	image_type(input)
+		.or_else(|| audio_video_type(input))
+		.or_else(|| archive_type(input))
+		.unwrap_or_else(|| match has_binary_data(input) {
+			true => mime::APPLICATION_OCTET_STREAM,
+			false => mime::TEXT_PLAIN
+		})
+}
+
+/// Tells text from binary, for resources served with Apache's default type
+///
+/// Binary data isn't sniffed any further, so a resource served as text can
+/// never become an image or a script.
+fn text_or_binary(input: &[u8]) -> MIME {
+	if input.starts_with(b"\xfe\xff") || input.starts_with(b"\xff\xfe") ||
+		input.starts_with(b"\xef\xbb\xbf") || !has_binary_data(input) {
+		return mime::TEXT_PLAIN;
+	}
+	mime::APPLICATION_OCTET_STREAM
+}
+
+/// Checks if the input holds `pattern` at `i`
+fn at(input: &[u8], i: usize, pattern: &[u8]) -> bool {
+	input.get(i..).is_some_and(|x| x.starts_with(pattern))
+}
+
+/// Finds the next `pattern` from `i`, returning the index after it
+fn skip_past(input: &[u8], i: usize, pattern: &[u8]) -> Option<usize> {
+	(i..input.len()).find(|&x| at(input, x, pattern)).map(|x| x + pattern.len())
+}
+
+const RSS_NAMESPACE: &[u8] = b"http://purl.org/rss/1.0/";
+const RDF_NAMESPACE: &[u8] = b"http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Tells an RSS or Atom feed served as `text/html` from HTML
+///
+/// Comments, declarations and processing instructions are skipped, and the
+/// first element decides.
+fn feed_or_html(input: &[u8]) -> Option<&'static str> {
+	let mut i = match input.starts_with(b"\xef\xbb\xbf") {
+		true => 3,
+		false => 0
+	};
+	while i < input.len() {
+		// Skip whitespace up to a tag
+		let tag = input[i..].iter().position(|x| !WHITESPACE.contains(x))?;
+		i += tag;
+		if input[i] != b'<' {
+			return None;
+		}
+		i += 1;
+
+		if at(input, i, b"!--") {
+			i = skip_past(input, i + 3, b"-->")?;
+		} else if at(input, i, b"!") {
+			i = skip_past(input, i + 1, b">")?;
+		} else if at(input, i, b"?") {
+			i = skip_past(input, i + 1, b"?>")?;
+		} else if at(input, i, b"rss") {
+			return Some("application/rss+xml");
+		} else if at(input, i, b"feed") {
+			return Some("application/atom+xml");
+		} else if at(input, i, b"rdf:RDF") {
+			// RSS 1.0 declares both namespaces, in either order
+			let rest = &input[i + 7..];
+			let (after, other) = (0..rest.len()).find_map(|x| {
+				if at(rest, x, RSS_NAMESPACE) {
+					Some( (x + RSS_NAMESPACE.len(), RDF_NAMESPACE) )
+				} else if at(rest, x, RDF_NAMESPACE) {
+					Some( (x + RDF_NAMESPACE.len(), RSS_NAMESPACE) )
+				} else {
+					None
+				}
+			})?;
+			return skip_past(rest, after, other).map(|_| "application/rss+xml");
+		} else {
+			return None;
+		}
+	}
+	None
+}
+
+/// Checks if a type is an XML type
+fn is_xml(mimetype: &MIME) -> bool {
+	mimetype.suffix() == Some(mime::XML) ||
+		*mimetype == mime::TEXT_XML ||
+		mimetype.essence_str() == "application/xml"
+}
+
+/// Computes the type a browser gives a resource
+///
+/// `content_type` is the value of the `Content-Type` header, if any, and
+/// `no_sniff` whether the resource was served with
+/// `X-Content-Type-Options: nosniff` (see `is_nosniff`). Only the first
+/// `RESOURCE_HEADER_LEN` bytes of `bytes` are looked at.
+pub fn from_u8(bytes: &[u8], content_type: Option<&str>, no_sniff: bool) -> MIME {
+	let input = &bytes[..std::cmp::min(bytes.len(), RESOURCE_HEADER_LEN)];
+	let supplied = content_type.and_then(|x| x.trim().parse::<MIME>().ok());
+	let supplied = match supplied {
+		Some(x) if !matches!(x.essence_str(), "unknown/unknown" | "application/unknown" | "*/*") => x,
+		_ => return unknown_type(input, !no_sniff)
+	};
+
+	if no_sniff {
+		return supplied;
+	}
+	if content_type.is_some_and(|x| APACHE_BUG_TYPES.contains(&x)) {
+		return text_or_binary(input);
+	}
+	if is_xml(&supplied) {
+		return supplied;
+	}
+	if supplied.essence_str() == "text/html" {
+		return match feed_or_html(input) {
+			Some(x) => x.parse().unwrap(),
+			None => supplied
+		};
+	}
+
+	let kind = supplied.type_();
+	let sniffed = if kind == mime::IMAGE {
+		image_type(input)
+	} else if kind == mime::AUDIO || kind == mime::VIDEO || supplied.essence_str() == "application/ogg" {
+		audio_video_type(input)
+	} else {
+		None
+	};
+	sniffed.unwrap_or(supplied)
+}
+
+/// Computes the type a browser gives a resource
+///
+/// Returns None if the input can't be read.
+pub fn from_probe(probe: &Probe, content_type: Option<&str>, no_sniff: bool) -> Option<MIME> {
+	probe.bytes(RESOURCE_HEADER_LEN).map(|b| from_u8(b, content_type, no_sniff))
+}
+
+/// Computes the type a browser gives a file
+///
+/// Returns None if the file can't be read.
+pub fn from_filepath(filepath: &Path, content_type: Option<&str>, no_sniff: bool) -> Option<MIME> {
+	from_probe(&Probe::from_filepath(filepath), content_type, no_sniff)
+}
+
+/// Checks if an `X-Content-Type-Options` header value turns sniffing off
+///
+/// Only the first of a list of values counts, and case is ignored.
+pub fn is_nosniff(header: &str) -> bool {
+	header.split(',')
+		.next()
+		.is_some_and(|x| x.trim_matches(|c| c == ' ' || c == '\t').eq_ignore_ascii_case("nosniff"))
+}
@@ -0,0 +1,13 @@
+use crate::MIME;
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	mimetype == "application/x-tar" && super::is_tar(b)
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	mimetype == "application/x-tar" &&
+	probe.bytes(super::BLOCK_LEN).is_some_and(super::is_tar)
+}
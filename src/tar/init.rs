@@ -0,0 +1,18 @@
+use fnv::FnvHashMap;
+use crate::MIME;
+
+/// Get list of supported MIME types
+pub fn get_supported() -> Vec<MIME> {
+	vec!["application/x-tar".parse().unwrap()]
+}
+
+/// Get list of parent -> child subclass links
+///
+/// Types based on tar come from the magic database.
+pub fn get_subclasses() -> Vec<(MIME, MIME)> {
+	Vec::new()
+}
+
+pub fn get_aliaslist() -> FnvHashMap<MIME, MIME> {
+	FnvHashMap::default()
+}
@@ -0,0 +1,240 @@
+//! Recognize tar archives and list what's in them
+//!
+//! A tar archive has no magic number at its start. POSIX and GNU archives
+//! have `ustar` at offset 257, but old V7 archives don't have even that.
+//! What every tar header does have is a checksum over its 512 bytes, so the
+//! tar checker detects `application/x-tar` by checking the first header's
+//! checksum instead. Only an input cut off before the end of that header
+//! falls back to looking for `ustar`.
+//!
+//! `from_probe` walks the headers to list the first members of an archive,
+//! and detects the type of each file from the start of its data, without
+//! extracting anything. GNU long names and POSIX `path` records are followed.
+//!
+//! # Examples
+//! ```rust
+//! use std::path::Path;
+//! use tree_magic::tar::EntryKind;
+//!
+//! let members = tree_magic::tar::from_filepath(Path::new("tests/application/x-tar"), 10).unwrap();
+//! assert_eq!(members.len(), 1);
+//! assert_eq!(members[0].name, "plain");
+//! assert_eq!(members[0].kind, EntryKind::File);
+//! assert_eq!(members[0].mimetype.clone().unwrap(), "text/plain");
+//! ```
+
+use std::convert::TryFrom;
+use std::path::Path;
+use crate::probe::{Probe, HEADER_LEN};
+use crate::MIME;
+
+pub mod check;
+pub mod init;
+
+/// Length of a tar header, and of the blocks member data is padded to
+pub const BLOCK_LEN: usize = 512;
+
+/// Most bytes of a GNU long name or POSIX extended header that are read
+///
+/// The header gives their length, which a damaged or hostile archive can
+/// set to anything. Names are far shorter than this in practice.
+const LONG_NAME_LEN: usize = 4096;
+
+/// What kind of entry a tar member is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+	File,
+	Directory,
+	Symlink,
+	/// A link to an earlier member of the archive
+	HardLink,
+	/// Devices, FIFOs and vendor extensions, by their type flag
+	Other(u8)
+}
+
+/// One member of a tar archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+	pub name: String,
+	/// Length of the member's data
+	pub size: u64,
+	pub kind: EntryKind,
+	/// Type detected from the start of the data, for files that have any
+	pub mimetype: Option<MIME>
+}
+
+/// Parses a numeric header field: octal digits padded with spaces or NULs,
+/// or the GNU base-256 encoding for large values
+fn parse_number(field: &[u8]) -> Option<u64> {
+	if field.first().is_some_and(|x| x & 0x80 != 0) {
+		return field[1..].iter()
+			.try_fold(u64::from(field[0] & 0x7f), |n, &x| n.checked_mul(256)?.checked_add(u64::from(x)));
+	}
+
+	let field = match field.iter().position(|&x| x == 0) {
+		Some(i) => &field[..i],
+		None => field
+	};
+	let digits = std::str::from_utf8(field).ok()?.trim_matches(' ');
+	if digits.is_empty() {
+		return None;
+	}
+	u64::from_str_radix(digits, 8).ok()
+}
+
+/// Checks if a block is a tar header, by its checksum
+///
+/// The checksum is the sum of the header's bytes with the checksum field
+/// counted as spaces. Some old archivers summed signed bytes, so that sum
+/// is accepted too.
+pub fn is_header(block: &[u8]) -> bool {
+	let block = match block.get(..BLOCK_LEN) {
+		Some(x) => x,
+		None => return false
+	};
+	// An empty name can't start an archive, and rules out the all-zero end blocks
+	if block[0] == 0 {
+		return false;
+	}
+	let expected = match parse_number(&block[148..156]) {
+		Some(x) => x,
+		None => return false
+	};
+
+	let field = 148..156;
+	let (unsigned, signed) = block.iter().enumerate()
+		.map(|(i, &x)| match field.contains(&i) {
+			true => b' ',
+			false => x
+		})
+		.fold((0u64, 0i64), |(u, s), x| (u + u64::from(x), s + i64::from(x as i8)));
+	expected == unsigned || i64::try_from(expected) == Ok(signed)
+}
+
+/// Checks if the start of an input is a tar archive
+///
+/// A whole first header must have a valid checksum. An input too short to
+/// hold one is taken as a tar archive if it has `ustar` at offset 257.
+pub fn is_tar(b: &[u8]) -> bool {
+	match b.len() < BLOCK_LEN {
+		true => b.get(257..262) == Some(&b"ustar"[..]),
+		false => is_header(b)
+	}
+}
+
+/// Reads a NUL-terminated string field
+fn field_str(field: &[u8]) -> String {
+	let end = field.iter().position(|&x| x == 0).unwrap_or(field.len());
+	String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Finds the `path` record in POSIX extended header data
+///
+/// Records look like `<length> <key>=<value>\n`, where the length counts
+/// the whole record.
+fn pax_path(data: &[u8]) -> Option<String> {
+	let mut rest = data;
+	while !rest.is_empty() {
+		let space = rest.iter().position(|&x| x == b' ')?;
+		let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+		let record = rest.get(space + 1..len)?;
+		let record = record.strip_suffix(b"\n").unwrap_or(record);
+		if let Some(value) = record.strip_prefix(b"path=") {
+			return Some(String::from_utf8_lossy(value).into_owned());
+		}
+		rest = &rest[len..];
+	}
+	None
+}
+
+/// Lists up to `limit` members of a probed tar archive
+///
+/// Returns None if the input doesn't start with a tar header. A damaged or
+/// cut off archive lists the members before the damage.
+pub fn from_probe(probe: &Probe, limit: usize) -> Option<Vec<Member>> {
+	if !probe.bytes(BLOCK_LEN).is_some_and(is_header) {
+		return None;
+	}
+
+	let mut members = Vec::<Member>::new();
+	let mut long_name: Option<String> = None;
+	let mut offset = 0usize;
+	while members.len() < limit {
+		let header = match probe.bytes_at(offset, BLOCK_LEN) {
+			Some(x) if is_header(&x) => x,
+			_ => break
+		};
+		let size = match parse_number(&header[124..136]) {
+			Some(x) => x,
+			None => break
+		};
+		let data_offset = offset + BLOCK_LEN;
+		let data_len = usize::try_from(size).ok();
+		let typeflag = header[156];
+
+		match typeflag {
+			// The name of the next member, stored as data
+			b'L' | b'x' => {
+				let data = data_len.and_then(|len| probe.bytes_at(data_offset, std::cmp::min(len, LONG_NAME_LEN)));
+				long_name = match (typeflag, data.as_deref()) {
+					(b'L', Some(x)) => Some(field_str(x)),
+					(_, Some(x)) => pax_path(x).or(long_name),
+					(_, None) => break
+				};
+			}
+			// Other metadata that belongs to the next member, or the whole archive
+			b'K' | b'g' => {}
+			_ => {
+				let kind = match typeflag {
+					b'0' | b'\0' | b'7' => EntryKind::File,
+					b'1' => EntryKind::HardLink,
+					b'2' => EntryKind::Symlink,
+					b'5' => EntryKind::Directory,
+					x => EntryKind::Other(x)
+				};
+				let name = long_name.take().unwrap_or_else(|| {
+					let name = field_str(&header[..100]);
+					match (&header[257..263], field_str(&header[345..500])) {
+						// POSIX archives split long names into a prefix and a name
+						(b"ustar\0", prefix) if !prefix.is_empty() => format!("{}/{}", prefix, name),
+						_ => name
+					}
+				});
+				let mimetype = match kind {
+					EntryKind::File if size > 0 => {
+						let len = std::cmp::min(size, HEADER_LEN as u64) as usize;
+						probe.bytes_at(data_offset, len).map(|x| crate::from_u8(&x))
+					}
+					_ => None
+				};
+				members.push(Member{name, size, kind, mimetype});
+			}
+		}
+
+		// Data is padded to whole blocks
+		let blocks = match data_len.and_then(|x| x.checked_add(BLOCK_LEN - 1)) {
+			Some(x) => x / BLOCK_LEN,
+			None => break
+		};
+		offset = match blocks.checked_mul(BLOCK_LEN).and_then(|x| x.checked_add(data_offset)) {
+			Some(x) => x,
+			None => break
+		};
+	}
+
+	Some(members)
+}
+
+/// Lists up to `limit` members of a tar archive in a bytestream
+///
+/// Returns None if the bytestream doesn't start with a tar header.
+pub fn from_u8(bytes: &[u8], limit: usize) -> Option<Vec<Member>> {
+	from_probe(&Probe::from_u8(bytes), limit)
+}
+
+/// Lists up to `limit` members of a tar archive
+///
+/// Returns None if the file can't be read, or doesn't start with a tar header.
+pub fn from_filepath(filepath: &Path, limit: usize) -> Option<Vec<Member>> {
+	from_probe(&Probe::from_filepath(filepath), limit)
+}
@@ -0,0 +1,42 @@
+//! Tables of the types a format checker can tell apart
+//!
+//! Checkers that parse a container (ISO media, EBML, Ogg, ZIP...) work out
+//! the most specific type they can, then match that type and its ancestors.
+//! Each lists its types in a `TypeTable`, which feeds `get_supported` and
+//! `get_subclasses`. The ancestor check then walks the type graph built from
+//! them, like every other subclass lookup.
+
+use petgraph::algo::has_path_connecting;
+use crate::{MIME, TYPE};
+
+/// Types with their parents, which may be types of other checkers
+pub type TypeTable = [(&'static str, &'static [&'static str])];
+
+/// Get list of supported MIME types
+pub fn supported(table: &TypeTable) -> Vec<MIME> {
+	table.iter().map(|x| x.0.parse().unwrap()).collect()
+}
+
+/// Get list of parent -> child subclass links
+pub fn subclasses(table: &TypeTable) -> Vec<(MIME, MIME)> {
+	table.iter()
+		.flat_map(|&(child, parents)| parents.iter().map(move |&parent| (parent, child)))
+		.map(|(parent, child)| (parent.parse().unwrap(), child.parse().unwrap()))
+		.collect()
+}
+
+/// Checks if a type is `detected` or one of its ancestors
+pub fn is_ancestor_or_self(mimetype: &MIME, detected: &str) -> bool {
+	if mimetype.essence_str() == detected {
+		return true;
+	}
+
+	let detected: MIME = match detected.parse() {
+		Ok(x) => x,
+		Err(_) => return false
+	};
+	match (TYPE.hash.get(mimetype), TYPE.hash.get(&detected)) {
+		(Some(&parent), Some(&child)) => has_path_connecting(&TYPE.graph, parent, child, None),
+		_ => false
+	}
+}
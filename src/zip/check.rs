@@ -0,0 +1,15 @@
+use crate::{typetable, MIME};
+use crate::probe::Probe;
+
+pub fn from_u8(b: &[u8], mimetype: MIME) -> bool
+{
+	from_probe(&Probe::from_u8(b), mimetype)
+}
+
+pub fn from_probe(probe: &Probe, mimetype: MIME) -> bool
+{
+	match probe.zip_entries().and_then(super::detect) {
+		Some(x) => typetable::is_ancestor_or_self(&mimetype, x),
+		None => false
+	}
+}
@@ -0,0 +1,48 @@
+//! Tells ZIP-based formats apart by the names in the central directory
+//!
+//! Office Open XML documents, Java archives and Android packages have no
+//! magic of their own, so the magic rules only find `application/zip`.
+//! Their member names give them away: a Word document has `word/` parts
+//! next to `[Content_Types].xml`, and a Java archive has a manifest.
+//! The directory is read once per file through `Probe::zip_entries`.
+
+pub mod check;
+
+use crate::typetable::TypeTable;
+
+/// Types handled here, with their parents
+pub const TYPES: &TypeTable = &[
+	("application/vnd.openxmlformats-officedocument.wordprocessingml.document",   &["application/zip"]),
+	("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",         &["application/zip"]),
+	("application/vnd.openxmlformats-officedocument.presentationml.presentation", &["application/zip"]),
+	("application/x-java-archive",                                               &["application/zip"]),
+	("application/vnd.android.package-archive",                                  &["application/x-java-archive"])
+];
+
+/// Office Open XML formats by the folder of their main part
+const OOXML: [(&str, &str); 3] =
+[
+	("word/", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+	("xl/",   "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+	("ppt/",  "application/vnd.openxmlformats-officedocument.presentationml.presentation")
+];
+
+/// Finds the most specific type for the names in a ZIP directory
+fn detect(names: &[String]) -> Option<&'static str> {
+	let has = |name: &str| names.iter().any(|x| x == name);
+
+	if has("[Content_Types].xml") {
+		let found = OOXML.iter().find(|x| names.iter().any(|y| y.starts_with(x.0)));
+		if let Some(&(_, mimetype)) = found {
+			return Some(mimetype);
+		}
+	}
+	// Packages signed with only the newer schemes have no JAR manifest
+	if has("AndroidManifest.xml") && (has("classes.dex") || has("resources.arsc")) {
+		return Some("application/vnd.android.package-archive");
+	}
+	if has("META-INF/MANIFEST.MF") {
+		return Some("application/x-java-archive");
+	}
+	None
+}
@@ -0,0 +1,60 @@
+mod alloc {
+    extern crate tree_magic;
+
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts allocations made by the current thread
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|x| x.set(x.get() + 1));
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Runs `f` and returns the number of allocations it made
+    fn allocations<F: FnOnce() -> bool>(f: F) -> (bool, usize) {
+        let before = ALLOCATIONS.with(|x| x.get());
+        let result = f();
+        (result, ALLOCATIONS.with(|x| x.get()) - before)
+    }
+
+    #[test]
+    fn match_u8_allocation_free() {
+        let input: &[u8] = include_bytes!("image/gif");
+        // Load the database before counting
+        assert!(tree_magic::match_u8("image/gif".parse().unwrap(), input));
+
+        // MIMEs are parsed up front, since parsing one allocates
+        let gif = "image/gif".parse().unwrap();
+        let png = "image/png".parse().unwrap();
+        let text = "text/plain".parse().unwrap();
+        let zip = "application/zip".parse().unwrap();
+        let html = "text/html".parse().unwrap();
+
+        assert_eq!(allocations(|| tree_magic::match_u8(gif, input)), (true, 0));
+        assert_eq!(allocations(|| tree_magic::match_u8(png, input)), (false, 0));
+        assert_eq!(allocations(|| tree_magic::match_u8(text, input)), (false, 0));
+        assert_eq!(
+            allocations(|| tree_magic::match_u8(zip, include_bytes!("application/zip"))),
+            (true, 0)
+        );
+
+        // Region rules are scanned without copying the region
+        let mut page = b"\n\n  <!DOCTYPE html>\n<html>".to_vec();
+        page.resize(512, b' ');
+        assert_eq!(allocations(|| tree_magic::match_u8(html, &page)), (true, 0));
+    }
+}
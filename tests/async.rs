@@ -0,0 +1,134 @@
+#![cfg(feature = "async")]
+
+mod asynchronous {
+
+    extern crate tree_magic;
+
+    use std::io;
+    use std::path::Path;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tree_magic::asynchronous::{from_async_reader, from_path_async};
+
+    /// An in-memory reader that hands out a few bytes per read
+    struct Trickle {
+        data: &'static [u8],
+        chunk: usize
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            let n = self.chunk.min(self.data.len()).min(buf.remaining());
+            buf.put_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// A reader that always fails
+    struct Broken;
+
+    impl AsyncRead for Broken {
+        fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::Error::other("broken")))
+        }
+    }
+
+    #[tokio::test]
+    async fn reader_gif() {
+        let input: &[u8] = include_bytes!("image/gif");
+        assert_eq!(from_async_reader(input).await.unwrap(), "image/gif");
+    }
+
+    #[tokio::test]
+    async fn reader_short_reads() {
+        let reader = Trickle{data: include_bytes!("application/x-tar"), chunk: 7};
+        assert_eq!(from_async_reader(reader).await.unwrap(), "application/x-tar");
+    }
+
+    #[tokio::test]
+    // Only the prefix is read, so an endless reader still finishes
+    async fn reader_endless() {
+        assert_eq!(from_async_reader(tokio::io::repeat(b'a')).await.unwrap(), "text/plain");
+    }
+
+    #[tokio::test]
+    async fn reader_error() {
+        assert!(from_async_reader(Broken).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reader_same_as_from_u8() {
+        let input: &[u8] = include_bytes!("application/zip");
+        assert_eq!(from_async_reader(input).await.unwrap(), tree_magic::from_u8(input));
+    }
+
+    #[tokio::test]
+    // A full header is known to be cut off, as with a blocking reader
+    async fn reader_same_as_from_reader() {
+        let input = vec![b'a'; 3 * tree_magic::probe::HEADER_LEN];
+        let probe = tree_magic::probe::Probe::from_reader(input.as_slice()).unwrap();
+        assert_eq!(
+            from_async_reader(input.as_slice()).await.ok(),
+            tree_magic::from_probe(&probe)
+        );
+    }
+
+    #[tokio::test]
+    async fn path_png() {
+        assert_eq!(from_path_async("tests/image/png").await.unwrap(), "image/png");
+    }
+
+    #[tokio::test]
+    // Parts past the header are read as the checkers ask for them
+    async fn path_same_as_from_filepath() {
+        for name in &[
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "application/vnd.android.package-archive",
+            "application/x-mozilla-places+sqlite3",
+            "audio/x-matroska",
+            "video/x-msvideo-opendml",
+            "image/png"
+        ] {
+            let path = Path::new("tests").join(name);
+            assert_eq!(from_path_async(&path).await.ok(), tree_magic::from_filepath(&path));
+        }
+    }
+
+    #[tokio::test]
+    // An ID3 tag as long as a cover picture puts the first frame far past the header
+    async fn path_large_tag() {
+        let mp3 = include_bytes!("audio/mpeg");
+        let mut b = mp3[..2048].to_vec();
+        b[6..10].copy_from_slice(&[0, 6, 13, 22]);
+        b.resize(100_000, 0);
+        b.extend_from_slice(&mp3[2048..]);
+
+        let path = std::env::temp_dir().join(format!("tree_magic-async-{}", std::process::id()));
+        std::fs::write(&path, &b).unwrap();
+        let result = from_path_async(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), "audio/mpeg");
+    }
+
+    #[tokio::test]
+    // A reader can't seek to the central directory at the end
+    async fn reader_header_only() {
+        let name = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+        let path = Path::new("tests").join(name);
+        let input = std::fs::read(&path).unwrap();
+        assert_eq!(from_async_reader(input.as_slice()).await.unwrap(), "application/zip");
+        assert_eq!(from_path_async(&path).await.unwrap(), name);
+    }
+
+    #[tokio::test]
+    async fn path_directory() {
+        assert_eq!(from_path_async("tests").await.unwrap(), "inode/directory");
+    }
+
+    #[tokio::test]
+    async fn path_missing() {
+        assert!(from_path_async("this/file/does/not/exist").await.is_err());
+    }
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "compressed")]
+
+mod compressed {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use tree_magic::compressed::Compressed;
+    use tree_magic::probe::Probe;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Checks what a compressed test file holds
+    fn check(path: &str, container: &str, inner: &str, compound: Option<&str>) {
+        let result = tree_magic::compressed::from_filepath(Path::new(path)).unwrap();
+        assert_eq!(result, Compressed{
+            container: convmime!(container),
+            inner: Some(convmime!(inner)),
+            compound: compound.map(|x| x.parse().unwrap())
+        });
+    }
+
+    #[test]
+    fn gzip_tar() {
+        check(
+            "tests/application/x-compressed-tar",
+            "application/gzip", "application/x-tar", Some("application/x-compressed-tar")
+        );
+    }
+
+    #[test]
+    fn bzip2_tar() {
+        check(
+            "tests/application/x-bzip-compressed-tar",
+            "application/x-bzip", "application/x-tar", Some("application/x-bzip-compressed-tar")
+        );
+    }
+
+    #[test]
+    fn xz_tar() {
+        check(
+            "tests/application/x-xz-compressed-tar",
+            "application/x-xz", "application/x-tar", Some("application/x-xz-compressed-tar")
+        );
+    }
+
+    #[test]
+    fn zstd_tar() {
+        check(
+            "tests/application/x-zstd-compressed-tar",
+            "application/zstd", "application/x-tar", Some("application/x-zstd-compressed-tar")
+        );
+    }
+
+    #[test]
+    fn lz4_tar() {
+        check(
+            "tests/application/x-lz4-compressed-tar",
+            "application/x-lz4", "application/x-tar", Some("application/x-lz4-compressed-tar")
+        );
+    }
+
+    #[test]
+    fn gzip_svg() {
+        check(
+            "tests/image/svg+xml-compressed",
+            "application/gzip", "image/svg+xml", Some("image/svg+xml-compressed")
+        );
+    }
+
+    #[test]
+    // Contents without a compound type only get the container
+    fn gzip_text() {
+        check("tests/application/gzip", "application/gzip", "text/plain", None);
+    }
+
+    #[test]
+    fn not_compressed() {
+        assert_eq!(tree_magic::compressed::from_u8(include_bytes!("application/x-tar")), None);
+        assert_eq!(tree_magic::compressed::from_filepath(Path::new("tests")), None);
+        assert_eq!(tree_magic::compressed::from_filepath(Path::new("tests/missing")), None);
+    }
+
+    #[test]
+    // A gzip header with nothing after it
+    fn truncated() {
+        let input = &include_bytes!("application/x-compressed-tar")[..10];
+        let result = tree_magic::compressed::from_u8(input).unwrap();
+        assert_eq!(result.container, "application/gzip");
+        assert_eq!(result.inner, None);
+        assert_eq!(result.compound, None);
+        assert_eq!(tree_magic::from_u8(input), "application/gzip");
+    }
+
+    #[test]
+    // The start of a gzip stream is enough, if it holds the whole tar header
+    fn cut_off() {
+        let input = include_bytes!("application/x-compressed-tar");
+        let result = tree_magic::compressed::from_u8(&input[..input.len() - 40]).unwrap();
+        assert_eq!(result.inner.unwrap(), "application/x-tar");
+    }
+
+    #[test]
+    fn from_u8() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-compressed-tar")),
+            "application/x-compressed-tar"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-zstd-compressed-tar")),
+            "application/x-zstd-compressed-tar"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/gzip")),
+            "application/gzip"
+        );
+    }
+
+    #[test]
+    fn from_filepath() {
+        for (path, expected) in &[
+            ("tests/application/x-bzip-compressed-tar", "application/x-bzip-compressed-tar"),
+            ("tests/application/x-xz-compressed-tar", "application/x-xz-compressed-tar"),
+            ("tests/application/x-lz4-compressed-tar", "application/x-lz4-compressed-tar"),
+            ("tests/image/svg+xml-compressed", "image/svg+xml-compressed")
+        ] {
+            assert_eq!(tree_magic::from_filepath(Path::new(path)).unwrap(), *expected);
+        }
+    }
+
+    #[test]
+    fn match_u8() {
+        let input = include_bytes!("application/x-compressed-tar");
+        assert!(tree_magic::match_u8(convmime!("application/x-compressed-tar"), input));
+        assert!(tree_magic::match_u8(convmime!("application/gzip"), input));
+        assert!(!tree_magic::match_u8(convmime!("application/x-tar"), input));
+        assert!(!tree_magic::match_u8(
+            convmime!("application/x-compressed-tar"),
+            include_bytes!("application/gzip")
+        ));
+    }
+
+    #[test]
+    // The contents are decompressed once and kept in the probe
+    fn probe_cache() {
+        let probe = Probe::from_filepath(Path::new("tests/application/x-compressed-tar"));
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("application/x-compressed-tar")));
+        assert_eq!(probe.decompressed().unwrap(), &include_bytes!("application/x-tar")[..]);
+    }
+}
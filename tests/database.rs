@@ -0,0 +1,119 @@
+mod database {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    #[test]
+    fn sqlite() {
+        let b = include_bytes!("application/vnd.sqlite3");
+        assert_eq!(tree_magic::from_u8(b), "application/vnd.sqlite3");
+        // The old name is an alias
+        assert!(tree_magic::match_u8(convmime!("application/x-sqlite3"), b));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut b = include_bytes!("application/vnd.sqlite3").to_vec();
+        // A page size that isn't a power of two
+        b[16..18].copy_from_slice(&[0x03, 0x00]);
+        assert!(!tree_magic::match_u8(convmime!("application/vnd.sqlite3"), &b));
+
+        let mut b = include_bytes!("application/vnd.sqlite3").to_vec();
+        b[18] = 7;
+        assert!(!tree_magic::match_u8(convmime!("application/vnd.sqlite3"), &b));
+
+        // Just the magic string
+        let mut b = b"SQLite format 3\0".to_vec();
+        b.resize(100, 0);
+        assert!(!tree_magic::match_u8(convmime!("application/vnd.sqlite3"), &b));
+    }
+
+    #[test]
+    fn application_id() {
+        let b = include_bytes!("application/geopackage+sqlite3");
+        assert_eq!(tree_magic::from_u8(b), "application/geopackage+sqlite3");
+        assert!(tree_magic::match_u8(convmime!("application/vnd.sqlite3"), b));
+    }
+
+    #[test]
+    fn schema() {
+        let b = include_bytes!("application/x-mozilla-places+sqlite3");
+        assert_eq!(tree_magic::from_u8(b), "application/x-mozilla-places+sqlite3");
+        assert!(tree_magic::match_u8(convmime!("application/vnd.sqlite3"), b));
+        assert!(!tree_magic::match_u8(convmime!("application/geopackage+sqlite3"), b));
+
+        // Without its application_id, a GeoPackage is known by its tables
+        let mut b = include_bytes!("application/geopackage+sqlite3").to_vec();
+        b[68..72].copy_from_slice(&[0; 4]);
+        assert_eq!(tree_magic::from_u8(&b), "application/geopackage+sqlite3");
+    }
+
+    #[test]
+    // The header is there, but the schema is cut off
+    fn truncated() {
+        let b = include_bytes!("application/x-mozilla-places+sqlite3");
+        assert_eq!(tree_magic::from_u8(&b[..120]), "application/vnd.sqlite3");
+    }
+
+    #[test]
+    // The places database has 32 KiB pages, so its schema is past the header
+    fn from_filepath() {
+        for (path, expected) in &[
+            ("tests/application/vnd.sqlite3", "application/vnd.sqlite3"),
+            ("tests/application/geopackage+sqlite3", "application/geopackage+sqlite3"),
+            ("tests/application/x-mozilla-places+sqlite3", "application/x-mozilla-places+sqlite3")
+        ] {
+            assert_eq!(tree_magic::from_filepath(Path::new(path)), Some(convmime!(expected)));
+        }
+    }
+
+    #[test]
+    fn berkeley_db() {
+        // A B-tree database written on a little-endian machine
+        let mut b = vec![0; 12];
+        b.extend_from_slice(&0x00053162u32.to_le_bytes());
+        b.extend_from_slice(&9u32.to_le_bytes());
+        b.resize(512, 0);
+        assert_eq!(tree_magic::from_u8(&b), "application/x-berkeley-db");
+
+        // A hash database from a big-endian one
+        let mut b = vec![0; 12];
+        b.extend_from_slice(&0x00061561u32.to_be_bytes());
+        b.extend_from_slice(&9u32.to_be_bytes());
+        b.resize(512, 0);
+        assert_eq!(tree_magic::from_u8(&b), "application/x-berkeley-db");
+    }
+
+    #[test]
+    fn lmdb() {
+        let mut b = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x08, 0, 0, 0, 0, 0];
+        b.extend_from_slice(&0xbeefc0deu32.to_le_bytes());
+        b.extend_from_slice(&1u32.to_le_bytes());
+        b.resize(4096, 0);
+        assert_eq!(tree_magic::from_u8(&b), "application/x-lmdb");
+    }
+
+    #[test]
+    fn duckdb() {
+        let mut b = vec![0x5a; 8];
+        b.extend_from_slice(b"DUCK");
+        b.extend_from_slice(&64u64.to_le_bytes());
+        b.resize(4096, 0);
+        assert_eq!(tree_magic::from_u8(&b), "application/x-duckdb");
+    }
+
+    #[test]
+    fn metadata() {
+        let gpkg = convmime!("application/geopackage+sqlite3");
+        assert_eq!(tree_magic::metadata::preferred_extension(gpkg), Some("gpkg".to_string()));
+        assert!(tree_magic::metadata::is_subclass(
+            convmime!("application/geopackage+sqlite3"),
+            convmime!("application/vnd.sqlite3")
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+mod ebml {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Finds an element ID in a test file
+    fn find(b: &[u8], id: &[u8]) -> usize {
+        b.windows(id.len()).position(|x| x == id).unwrap()
+    }
+
+    #[test]
+    fn webm() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/webm")),
+            "video/webm"
+        );
+        // The segment's length is unknown in live recordings
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/webm")),
+            "audio/webm"
+        );
+    }
+
+    #[test]
+    fn matroska() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-matroska")),
+            "video/x-matroska"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/x-matroska")),
+            "audio/x-matroska"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-matroska-3d")),
+            "video/x-matroska-3d"
+        );
+
+        // A mono stereo mode isn't 3D
+        let mut b = include_bytes!("video/x-matroska-3d").to_vec();
+        let mode = find(&b, &[0x53, 0xb8, 0x81]) + 3;
+        b[mode] = 0;
+        assert_eq!(tree_magic::from_u8(&b), "video/x-matroska");
+    }
+
+    #[test]
+    // Without tracks, the DocType is all there is to go on
+    fn no_tracks() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-matroska")),
+            "application/x-matroska"
+        );
+
+        let b = include_bytes!("video/x-matroska");
+        assert_eq!(tree_magic::from_u8(&b[..find(b, &[0x18, 0x53, 0x80, 0x67])]), "application/x-matroska");
+        let b = include_bytes!("video/webm");
+        assert_eq!(tree_magic::from_u8(&b[..find(b, &[0x18, 0x53, 0x80, 0x67])]), "video/webm");
+    }
+
+    #[test]
+    // A cut-off Tracks element might be missing a video track
+    fn truncated() {
+        let b = include_bytes!("audio/webm");
+        let end = find(b, &[0x16, 0x54, 0xae, 0x6b]) + 10;
+        assert_eq!(tree_magic::from_u8(&b[..end]), "video/webm");
+        assert!(!tree_magic::match_u8(convmime!("application/x-matroska"), &b[..10]));
+    }
+
+    #[test]
+    fn other_doc_types() {
+        let mut b = include_bytes!("video/webm").to_vec();
+        let doc_type = find(&b, b"webm");
+        b[doc_type..doc_type + 4].copy_from_slice(b"wxyz");
+        assert_eq!(tree_magic::from_u8(&b), "application/octet-stream");
+        assert!(!tree_magic::match_u8(convmime!("application/x-matroska"), b"\x1a\x45\xdf\xa3"));
+    }
+
+    #[test]
+    fn match_parents() {
+        let b = include_bytes!("audio/x-matroska");
+        assert!(tree_magic::match_u8(convmime!("audio/x-matroska"), b));
+        assert!(tree_magic::match_u8(convmime!("application/x-matroska"), b));
+        assert!(!tree_magic::match_u8(convmime!("video/x-matroska"), b));
+        assert!(!tree_magic::match_u8(convmime!("video/webm"), b));
+    }
+
+    #[test]
+    // The tracks of audio/x-matroska are after a large void element, past the header
+    fn from_filepath() {
+        for name in &["audio/x-matroska", "video/x-matroska", "video/webm", "audio/webm"] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+}
@@ -0,0 +1,220 @@
+mod executable {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Builds a 64-bit big-endian ELF header with the given program header types,
+    /// as the test machine can't build one
+    fn elf64_be(e_type: u16, program_headers: &[u32]) -> Vec<u8> {
+        let mut b = vec![0x7f, b'E', b'L', b'F', 2, 2, 1, 0];
+        b.resize(16, 0);
+        b.extend_from_slice(&e_type.to_be_bytes());
+        b.resize(32, 0);
+        // e_phoff
+        b.extend_from_slice(&64u64.to_be_bytes());
+        b.resize(54, 0);
+        b.extend_from_slice(&56u16.to_be_bytes());
+        b.extend_from_slice(&(program_headers.len() as u16).to_be_bytes());
+        b.resize(64, 0);
+        for &x in program_headers {
+            let start = b.len();
+            b.extend_from_slice(&x.to_be_bytes());
+            b.resize(start + 56, 0);
+        }
+        b
+    }
+
+    /// Builds a 32-bit little-endian ELF header
+    fn elf32(e_type: u16) -> Vec<u8> {
+        let mut b = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        b.resize(16, 0);
+        b.extend_from_slice(&e_type.to_le_bytes());
+        b.resize(52, 0);
+        b
+    }
+
+    /// Builds a Mach-O header
+    fn mach_o_header(magic: [u8; 4], big_endian: bool, filetype: u32) -> Vec<u8> {
+        let mut b = magic.to_vec();
+        b.resize(12, 0);
+        b.extend_from_slice(&if big_endian {filetype.to_be_bytes()} else {filetype.to_le_bytes()});
+        b.resize(32, 0);
+        b
+    }
+
+    /// Builds a universal binary with one slice
+    fn fat(slice: &[u8], offset: usize) -> Vec<u8> {
+        let mut b = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 1];
+        b.extend_from_slice(&[0x01, 0, 0, 0x07, 0, 0, 0, 3]);
+        b.extend_from_slice(&(offset as u32).to_be_bytes());
+        b.extend_from_slice(&(slice.len() as u32).to_be_bytes());
+        b.extend_from_slice(&12u32.to_be_bytes());
+        b.resize(offset, 0);
+        b.extend_from_slice(slice);
+        b
+    }
+
+    /// Offset of the PE header in the test executable
+    fn pe_offset(b: &[u8]) -> usize {
+        u32::from_le_bytes([b[0x3c], b[0x3d], b[0x3e], b[0x3f]]) as usize
+    }
+
+    /// Gets the test PE executable, marked as a DLL
+    fn dll() -> Vec<u8> {
+        let mut b = include_bytes!("application/vnd.microsoft.portable-executable").to_vec();
+        let characteristics = pe_offset(&b) + 22;
+        b[characteristics + 1] |= 0x20;
+        b
+    }
+
+    #[test]
+    fn elf() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-object")),
+            "application/x-object"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-sharedlib")),
+            "application/x-sharedlib"
+        );
+        // Shared objects with an interpreter are position-independent executables
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/x-executable")),
+            "application/x-executable"
+        );
+
+        // The type in the header is all that tells these apart
+        let mut b = include_bytes!("application/x-executable").to_vec();
+        b[16] = 2;
+        assert_eq!(tree_magic::from_u8(&b), "application/x-executable");
+        b[16] = 4;
+        assert_eq!(tree_magic::from_u8(&b), "application/x-core");
+    }
+
+    #[test]
+    // Byte orders and classes the test machine doesn't build
+    fn elf_headers() {
+        assert_eq!(tree_magic::from_u8(&elf64_be(1, &[])), "application/x-object");
+        assert_eq!(tree_magic::from_u8(&elf64_be(3, &[1, 1])), "application/x-sharedlib");
+        assert_eq!(tree_magic::from_u8(&elf64_be(3, &[6, 3, 1])), "application/x-executable");
+        assert_eq!(tree_magic::from_u8(&elf64_be(4, &[4, 1])), "application/x-core");
+        assert_eq!(tree_magic::from_u8(&elf32(2)), "application/x-executable");
+        assert_eq!(tree_magic::from_u8(&elf32(3)), "application/x-sharedlib");
+    }
+
+    #[test]
+    fn mach_o() {
+        let be32 = [0xfe, 0xed, 0xfa, 0xce];
+        let le64 = [0xcf, 0xfa, 0xed, 0xfe];
+        assert_eq!(tree_magic::from_u8(&mach_o_header(le64, false, 2)), "application/x-executable");
+        assert_eq!(tree_magic::from_u8(&mach_o_header(le64, false, 6)), "application/x-sharedlib");
+        assert_eq!(tree_magic::from_u8(&mach_o_header(le64, false, 8)), "application/x-sharedlib");
+        assert_eq!(tree_magic::from_u8(&mach_o_header(le64, false, 1)), "application/x-object");
+        assert_eq!(tree_magic::from_u8(&mach_o_header(be32, true, 4)), "application/x-core");
+        assert_eq!(tree_magic::from_u8(&mach_o_header(be32, true, 2)), "application/x-executable");
+    }
+
+    #[test]
+    fn universal() {
+        let slice = mach_o_header([0xcf, 0xfa, 0xed, 0xfe], false, 6);
+        assert_eq!(tree_magic::from_u8(&fat(&slice, 0x1000)), "application/x-sharedlib");
+
+        // The first slice is past the header of a file
+        let b = fat(&mach_o_header([0xcf, 0xfa, 0xed, 0xfe], false, 2), 0x4000);
+        let path = std::env::temp_dir().join(format!("tree_magic-fat-{}", std::process::id()));
+        std::fs::write(&path, &b).unwrap();
+        let result = tree_magic::from_filepath(Path::new(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Some(convmime!("application/x-executable")));
+    }
+
+    #[test]
+    // Java classes share the magic number of universal binaries
+    fn java_class() {
+        let b = b"\xca\xfe\xba\xbe\x00\x00\x00\x34\x00\x1d\x0a\x00\x06\x00\x0f\x09";
+        assert_eq!(tree_magic::from_u8(b), "application/x-java");
+        assert!(!tree_magic::match_u8(convmime!("application/x-executable"), b));
+        assert!(!tree_magic::match_u8(
+            convmime!("application/x-java"),
+            &fat(&mach_o_header([0xcf, 0xfa, 0xed, 0xfe], false, 2), 0x1000)
+        ));
+    }
+
+    #[test]
+    fn pe() {
+        let exe = include_bytes!("application/vnd.microsoft.portable-executable");
+        assert_eq!(tree_magic::from_u8(exe), "application/vnd.microsoft.portable-executable");
+        assert!(tree_magic::match_u8(convmime!("application/x-ms-dos-executable"), exe));
+        assert!(!tree_magic::match_u8(convmime!("application/x-sharedlib"), exe));
+
+        let dll = dll();
+        assert_eq!(tree_magic::from_u8(&dll), "application/x-ms-dll");
+        assert!(tree_magic::match_u8(convmime!("application/vnd.microsoft.portable-executable"), &dll));
+        assert!(tree_magic::match_u8(convmime!("application/x-sharedlib"), &dll));
+        assert!(!tree_magic::match_u8(convmime!("application/x-executable"), &dll));
+    }
+
+    #[test]
+    fn dos() {
+        // Without a PE header, only the DOS stub is left
+        let mut b = include_bytes!("application/vnd.microsoft.portable-executable").to_vec();
+        b[0x3c..0x40].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(tree_magic::from_u8(&b), "application/x-ms-dos-executable");
+        // A PE offset past the end of the input
+        b[0x3c..0x40].copy_from_slice(&0x10000u32.to_le_bytes());
+        assert_eq!(tree_magic::from_u8(&b), "application/x-ms-dos-executable");
+    }
+
+    #[test]
+    // A PE header after a long DOS stub
+    fn pe_path() {
+        let dll = dll();
+        let mut b = dll[..0x3c].to_vec();
+        b.extend_from_slice(&0x1200u32.to_le_bytes());
+        b.resize(0x1200, 0);
+        b.extend_from_slice(&dll[pe_offset(&dll)..]);
+
+        let path = std::env::temp_dir().join(format!("tree_magic-pe-{}", std::process::id()));
+        std::fs::write(&path, &b).unwrap();
+        let result = tree_magic::from_filepath(Path::new(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Some(convmime!("application/x-ms-dll")));
+    }
+
+    #[test]
+    fn from_filepath() {
+        for name in &[
+            "application/x-object",
+            "application/x-sharedlib",
+            "application/x-executable",
+            "application/vnd.microsoft.portable-executable"
+        ] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+    #[test]
+    fn current_exe() {
+        let path = std::env::current_exe().unwrap();
+        let result = tree_magic::from_filepath(&path).unwrap();
+        assert!(
+            result == "application/x-executable" ||
+            result == "application/vnd.microsoft.portable-executable",
+            "{}", result
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let dll = convmime!("application/x-ms-dll");
+        assert_eq!(tree_magic::metadata::preferred_extension(dll), Some("dll".to_string()));
+    }
+}
@@ -0,0 +1,123 @@
+mod extension {
+
+    extern crate tree_magic;
+
+    use std::path::{Path, PathBuf};
+    use tree_magic::extension::{check_extension, suggest_name, Verdict};
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Copies contents to a scratch file with the given name
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("tree_magic-extension-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn verdict(name: &str, contents: &[u8]) -> Verdict {
+        let path = scratch_file(name, contents);
+        let result = check_extension(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        result.verdict
+    }
+
+    #[test]
+    fn consistent() {
+        assert_eq!(verdict("image.gif", include_bytes!("image/gif")), Verdict::Consistent);
+        // Extensions are not case sensitive
+        assert_eq!(verdict("IMAGE.PNG", include_bytes!("image/png")), Verdict::Consistent);
+    }
+
+    #[test]
+    fn consistent_alias() {
+        // .zip belongs to application/zip, detected under its alias' real name
+        assert_eq!(verdict("archive.zip", include_bytes!("application/zip")), Verdict::Consistent);
+    }
+
+    #[test]
+    // An HTML page is also plain text
+    fn consistent_ancestor() {
+        let page = b"<!DOCTYPE html>\n<html><body>Hello</body></html>\n";
+        assert_eq!(verdict("page.txt", page), Verdict::Consistent);
+    }
+
+    #[test]
+    // Content that can't be told apart from its more specific type
+    fn consistent_descendant() {
+        assert_eq!(verdict("table.csv", b"a,b,c\n1,2,3\n"), Verdict::Consistent);
+        assert_eq!(verdict("report.docx", include_bytes!("application/zip")), Verdict::Consistent);
+    }
+
+    #[test]
+    fn mismatch() {
+        let path = scratch_file("invoice.pdf", b"MZ\x90\x00\x03\x00\x00\x00\x04\x00\x00\x00\xff\xff\x00\x00");
+        let result = check_extension(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.extension, Some("pdf".to_string()));
+        assert!(!tree_magic::is_alias(result.detected.clone(), convmime!("application/pdf")));
+        assert_eq!(result.verdict, Verdict::Mismatch(vec![convmime!("application/pdf")]));
+    }
+
+    #[test]
+    fn mismatch_image() {
+        assert_eq!(
+            verdict("photo.png", include_bytes!("image/gif")),
+            Verdict::Mismatch(vec![convmime!("image/png")])
+        );
+    }
+
+    #[test]
+    // Unrecognised content can't stand in for a format with magic
+    fn mismatch_generic() {
+        assert_eq!(
+            verdict("document.pdf", &[0u8, 1, 2, 3, 0, 1, 2, 3]),
+            Verdict::Mismatch(vec![convmime!("application/pdf")])
+        );
+    }
+
+    #[test]
+    fn unknown_extension() {
+        assert_eq!(verdict("image.notarealextension", include_bytes!("image/gif")), Verdict::UnknownExtension);
+        assert_eq!(verdict("image", include_bytes!("image/gif")), Verdict::UnknownExtension);
+    }
+
+    #[test]
+    fn missing_file() {
+        assert_eq!(check_extension(Path::new("this/file/does/not/exist.pdf")), None);
+    }
+
+    fn suggestion(name: &str, contents: &[u8]) -> Option<String> {
+        let path = scratch_file(name, contents);
+        let result = suggest_name(&path);
+        std::fs::remove_file(&path).unwrap();
+        result.map(|x| x.file_name().unwrap().to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn suggest_added() {
+        assert_eq!(suggestion("blob", include_bytes!("image/png")), Some("blob.png".to_string()));
+        assert_eq!(suggestion("download.1", include_bytes!("image/gif")), Some("download.1.gif".to_string()));
+        assert_eq!(suggestion("notes", b"Hello there\n"), Some("notes.txt".to_string()));
+    }
+
+    #[test]
+    fn suggest_replaced() {
+        assert_eq!(suggestion("photo.png", include_bytes!("image/gif")), Some("photo.gif".to_string()));
+    }
+
+    #[test]
+    fn suggest_nothing() {
+        // Already fits
+        assert_eq!(suggestion("photo.gif", include_bytes!("image/gif")), None);
+        // Nothing known about the contents
+        assert_eq!(suggestion("data", &[0u8, 1, 2, 3, 0, 1, 2, 3]), None);
+        assert_eq!(suggestion("empty.png", b""), None);
+        assert_eq!(suggest_name(Path::new("tests")), None);
+    }
+}
@@ -0,0 +1,66 @@
+mod from_filename {
+
+    extern crate tree_magic;
+
+    use tree_magic::{from_extension, from_filename};
+
+    macro_rules! convmime {
+        ($x:expr) => {Some($x.parse().unwrap())}
+    }
+
+    #[test]
+    fn extension() {
+        assert_eq!(from_extension("png"), convmime!("image/png"));
+        assert_eq!(
+            from_extension("docx"),
+            convmime!("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+        assert_eq!(from_extension("notarealextension"), None);
+    }
+
+    #[test]
+    // The longest suffix wins
+    fn multi_dot_suffix() {
+        assert_eq!(from_filename("archive.tar.gz"), convmime!("application/x-compressed-tar"));
+        assert_eq!(from_filename("archive.gz"), convmime!("application/gzip"));
+        assert_eq!(from_extension("tar.gz"), convmime!("application/x-compressed-tar"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(from_filename("PHOTO.JPG"), convmime!("image/jpeg"));
+        assert_eq!(from_filename("Archive.Tar.Gz"), convmime!("application/x-compressed-tar"));
+    }
+
+    #[test]
+    // *.c and *.C are different languages
+    fn case_sensitive() {
+        assert_eq!(from_filename("main.c"), convmime!("text/x-csrc"));
+        assert_eq!(from_filename("main.C"), convmime!("text/x-c++src"));
+    }
+
+    #[test]
+    fn literal_name() {
+        assert_eq!(from_filename("Makefile"), convmime!("text/x-makefile"));
+    }
+
+    #[test]
+    // README* is neither a literal nor a suffix
+    fn other_glob() {
+        assert_eq!(from_filename("README.first"), convmime!("text/x-readme"));
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(from_filename("some/dir/photo.png"), convmime!("image/png"));
+        assert_eq!(from_filename("some/dir/"), None);
+        assert_eq!(from_filename(""), None);
+    }
+
+    #[test]
+    // The same canonical types as content detection
+    fn same_as_content() {
+        let content = tree_magic::from_filepath(std::path::Path::new("tests/application/zip"));
+        assert_eq!(from_extension("zip"), content);
+    }
+}
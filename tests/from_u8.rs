@@ -82,6 +82,22 @@ mod from_u8 {
         );
     }
 
+    /// Masked rules
+    #[test]
+    fn image_vnd_adobe_photoshop() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/vnd.adobe.photoshop")),
+            convmime!("image/vnd.adobe.photoshop")
+        );
+    }
+    #[test]
+    fn video_dv() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/dv")),
+            convmime!("video/dv")
+        );
+    }
+
     /// Text tests
     #[test]
     fn text_plain() {
@@ -90,6 +106,17 @@ mod from_u8 {
             convmime!("text/plain")
         );
     }
+    #[test]
+    // Matched by a region rule, not at offset 0
+    fn text_html() {
+        let mut input = b"\n\n  <!DOCTYPE html>\n<html>".to_vec();
+        input.extend(b"<p>Hello</p>\n".iter().cycle().take(300));
+        input.extend(b"</html>\n");
+        assert_eq!(
+            tree_magic::from_u8(&input),
+            convmime!("text/html")
+        );
+    }
 
 	// Audio tests
 	#[test]
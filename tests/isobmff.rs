@@ -0,0 +1,128 @@
+mod isobmff {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Builds an `ftyp` box, for brands there's no test file of
+    fn ftyp(major: &[u8], compatible: &[&[u8]]) -> Vec<u8> {
+        let mut b = (((compatible.len() + 2) * 4 + 8) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(b"ftyp");
+        b.extend_from_slice(major);
+        b.extend_from_slice(&[0, 0, 2, 0]);
+        for x in compatible {
+            b.extend_from_slice(x);
+        }
+        b
+    }
+
+    #[test]
+    fn video_mp4() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/mp4")),
+            "video/mp4"
+        );
+        assert_eq!(tree_magic::from_u8(&ftyp(b"mp42", &[b"mp42", b"isom"])), "video/mp4");
+        assert_eq!(tree_magic::from_u8(&ftyp(b"M4V ", &[b"M4V ", b"mp42"])), "video/mp4");
+    }
+
+    #[test]
+    // A generic brand with only a sound track
+    fn audio_mp4() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/mp4")),
+            "audio/mp4"
+        );
+        assert_eq!(tree_magic::from_u8(&ftyp(b"M4A ", &[b"M4A ", b"mp42", b"isom"])), "audio/mp4");
+        assert_eq!(tree_magic::from_u8(&ftyp(b"M4B ", &[b"M4B ", b"mp42"])), "audio/x-m4b");
+    }
+
+    #[test]
+    // Older movies have no ftyp
+    fn video_quicktime() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/quicktime")),
+            "video/quicktime"
+        );
+        assert_eq!(tree_magic::from_u8(&ftyp(b"qt  ", &[b"qt  "])), "video/quicktime");
+    }
+
+    #[test]
+    fn video_3gpp() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/3gpp")),
+            "video/3gpp"
+        );
+        assert_eq!(tree_magic::from_u8(&ftyp(b"3g2a", &[b"3g2a"])), "video/3gpp2");
+        // Specific brands win over generic ones, wherever they are
+        assert_eq!(tree_magic::from_u8(&ftyp(b"isom", &[b"isom", b"3gp6"])), "video/3gpp");
+    }
+
+    #[test]
+    // Generic brands, with the codec of the image in the meta box
+    fn image_items() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/heic")),
+            "image/heic"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/heif")),
+            "image/heif"
+        );
+    }
+
+    #[test]
+    fn image_brands() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/avif")),
+            "image/avif"
+        );
+        assert_eq!(tree_magic::from_u8(&ftyp(b"heic", &[b"mif1", b"heic"])), "image/heic");
+        assert_eq!(tree_magic::from_u8(&ftyp(b"hevc", &[b"msf1", b"hevc"])), "image/heic-sequence");
+        assert_eq!(tree_magic::from_u8(&ftyp(b"msf1", &[b"msf1"])), "image/heif-sequence");
+        assert_eq!(tree_magic::from_u8(&ftyp(b"avis", &[b"avis", b"msf1"])), "image/avif-sequence");
+    }
+
+    #[test]
+    fn from_filepath() {
+        for name in &["video/mp4", "audio/mp4", "video/quicktime", "video/3gpp", "image/heic", "image/heif", "image/avif"] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+    #[test]
+    fn match_parents() {
+        let b = include_bytes!("image/heic");
+        assert!(tree_magic::match_u8(convmime!("image/heic"), b));
+        assert!(tree_magic::match_u8(convmime!("image/heif"), b));
+        assert!(!tree_magic::match_u8(convmime!("image/avif"), b));
+        assert!(!tree_magic::match_u8(convmime!("video/mp4"), b));
+
+        let b = include_bytes!("video/3gpp");
+        assert!(tree_magic::match_u8(convmime!("video/mp4"), b));
+        assert!(tree_magic::match_u8(convmime!("video/3gp"), b));
+    }
+
+    #[test]
+    fn not_isobmff() {
+        // Unknown brands are left to other checkers
+        assert_eq!(tree_magic::from_u8(&ftyp(b"abcd", &[b"abcd"])), "application/octet-stream");
+        assert!(!tree_magic::match_u8(convmime!("video/mp4"), include_bytes!("image/gif")));
+        assert!(!tree_magic::match_u8(convmime!("video/quicktime"), b"\0\0\0\x04mdat"));
+    }
+
+    #[test]
+    // A box cut off by the end of the input
+    fn truncated() {
+        let b = include_bytes!("image/avif");
+        assert_eq!(tree_magic::from_u8(&b[..12]), "image/avif");
+        assert!(!tree_magic::match_u8(convmime!("image/avif"), &b[..6]));
+    }
+}
@@ -0,0 +1,97 @@
+mod match_many {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use tree_magic::probe::Probe;
+
+    macro_rules! convmimes {
+        ($($x:expr),*) => {vec![$($x.parse::<mime::Mime>().unwrap()),*]}
+    }
+
+    #[test]
+    fn any_bytes() {
+        let allowed = convmimes!("image/png", "image/gif", "application/pdf");
+        let probe = Probe::from_u8(include_bytes!("image/gif"));
+        assert_eq!(tree_magic::match_any(&allowed, &probe), Some(allowed[1].clone()));
+    }
+
+    #[test]
+    fn any_none() {
+        let allowed = convmimes!("image/png", "application/pdf");
+        let probe = Probe::from_u8(include_bytes!("image/gif"));
+        assert_eq!(tree_magic::match_any(&allowed, &probe), None);
+        assert_eq!(tree_magic::match_any(&[], &probe), None);
+    }
+
+    #[test]
+    fn any_path() {
+        let allowed = convmimes!("image/png", "application/x-tar");
+        let probe = Probe::from_filepath(Path::new("tests/application/x-tar"));
+        assert_eq!(tree_magic::match_any(&allowed, &probe), Some(allowed[1].clone()));
+    }
+
+    #[test]
+    fn any_reader() {
+        let allowed = convmimes!("application/zip");
+        let probe = Probe::from_reader(&include_bytes!("application/zip")[..]).unwrap();
+        assert_eq!(tree_magic::match_any(&allowed, &probe), Some(allowed[0].clone()));
+    }
+
+    #[test]
+    // Aliases are returned as given
+    fn any_alias() {
+        let allowed = convmimes!("image/png", "application/x-zip-compressed");
+        let probe = Probe::from_filepath(Path::new("tests/application/zip"));
+        assert_eq!(tree_magic::match_any(&allowed, &probe), Some(allowed[1].clone()));
+    }
+
+    #[test]
+    fn all_in_order() {
+        let types = convmimes!(
+            "text/plain", "application/zip", "image/gif",
+            "application/x-zip-compressed", "application/zip", "all/all"
+        );
+        let probe = Probe::from_u8(include_bytes!("application/zip"));
+        assert_eq!(
+            tree_magic::match_all(&types, &probe),
+            convmimes!("application/zip", "application/x-zip-compressed", "application/zip")
+        );
+    }
+
+    #[test]
+    // The same answers as checking each type on its own
+    fn all_same_as_match_u8() {
+        let types = convmimes!(
+            "image/png", "image/gif", "image/bmp", "application/zip", "application/x-tar",
+            "text/plain", "text/html", "application/octet-stream", "inode/directory"
+        );
+        for input in &[&include_bytes!("image/gif")[..], include_bytes!("text/plain")] {
+            let expected: Vec<mime::Mime> = types.iter()
+                .filter(|x| tree_magic::match_u8((*x).clone(), input))
+                .cloned()
+                .collect();
+            assert_eq!(tree_magic::match_all(&types, &Probe::from_u8(input)), expected);
+        }
+    }
+
+    #[test]
+    // Subclasses are only matched below an ancestor that matches
+    fn all_ancestors() {
+        let types = convmimes!(
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "application/zip", "application/octet-stream", "text/plain"
+        );
+        let probe = Probe::from_filepath(Path::new("tests/application/zip"));
+        assert_eq!(tree_magic::match_all(&types, &probe), convmimes!("application/zip", "application/octet-stream"));
+        let probe = Probe::from_filepath(Path::new("tests"));
+        assert!(tree_magic::match_all(&types, &probe).is_empty());
+    }
+
+    #[test]
+    fn all_directory() {
+        let types = convmimes!("application/octet-stream", "inode/directory");
+        let probe = Probe::from_filepath(Path::new("tests"));
+        assert_eq!(tree_magic::match_all(&types, &probe), convmimes!("inode/directory"));
+    }
+}
@@ -47,11 +47,39 @@ mod match_u8 {
         assert!(tree_magic::match_u8("application/zip", include_bytes!("application/zip")));
     }
 
+    /// Masked rules
+    #[test]
+    fn application_vnd_corel_draw() {
+        assert!(tree_magic::match_u8("application/vnd.corel-draw", include_bytes!("application/vnd.corel-draw")));
+    }
+    #[test]
+    // The masked-out version byte must not matter, but the rest must
+    fn application_vnd_corel_draw_masked() {
+        let mut input = include_bytes!("application/vnd.corel-draw").to_vec();
+        input[11] = b'X';
+        assert!(tree_magic::match_u8("application/vnd.corel-draw", &input));
+        input[12] = b'V';
+        assert!(!tree_magic::match_u8("application/vnd.corel-draw", &input));
+    }
+    #[test]
+    fn image_vnd_adobe_photoshop() {
+        assert!(tree_magic::match_u8("image/vnd.adobe.photoshop", include_bytes!("image/vnd.adobe.photoshop")));
+    }
+    #[test]
+    fn video_dv() {
+        assert!(tree_magic::match_u8("video/dv", include_bytes!("video/dv")));
+    }
+
     /// Text tests
     #[test]
     fn text_plain() {
         assert!(tree_magic::match_u8("text/plain", include_bytes!("text/plain")));
     }
+    #[test]
+    // The region ends past the end of the input, but the match doesn't
+    fn text_html_short() {
+        assert!(tree_magic::match_u8("text/html", b"<!DOCTYPE html>\n<html></html>\n"));
+    }
     
 	// Audio tests
 	#[test]
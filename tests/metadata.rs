@@ -0,0 +1,169 @@
+mod metadata {
+
+    extern crate tree_magic;
+
+    use tree_magic::metadata;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    const PACKAGE: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>
+<mime-info xmlns="http://www.freedesktop.org/standards/shared-mime-info">
+  <mime-type type="application/x-example">
+    <comment>Example document</comment>
+    <comment xml:lang="de">Beispieldokument</comment>
+    <comment xml:lang="pt_BR">Documento de exemplo</comment>
+    <icon name="example-icon"/>
+    <generic-icon name="x-office-document"/>
+    <glob pattern="*.exa" weight="40"/>
+    <glob pattern="*.example" weight="80"/>
+    <glob pattern="Examplefile" case-sensitive="true"/>
+    <alias type="application/example"/>
+    <sub-class-of type="application/zip"/>
+  </mime-type>
+  <mime-type type="application/x-example-draft">
+    <comment>Example draft</comment>
+    <sub-class-of type="application/example"/>
+  </mime-type>
+  <mime-type type="text/x-example-notes">
+    <comment>Example notes</comment>
+  </mime-type>
+</mime-info>"#;
+
+    #[test]
+    fn builtin_description() {
+        assert_eq!(
+            metadata::description(convmime!("image/png"), None),
+            Some("PNG image".to_string())
+        );
+    }
+
+    #[test]
+    fn builtin_description_alias() {
+        assert_eq!(
+            metadata::description(convmime!("application/x-pdf"), Some("en_US.UTF-8")),
+            metadata::description(convmime!("application/pdf"), None)
+        );
+    }
+
+    #[test]
+    fn builtin_extensions() {
+        let exts = metadata::extensions(convmime!("image/jpeg"));
+        assert!(exts.contains(&"jpg".to_string()));
+        assert!(exts.contains(&"jpeg".to_string()));
+    }
+
+    #[test]
+    fn builtin_icons() {
+        assert_eq!(
+            metadata::generic_icon_name(convmime!("image/png")),
+            Some("image-x-generic".to_string())
+        );
+        assert_eq!(
+            metadata::icon_name(convmime!("image/png")),
+            Some("image-png".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        assert_eq!(metadata::description(convmime!("x-nothing/x-nothing"), None), None);
+        assert_eq!(metadata::preferred_extension(convmime!("x-nothing/x-nothing")), None);
+    }
+
+    #[test]
+    fn package_locales() {
+        let info = metadata::MimeInfo::from_u8(PACKAGE).unwrap();
+        assert_eq!(
+            info.description(convmime!("application/x-example"), None),
+            Some("Example document".to_string())
+        );
+        assert_eq!(
+            info.description(convmime!("application/x-example"), Some("de_AT.UTF-8")),
+            Some("Beispieldokument".to_string())
+        );
+        assert_eq!(
+            info.description(convmime!("application/x-example"), Some("pt_BR")),
+            Some("Documento de exemplo".to_string())
+        );
+        assert_eq!(
+            info.description(convmime!("application/x-example"), Some("fr")),
+            Some("Example document".to_string())
+        );
+    }
+
+    #[test]
+    fn package_icons_and_globs() {
+        let info = metadata::MimeInfo::from_u8(PACKAGE).unwrap();
+        assert_eq!(
+            info.icon_name(convmime!("application/example")),
+            Some("example-icon".to_string())
+        );
+        assert_eq!(
+            info.generic_icon_name(convmime!("application/example")),
+            Some("x-office-document".to_string())
+        );
+        assert_eq!(
+            info.extensions(convmime!("application/example")),
+            vec!["example".to_string(), "exa".to_string()]
+        );
+        assert_eq!(
+            info.preferred_extension(convmime!("application/example")),
+            Some("example".to_string())
+        );
+    }
+
+    #[test]
+    fn package_subclasses() {
+        let info = metadata::MimeInfo::from_u8(PACKAGE).unwrap();
+        assert!(info.is_subclass(convmime!("application/example"), convmime!("application/zip")));
+        assert!(info.is_subclass(convmime!("application/x-example"), convmime!("application/x-example")));
+        assert!(!info.is_subclass(convmime!("application/zip"), convmime!("application/x-example")));
+        assert_eq!(
+            info.types_with_extension("EXA"),
+            vec!["application/x-example".parse::<mime::Mime>().unwrap()]
+        );
+    }
+
+    #[test]
+    // Types without globs use their nearest ancestor's extensions
+    fn package_inherited_extensions() {
+        let info = metadata::MimeInfo::from_u8(PACKAGE).unwrap();
+        assert_eq!(
+            info.extensions(convmime!("application/x-example-draft")),
+            vec!["example".to_string(), "exa".to_string()]
+        );
+        // No text/plain in this package, so nothing to inherit
+        assert_eq!(info.preferred_extension(convmime!("text/x-example-notes")), None);
+        assert_eq!(
+            metadata::preferred_extension(convmime!("text/x-readme")),
+            Some("txt".to_string())
+        );
+    }
+
+    #[test]
+    fn builtin_subclasses() {
+        // Through <sub-class-of>, and the implicit text/plain and octet-stream parents
+        assert!(metadata::is_subclass(convmime!("application/vnd.oasis.opendocument.text"), convmime!("application/zip")));
+        assert!(metadata::is_subclass(convmime!("text/csv"), convmime!("text/plain")));
+        assert!(metadata::is_subclass(convmime!("image/png"), convmime!("application/octet-stream")));
+        assert!(!metadata::is_subclass(convmime!("image/png"), convmime!("text/plain")));
+        assert!(!metadata::is_subclass(convmime!("inode/directory"), convmime!("application/octet-stream")));
+    }
+
+    #[test]
+    // The bundled database would otherwise fail silently into an empty one
+    fn builtin_packages_parse() {
+        let fdo = metadata::MimeInfo::from_u8(include_bytes!("../src/metadata/builtin/freedesktop.org.xml")).unwrap();
+        assert!(fdo.get(convmime!("image/png")).is_some());
+        let own = metadata::MimeInfo::from_u8(include_bytes!("../src/metadata/builtin/tree_magic.xml")).unwrap();
+        assert!(own.get(convmime!("image/x-webp-lossy")).is_some());
+    }
+
+    #[test]
+    fn package_rejects_other_xml() {
+        assert!(metadata::MimeInfo::from_u8(b"<html></html>").is_err());
+    }
+
+}
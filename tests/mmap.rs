@@ -0,0 +1,108 @@
+#![cfg(feature = "mmap")]
+
+mod mmap {
+
+    extern crate tree_magic;
+
+    use std::path::{Path, PathBuf};
+    use tree_magic::probe::{Probe, HEADER_LEN};
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Writes a scratch file for one test
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("tree_magic-mmap-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    // Long enough to be mapped
+    fn large_tar() {
+        let mut contents = include_bytes!("application/x-tar").to_vec();
+        contents.resize(16 * HEADER_LEN, 0);
+        let path = scratch_file("tar", &contents);
+
+        assert_eq!(tree_magic::from_filepath(&path), Some(convmime!("application/x-tar")));
+        assert!(tree_magic::match_filepath(convmime!("application/x-tar"), &path));
+
+        let probe = Probe::from_filepath(&path);
+        assert_eq!(probe.bytes(usize::MAX).unwrap(), &contents[..]);
+        assert_eq!(&probe.bytes(262).unwrap()[257..], b"ustar");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // DTS-HD is found anywhere in the first 18 KiB, past the header
+    fn rule_past_header() {
+        let mut contents = vec![0u8; 12 * HEADER_LEN];
+        contents[..4].copy_from_slice(b"\x7f\xfe\x80\x01");
+        contents[9 * HEADER_LEN..9 * HEADER_LEN + 4].copy_from_slice(b"dX %");
+        let path = scratch_file("dts", &contents);
+
+        assert_eq!(tree_magic::from_filepath(&path), Some(convmime!("audio/vnd.dts.hd")));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // Short files are read instead of mapped
+    fn short_file() {
+        assert_eq!(
+            tree_magic::from_filepath(Path::new("tests/image/gif")),
+            Some(convmime!("image/gif"))
+        );
+    }
+
+    #[test]
+    fn empty_file() {
+        let path = scratch_file("empty", b"");
+        let probe = Probe::from_filepath(&path);
+        assert_eq!(probe.header().unwrap(), b"");
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("text/plain")));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // A mapped file that was cut short is read buffered instead of from the mapping
+    fn truncated_while_mapped() {
+        let contents = vec![b'a'; 16 * HEADER_LEN];
+        let path = scratch_file("truncated", &contents);
+
+        let probe = Probe::from_filepath(&path);
+        assert_eq!(probe.bytes(usize::MAX).unwrap(), &contents[..]);
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(4 * HEADER_LEN as u64).unwrap();
+        assert_eq!(probe.bytes(usize::MAX).unwrap(), &contents[..4 * HEADER_LEN]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // The header is read, and only longer reads map the file
+    fn header_only() {
+        let mut contents = include_bytes!("image/png").to_vec();
+        contents.resize(16 * HEADER_LEN, 0);
+        let path = scratch_file("png", &contents);
+
+        let probe = Probe::from_filepath(&path);
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("image/png")));
+        // Truncating doesn't change what was already read
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(0).unwrap();
+        assert_eq!(probe.header().unwrap(), &contents[..HEADER_LEN]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // Character devices can't be mapped, and fall back to buffered reads
+    fn special_file() {
+        let probe = Probe::from_filepath(Path::new("/dev/zero"));
+        assert_eq!(probe.bytes(4 * HEADER_LEN).unwrap().len(), 4 * HEADER_LEN);
+        assert!(tree_magic::from_probe(&probe).is_none());
+    }
+}
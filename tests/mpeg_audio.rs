@@ -0,0 +1,128 @@
+mod mpeg_audio {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Length of the ID3v2 tag in front of the test MP3
+    const TAG_LEN: usize = 2048;
+
+    /// An MPEG-1 layer III frame at 128 kbit/s and 44.1 kHz, for streams
+    /// that are broken on purpose
+    fn mp3_frame() -> Vec<u8> {
+        let mut b = vec![0xff, 0xfb, 0x90, 0x44];
+        b.resize(417, 0x55);
+        b
+    }
+
+    /// Makes the test MP3's ID3v2 tag `len` bytes long, as a large cover picture would
+    fn with_tag_len(len: usize) -> Vec<u8> {
+        let mp3 = include_bytes!("audio/mpeg");
+        let mut b = mp3[..TAG_LEN].to_vec();
+        for i in 0..4 {
+            b[6 + i] = (((len - 10) >> (7 * (3 - i))) & 0x7f) as u8;
+        }
+        b.resize(len, 0);
+        b.extend_from_slice(&mp3[TAG_LEN..]);
+        b
+    }
+
+    #[test]
+    fn tagged() {
+        // The tag ends right where a file's header does
+        let mp3 = include_bytes!("audio/mpeg");
+        assert_eq!(tree_magic::from_u8(mp3), "audio/mpeg");
+        assert_eq!(
+            tree_magic::from_u8(&[&mp3[..TAG_LEN], include_bytes!("audio/aac")].concat()),
+            "audio/aac"
+        );
+
+        let mut ape = b"APETAGEX\xd0\x07\0\0\x40\0\0\0\0\0\0\0\0\0\0\xa0".to_vec();
+        ape.resize(32 + 64, 0);
+        assert_eq!(tree_magic::from_u8(&[&ape, &mp3[TAG_LEN..]].concat()), "audio/mpeg");
+    }
+
+    #[test]
+    fn untagged() {
+        assert_eq!(tree_magic::from_u8(&include_bytes!("audio/mpeg")[TAG_LEN..]), "audio/mpeg");
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/aac")),
+            "audio/aac"
+        );
+        assert_eq!(tree_magic::from_u8(b"ADIF\0\0\0\0"), "audio/aac");
+    }
+
+    #[test]
+    fn from_filepath() {
+        for name in &["audio/mpeg", "audio/aac"] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+    #[test]
+    // A cover picture longer than the header
+    fn large_tag() {
+        let b = with_tag_len(100_000);
+        let path = std::env::temp_dir().join(format!("tree_magic-mpeg-{}", std::process::id()));
+        std::fs::write(&path, &b).unwrap();
+        let result = tree_magic::from_filepath(Path::new(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Some(convmime!("audio/mpeg")));
+
+        // An APE tag after the long one, with more than the header of text
+        let mut ape = b"APETAGEX\xd0\x07\0\0\0\x20\0\0\0\0\0\0\0\0\0\xa0".to_vec();
+        ape.resize(32, 0);
+        ape.resize(32 + 0x2000, b'a');
+        let tagged = [&b[..100_000], &ape, &b[100_000..]].concat();
+        let path = std::env::temp_dir().join(format!("tree_magic-mpeg-ape-{}", std::process::id()));
+        std::fs::write(&path, &tagged).unwrap();
+        let result = tree_magic::from_filepath(Path::new(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Some(convmime!("audio/mpeg")));
+
+        // A tag cut off by the end of the input is still an MP3 tag
+        assert_eq!(tree_magic::from_u8(&b[..4096]), "audio/mpeg");
+    }
+
+    #[test]
+    fn junk() {
+        let b = [vec![0x12; 300], mp3_frame().repeat(3)].concat();
+        assert_eq!(tree_magic::from_u8(&b), "audio/mpeg");
+        // Past junk, two frames aren't enough
+        let b = [vec![0x12; 300], mp3_frame().repeat(2)].concat();
+        assert!(!tree_magic::match_u8(convmime!("audio/mpeg"), &b));
+    }
+
+    #[test]
+    // One sync word isn't a stream
+    fn false_positives() {
+        let mut b = mp3_frame();
+        b.extend_from_slice(&[0x42; 500]);
+        assert!(!tree_magic::match_u8(convmime!("audio/mpeg"), &b));
+
+        let mut b = include_bytes!("audio/aac")[..371].to_vec();
+        b.extend_from_slice(&[0xff, 0xf1, 0x50]);
+        b.resize(700, 0);
+        assert!(!tree_magic::match_u8(convmime!("audio/aac"), &b));
+
+        // A tag in front of something else
+        let b = [&include_bytes!("audio/mpeg")[..TAG_LEN], &include_bytes!("audio/flac")[..600]].concat();
+        assert!(!tree_magic::match_u8(convmime!("audio/mpeg"), &b));
+    }
+
+    #[test]
+    // Frames of different sample rates aren't one stream
+    fn mixed_frames() {
+        let mut other = mp3_frame();
+        other[2] = 0x94;
+        let b = [mp3_frame(), other, vec![0; 500]].concat();
+        assert!(!tree_magic::match_u8(convmime!("audio/mpeg"), &b));
+    }
+}
@@ -0,0 +1,139 @@
+mod ogg {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Builds the first pages of a file from the first packet of each stream,
+    /// for codec mixes there's no test file of
+    fn bos_pages(packets: &[&[u8]]) -> Vec<u8> {
+        let mut b = Vec::<u8>::new();
+        for (serial, packet) in packets.iter().enumerate() {
+            b.extend_from_slice(b"OggS\0\x02");
+            b.extend_from_slice(&[0; 8]);
+            b.extend_from_slice(&(serial as u32).to_le_bytes());
+            b.extend_from_slice(&[0; 8]);
+            let mut lacing = vec![255; packet.len() / 255];
+            lacing.push((packet.len() % 255) as u8);
+            b.push(lacing.len() as u8);
+            b.extend(lacing);
+            b.extend_from_slice(packet);
+        }
+        b
+    }
+
+    fn with_padding(start: &[u8], len: usize) -> Vec<u8> {
+        let mut b = start.to_vec();
+        b.resize(len, 0);
+        b
+    }
+
+    #[test]
+    fn audio() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/ogg")),
+            "audio/x-vorbis+ogg"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/opus")),
+            "audio/x-opus+ogg"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/x-flac+ogg")),
+            "audio/x-flac+ogg"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/x-speex+ogg")),
+            "audio/x-speex+ogg"
+        );
+
+        let flac = with_padding(b"\x7fFLAC\x01\x00\x00\x01fLaC", 51);
+        let speex = with_padding(b"Speex   1.2", 80);
+        let celt = with_padding(b"CELT    ", 60);
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[&celt])), "audio/ogg");
+        // Streams of different codecs
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[&flac, &speex])), "audio/ogg");
+    }
+
+    #[test]
+    fn video() {
+        // The Skeleton stream comes first, and says nothing about the contents
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-theora+ogg")),
+            "video/x-theora+ogg"
+        );
+        // Dirac
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/ogg")),
+            "video/ogg"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-ogm+ogg")),
+            "video/x-ogm+ogg"
+        );
+
+        let theora = with_padding(b"\x80theora\x03\x02\x01", 42);
+        let dirac = with_padding(b"BBCD\0", 20);
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[&theora, &dirac])), "video/ogg");
+    }
+
+    #[test]
+    fn skeleton() {
+        let skeleton = with_padding(b"fishead\0\x03\0\x00\0", 64);
+        let opus = with_padding(b"OpusHead\x01\x02", 19);
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[&skeleton, &opus])), "audio/x-opus+ogg");
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/ogg")),
+            "application/ogg"
+        );
+    }
+
+    #[test]
+    fn unknown_codec() {
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[b"XYZ codec"])), "application/ogg");
+        // A data page
+        let b = include_bytes!("audio/x-flac+ogg");
+        assert_eq!(tree_magic::from_u8(&b[79..]), "application/ogg");
+    }
+
+    #[test]
+    // Packets longer than one segment
+    fn long_packets() {
+        let skeleton = with_padding(b"fishead\0", 600);
+        let vorbis = with_padding(b"\x01vorbis", 30);
+        assert_eq!(tree_magic::from_u8(&bos_pages(&[&skeleton, &vorbis])), "audio/x-vorbis+ogg");
+    }
+
+    #[test]
+    fn truncated() {
+        // The Theora stream is cut off after the Skeleton one
+        let b = include_bytes!("video/x-theora+ogg");
+        assert_eq!(tree_magic::from_u8(&b[..100]), "application/ogg");
+        assert_eq!(tree_magic::from_u8(&b[..170]), "video/x-theora+ogg");
+        assert!(!tree_magic::match_u8(convmime!("application/ogg"), b"Ogg"));
+    }
+
+    #[test]
+    fn from_filepath() {
+        for name in &["audio/x-flac+ogg", "audio/x-speex+ogg", "video/x-theora+ogg", "video/ogg", "video/x-ogm+ogg"] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+    #[test]
+    fn match_parents() {
+        let b = include_bytes!("audio/opus");
+        assert!(tree_magic::match_u8(convmime!("audio/x-opus+ogg"), b));
+        assert!(tree_magic::match_u8(convmime!("audio/ogg"), b));
+        assert!(tree_magic::match_u8(convmime!("application/ogg"), b));
+        assert!(!tree_magic::match_u8(convmime!("audio/x-vorbis+ogg"), b));
+        assert!(!tree_magic::match_u8(convmime!("video/ogg"), b));
+    }
+}
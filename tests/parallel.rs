@@ -0,0 +1,98 @@
+#![cfg(feature = "parallel")]
+
+mod parallel {
+
+    extern crate tree_magic;
+
+    use std::path::{Path, PathBuf};
+    use tree_magic::parallel::{classify_paths, classify_paths_with, ClassifyOptions};
+
+    /// Every sample file, with the type it should be classified as
+    const SAMPLES: [(&str, &str); 6] = [
+        ("tests/image/gif", "image/gif"),
+        ("tests/image/png", "image/png"),
+        ("tests/image/bmp", "image/bmp"),
+        ("tests/application/zip", "application/zip"),
+        ("tests/application/x-tar", "application/x-tar"),
+        ("tests/text/plain", "text/plain")
+    ];
+
+    /// Many copies of the samples, so workers overlap
+    fn paths(copies: usize) -> Vec<PathBuf> {
+        (0..copies)
+            .flat_map(|_| SAMPLES.iter().map(|x| PathBuf::from(x.0)))
+            .collect()
+    }
+
+    fn expected(path: &Path) -> &'static str {
+        SAMPLES.iter().find(|x| Path::new(x.0) == path).unwrap().1
+    }
+
+    #[test]
+    fn unordered() {
+        let mut count = 0;
+        for (path, mime) in classify_paths(paths(20)) {
+            assert_eq!(mime.unwrap().to_string(), expected(&path));
+            count += 1;
+        }
+        assert_eq!(count, 120);
+    }
+
+    #[test]
+    fn ordered() {
+        let input = paths(20);
+        let options = ClassifyOptions{workers: 4, queue_len: 3, ordered: true};
+        let output: Vec<PathBuf> = classify_paths_with(input.clone(), options)
+            .map(|(path, mime)| {
+                assert_eq!(mime.unwrap().to_string(), expected(&path));
+                path
+            })
+            .collect();
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn missing_file() {
+        let input = vec![PathBuf::from("tests/nonexistent"), PathBuf::from("tests/image/gif")];
+        let options = ClassifyOptions{ordered: true, ..Default::default()};
+        let output: Vec<_> = classify_paths_with(input, options).collect();
+        assert!(output[0].1.is_err());
+        assert_eq!(output[1].1.as_ref().unwrap().to_string(), "image/gif");
+    }
+
+    #[test]
+    fn directory() {
+        let mut output = classify_paths(vec![PathBuf::from("tests/image")]);
+        assert_eq!(output.next().unwrap().1.unwrap().to_string(), "inode/directory");
+    }
+
+    #[test]
+    // DTS-HD is found anywhere in the first 18 KiB, past the header
+    fn rule_past_header() {
+        let mut contents = vec![0u8; 3 * 2048];
+        contents[..4].copy_from_slice(b"\x7f\xfe\x80\x01");
+        contents[2048 + 100..2048 + 104].copy_from_slice(b"dX %");
+        let path = std::env::temp_dir()
+            .join(format!("tree_magic-parallel-{}-dts", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+
+        let mut output = classify_paths(vec![path.clone()]);
+        assert_eq!(output.next().unwrap().1.unwrap().to_string(), "audio/vnd.dts.hd");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cancel() {
+        // Never runs out of paths on its own
+        let input = std::iter::repeat(PathBuf::from("tests/image/gif"));
+        let options = ClassifyOptions{workers: 2, queue_len: 4, ordered: false};
+        let mut output = classify_paths_with(input, options);
+        let canceller = output.canceller();
+
+        assert!(output.by_ref().take(10).all(|x| x.1.is_ok()));
+        canceller.cancel();
+        assert!(canceller.is_cancelled());
+        assert!(output.next().is_none());
+    }
+}
@@ -0,0 +1,135 @@
+mod probe {
+    extern crate tree_magic;
+
+    use std::path::{Path, PathBuf};
+    use tree_magic::probe::{Probe, HEADER_LEN};
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    /// Writes a scratch file for one test
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("tree_magic-probe-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn same_as_from_filepath() {
+        for name in &["image/gif", "image/png", "application/zip", "text/plain"] {
+            let path = Path::new("tests").join(name);
+            assert_eq!(
+                tree_magic::from_probe(&Probe::from_filepath(&path)),
+                tree_magic::from_filepath(&path)
+            );
+        }
+    }
+
+    #[test]
+    fn same_as_from_u8() {
+        let input: &[u8] = include_bytes!("application/x-tar");
+        assert_eq!(
+            tree_magic::from_probe(&Probe::from_u8(input)),
+            Some(tree_magic::from_u8(input))
+        );
+    }
+
+    #[test]
+    fn directory() {
+        let probe = Probe::from_filepath(Path::new("tests"));
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("inode/directory")));
+        assert!(probe.metadata().unwrap().is_dir());
+        assert_eq!(probe.header(), None);
+    }
+
+    #[test]
+    fn missing_file() {
+        let probe = Probe::from_filepath(Path::new("this/file/does/not/exist"));
+        assert_eq!(tree_magic::from_probe(&probe), None);
+        assert!(probe.metadata().is_none());
+        assert!(probe.zip_entries().is_none());
+    }
+
+    #[test]
+    fn checked() {
+        let err = Probe::from_filepath_checked(Path::new("this/file/does/not/exist")).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        let probe = Probe::from_filepath_checked(Path::new("tests")).unwrap();
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("inode/directory")));
+    }
+
+    #[test]
+    // One buffer holds the header of each file in turn
+    fn reusing() {
+        let mut buffer = Vec::<u8>::new();
+        for name in &["image/gif", "image/png", "application/zip", "text/plain"] {
+            let path = Path::new("tests").join(name);
+            let probe = Probe::from_filepath_reusing(&path, buffer).unwrap();
+            assert_eq!(tree_magic::from_probe(&probe), tree_magic::from_filepath(&path));
+            buffer = probe.into_buffer();
+            assert_eq!(buffer, std::fs::read(&path).unwrap()[..buffer.len()]);
+        }
+    }
+
+    #[test]
+    // DTS-HD is found anywhere in the first 18 KiB, past the header
+    fn rule_past_header() {
+        let mut contents = vec![0u8; 3 * HEADER_LEN];
+        contents[..4].copy_from_slice(b"\x7f\xfe\x80\x01");
+        contents[HEADER_LEN + 100..HEADER_LEN + 104].copy_from_slice(b"dX %");
+        let path = scratch_file("dts", &contents);
+
+        let probe = Probe::from_filepath(&path);
+        assert_eq!(tree_magic::from_probe(&probe), Some(convmime!("audio/vnd.dts.hd")));
+        assert_eq!(tree_magic::from_u8(probe.header().unwrap()), "audio/vnd.dts");
+        assert!(!probe.header_is_complete());
+        assert_eq!(probe.header().unwrap().len(), HEADER_LEN);
+        assert_eq!(probe.bytes(usize::MAX).unwrap(), &contents[..]);
+        assert!(tree_magic::match_filepath(convmime!("audio/vnd.dts.hd"), &path));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn short_file() {
+        let path = scratch_file("short", b"hello");
+        let probe = Probe::from_filepath(&path);
+        assert!(probe.header_is_complete());
+        assert_eq!(probe.bytes(4 * HEADER_LEN).unwrap(), b"hello");
+        assert_eq!(probe.text(), Some("hello"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn zip_entries() {
+        let input: &[u8] = include_bytes!("application/zip");
+        assert_eq!(Probe::from_u8(input).zip_entries().unwrap(), ["plain"]);
+        assert_eq!(
+            Probe::from_filepath(Path::new("tests/application/zip")).zip_entries().unwrap(),
+            ["plain"]
+        );
+        assert!(Probe::from_u8(include_bytes!("image/gif")).zip_entries().is_none());
+        // A truncated directory isn't trusted
+        assert!(Probe::from_u8(&input[..input.len() - 30]).zip_entries().is_none());
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(Probe::from_u8(b"\xef\xbb\xbfplain").text(), Some("plain"));
+        assert_eq!(Probe::from_u8(b"\xff\xfep\0l\0a\0i\0n\0").text(), Some("plain"));
+        assert_eq!(Probe::from_u8(b"\xfe\xff\0p\0l\0a\0i\0n").text(), Some("plain"));
+        // A character cut in half by the end of the header is dropped
+        assert_eq!(Probe::from_u8(b"caf\xc3").text(), Some("caf"));
+        assert_eq!(Probe::from_u8(b"\x80plain").text(), None);
+    }
+
+    #[test]
+    fn utf16_text() {
+        // NUL bytes are part of UTF-16 characters, not a sign of binary data
+        let probe = Probe::from_u8(b"\xff\xfeh\0i\0\n\0");
+        assert_eq!(tree_magic::from_probe(&probe).unwrap().to_string(), "text/plain");
+    }
+}
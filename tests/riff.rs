@@ -0,0 +1,114 @@
+mod riff {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    #[test]
+    fn webp() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/x-webp-lossy")),
+            "image/x-webp-lossy"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/x-webp-lossless")),
+            "image/x-webp-lossless"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("image/x-webp-extended")),
+            "image/x-webp-extended"
+        );
+        // Unknown first chunks are still WebP
+        assert_eq!(tree_magic::from_u8(b"RIFF\x10\0\0\0WEBPXXXX\x04\0\0\0\0\0\0\0"), "image/webp");
+    }
+
+    #[test]
+    fn wav() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/wav")),
+            "audio/x-wav"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("audio/x-rf64")),
+            "audio/x-rf64"
+        );
+
+        // Broadcast Wave files over 4 GiB have the layout of RF64
+        let mut b = include_bytes!("audio/x-rf64").to_vec();
+        b[..4].copy_from_slice(b"BW64");
+        assert_eq!(tree_magic::from_u8(&b), "audio/x-bw64");
+    }
+
+    #[test]
+    fn avi() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-msvideo")),
+            "video/x-msvideo"
+        );
+        // The odml list comes after the stream indexes
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("video/x-msvideo-opendml")),
+            "video/x-msvideo-opendml"
+        );
+    }
+
+    #[test]
+    // The odml list is past the header
+    fn from_filepath() {
+        for name in &["video/x-msvideo", "video/x-msvideo-opendml", "audio/x-rf64", "image/x-webp-extended"] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+    #[test]
+    fn other_riff() {
+        assert_eq!(tree_magic::from_u8(b"RIFF\x14\0\0\0ABCDdata\x08\0\0\0\0\0\0\0\0\0\0\0"), "application/x-riff");
+        assert_eq!(tree_magic::from_u8(b"RIFX\0\0\0\x04ABCD"), "application/x-riff");
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/vnd.corel-draw")),
+            "application/vnd.corel-draw"
+        );
+    }
+
+    #[test]
+    // Big-endian RIFF files
+    fn rifx() {
+        let mut b = b"RIFX\0\0\0\x1cWAVEfmt \0\0\0\x10".to_vec();
+        b.extend_from_slice(&[0; 16]);
+        assert_eq!(tree_magic::from_u8(&b), "audio/x-wav");
+    }
+
+    #[test]
+    fn truncated() {
+        let b = include_bytes!("image/x-webp-lossless");
+        assert_eq!(tree_magic::from_u8(&b[..16]), "image/x-webp-lossless");
+        assert_eq!(tree_magic::from_u8(&b[..12]), "image/webp");
+        assert!(!tree_magic::match_u8(convmime!("application/x-riff"), &b[..10]));
+    }
+
+    #[test]
+    fn match_parents() {
+        let b = include_bytes!("image/x-webp-lossy");
+        assert!(tree_magic::match_u8(convmime!("image/x-webp-lossy"), b));
+        assert!(tree_magic::match_u8(convmime!("image/webp"), b));
+        assert!(!tree_magic::match_u8(convmime!("image/x-webp-lossless"), b));
+    }
+
+    #[test]
+    // The variants are known to the metadata, so their extensions fit
+    fn metadata() {
+        let lossy = convmime!("image/x-webp-lossy");
+        assert_eq!(tree_magic::metadata::preferred_extension(lossy), Some("webp".to_string()));
+        assert!(tree_magic::metadata::is_subclass(
+            convmime!("audio/x-rf64"),
+            convmime!("audio/x-wav")
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+mod sniff_web {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use tree_magic::sniff::web;
+
+    fn sniff(b: &[u8], content_type: Option<&str>) -> String {
+        web::from_u8(b, content_type, false).to_string()
+    }
+
+    #[test]
+    fn unknown_html() {
+        assert_eq!(sniff(b"<html><body>", None), "text/html");
+        assert_eq!(sniff(b" \t\r\n<!DOCTYPE html>", None), "text/html");
+        assert_eq!(sniff(b"<DiV class=x>", None), "text/html");
+        assert_eq!(sniff(b"<!-- comment -->", None), "text/html");
+        // The tag has to end
+        assert_eq!(sniff(b"<bogus>", None), "text/plain");
+        assert_eq!(sniff(b"<html", None), "text/plain");
+    }
+
+    #[test]
+    fn unknown_scriptable() {
+        assert_eq!(sniff(b"  <?xml version=\"1.0\"?>", None), "text/xml");
+        assert_eq!(sniff(b"%PDF-1.7\n", None), "application/pdf");
+        // Scriptable types aren't sniffed with nosniff
+        assert_eq!(web::from_u8(b"<html>", None, true), "text/plain");
+        assert_eq!(web::from_u8(b"%PDF-1.7\n\x00", None, true), "application/octet-stream");
+    }
+
+    #[test]
+    fn unknown_supplied() {
+        for supplied in &["unknown/unknown", "application/unknown", "*/*", "garbage"] {
+            assert_eq!(sniff(b"<p>hi", Some(supplied)), "text/html");
+        }
+    }
+
+    #[test]
+    fn unknown_tables() {
+        assert_eq!(sniff(include_bytes!("image/png"), None), "image/png");
+        assert_eq!(sniff(include_bytes!("image/gif"), None), "image/gif");
+        assert_eq!(sniff(include_bytes!("audio/ogg"), None), "application/ogg");
+        assert_eq!(sniff(include_bytes!("application/zip"), None), "application/zip");
+        assert_eq!(sniff(include_bytes!("application/gzip"), None), "application/x-gzip");
+        assert_eq!(sniff(b"%!PS-Adobe-3.0", None), "application/postscript");
+        assert_eq!(sniff(b"\xef\xbb\xbfabc", None), "text/plain");
+        assert_eq!(sniff(b"just text", None), "text/plain");
+        assert_eq!(sniff(b"bin\x00ary", None), "application/octet-stream");
+    }
+
+    #[test]
+    fn nosniff() {
+        let png = include_bytes!("image/png");
+        assert_eq!(web::from_u8(png, Some("text/plain"), true), "text/plain");
+        assert_eq!(web::from_u8(png, Some("image/gif"), true), "image/gif");
+        assert!(web::is_nosniff("nosniff"));
+        assert!(web::is_nosniff(" NoSniff , other"));
+        assert!(!web::is_nosniff("other, nosniff"));
+        assert!(!web::is_nosniff(""));
+    }
+
+    #[test]
+    // Apache serves everything as text/plain, so only text is trusted
+    fn apache_bug() {
+        let png = include_bytes!("image/png");
+        assert_eq!(sniff(png, Some("text/plain")), "application/octet-stream");
+        assert_eq!(sniff(png, Some("text/plain; charset=UTF-8")), "application/octet-stream");
+        assert_eq!(sniff(b"bin\x00ary", Some("text/plain; charset=ISO-8859-1")), "application/octet-stream");
+        assert_eq!(sniff(b"plain text", Some("text/plain")), "text/plain");
+        // HTML isn't sniffed from text
+        assert_eq!(sniff(b"<html>\x00", Some("text/plain")), "application/octet-stream");
+        // Other spellings are trusted
+        assert_eq!(sniff(png, Some("text/plain;charset=utf-8")), "text/plain;charset=utf-8");
+    }
+
+    #[test]
+    fn xml_supplied() {
+        assert_eq!(sniff(b"<html>", Some("application/xhtml+xml")), "application/xhtml+xml");
+        assert_eq!(sniff(include_bytes!("image/png"), Some("text/xml")), "text/xml");
+    }
+
+    #[test]
+    fn feeds() {
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?>\n<rss version=\"2.0\">", Some("text/html")), "application/rss+xml");
+        assert_eq!(
+            sniff(b"\xef\xbb\xbf<!-- x --><!DOCTYPE x>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">", Some("text/html")),
+            "application/atom+xml"
+        );
+        let rdf = b"<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\" xmlns=\"http://purl.org/rss/1.0/\">";
+        assert_eq!(sniff(rdf, Some("text/html")), "application/rss+xml");
+        assert_eq!(sniff(b"<rdf:RDF xmlns=\"http://purl.org/rss/1.0/\">", Some("text/html")), "text/html");
+        assert_eq!(sniff(b"<html><rss>", Some("text/html; charset=utf-8")), "text/html; charset=utf-8");
+        assert_eq!(sniff(b"<!-- unterminated", Some("text/html")), "text/html");
+    }
+
+    #[test]
+    // Only types of the same kind replace a supplied image, audio or video type
+    fn same_kind() {
+        let png = include_bytes!("image/png");
+        assert_eq!(sniff(png, Some("image/jpeg")), "image/png");
+        assert_eq!(sniff(png, Some("video/mp4")), "video/mp4");
+        assert_eq!(sniff(include_bytes!("audio/ogg"), Some("audio/mpeg")), "application/ogg");
+        assert_eq!(sniff(b"not an image", Some("image/webp")), "image/webp");
+        assert_eq!(sniff(png, Some("application/json")), "application/json");
+    }
+
+    #[test]
+    fn mp4_and_webm() {
+        let mp4 = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isommp41";
+        assert_eq!(sniff(mp4, None), "video/mp4");
+        let webm = b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01\x42\xf7\x81\x01\x42\xf2\x81\x04\x42\xf3\x81\x08\x42\x82\x84webm\x42\x87\x81\x04";
+        assert_eq!(sniff(webm, None), "video/webm");
+        let matroska = b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01\x42\x82\x88matroska\x42\x87\x81\x04\x42\x85\x81\x02";
+        assert_eq!(sniff(matroska, None), "application/octet-stream");
+    }
+
+    #[test]
+    fn mp3() {
+        let mut b = vec![0xff, 0xfb, 0x90, 0x44];
+        b.resize(417, 0);
+        b.extend_from_slice(&[0xff, 0xfb, 0x90, 0x44]);
+        assert_eq!(sniff(&b, None), "audio/mpeg");
+        assert_eq!(sniff(b"ID3\x04\x00", None), "audio/mpeg");
+    }
+
+    #[test]
+    fn fonts() {
+        assert_eq!(web::font_type(b"wOF2\x00\x01").unwrap(), "font/woff2");
+        assert_eq!(web::font_type(b"\x00\x01\x00\x00\x00\x10").unwrap(), "font/ttf");
+        let mut eot = vec![0x41; 34];
+        eot.extend_from_slice(b"LP");
+        assert_eq!(web::font_type(&eot).unwrap(), "application/vnd.ms-fontobject");
+        // Fonts are only sniffed where a font is expected
+        assert_eq!(sniff(b"wOFF\x00\x01\x00\x00", None), "application/octet-stream");
+    }
+
+    #[test]
+    fn from_filepath() {
+        let result = web::from_filepath(Path::new("tests/image/png"), Some("image/gif"), false);
+        assert_eq!(result.unwrap(), "image/png");
+        assert_eq!(web::from_filepath(Path::new("tests/missing"), None, false), None);
+    }
+}
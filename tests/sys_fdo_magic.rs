@@ -0,0 +1,183 @@
+#![cfg(feature = "sys_fdo_magic")]
+
+mod sys_fdo_magic {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use std::sync::Once;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    static INIT: Once = Once::new();
+
+    /// Point the XDG directories at the test packages before the type graph is built
+    fn init() {
+        INIT.call_once(|| {
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/packages");
+            std::env::set_var("XDG_DATA_HOME", &dir);
+            std::env::set_var("XDG_DATA_DIRS", &dir);
+        });
+    }
+
+    #[test]
+    fn magic_nested_big16() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"TMTEST\x01\x02....").to_string(),
+            "application/x-tree-magic-test"
+        );
+    }
+
+    #[test]
+    fn magic_nested_little16() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"TMTEST\x02\x01....").to_string(),
+            "application/x-tree-magic-test"
+        );
+    }
+
+    #[test]
+    fn magic_nested_mismatch() {
+        init();
+        assert!(!tree_magic::match_u8(convmime!("application/x-tree-magic-test"), b"TMTEST\x01\x01...."));
+    }
+
+    #[test]
+    fn alias() {
+        init();
+        assert!(tree_magic::match_u8(convmime!("application/x-tmtest"), b"TMTEST\x01\x02...."));
+    }
+
+    #[test]
+    fn subclass_of_alias() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"TMTEST\x01\x02v2\n").to_string(),
+            "application/x-tree-magic-test-v2"
+        );
+    }
+
+    #[test]
+    // The bundled package doesn't know these types, but the type graph does
+    fn metadata_subclass() {
+        init();
+        assert!(tree_magic::metadata::is_subclass(
+            convmime!("application/x-tree-magic-test-v2"),
+            convmime!("application/x-tmtest")
+        ));
+        assert!(!tree_magic::metadata::is_subclass(
+            convmime!("application/x-tree-magic-test"),
+            convmime!("application/x-tree-magic-test-v2")
+        ));
+    }
+
+    #[cfg(not(feature = "sys_mime_cache"))]
+    #[test]
+    // Globs come from the packages, like the magic
+    fn glob() {
+        init();
+        assert_eq!(
+            tree_magic::from_filename("file.tmw").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test-v2".to_string())
+        );
+        assert_eq!(
+            tree_magic::from_filename("dir/file.TMTEST").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test".to_string())
+        );
+        // The bundled globs are still there
+        assert_eq!(tree_magic::from_extension("png").map(|x| x.to_string()), Some("image/png".to_string()));
+    }
+
+    #[test]
+    fn check_extension() {
+        init();
+        let path = std::env::temp_dir()
+            .join(format!("tree_magic-sys-{}.tmtest", std::process::id()));
+        std::fs::write(&path, b"TMTEST\x01\x02....").unwrap();
+        let result = tree_magic::extension::check_extension(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.verdict, tree_magic::extension::Verdict::Consistent);
+    }
+
+    #[test]
+    fn region() {
+        init();
+        assert!(tree_magic::match_u8(
+            convmime!("application/x-tree-magic-region"),
+            b"TMREGION..........\x00NEEDLE.............................."
+        ));
+        assert!(!tree_magic::match_u8(
+            convmime!("application/x-tree-magic-region"),
+            b"TMREGION..............................\x00NEEDLE.........."
+        ));
+    }
+
+    #[test]
+    fn root_xml() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"<?xml version=\"1.0\"?>\n<!-- test -->\n<test xmlns=\"urn:tree-magic\"/>\n").to_string(),
+            "application/x-tree-magic-xml"
+        );
+        assert!(!tree_magic::match_u8(
+            convmime!("application/x-tree-magic-xml"),
+            b"<?xml version=\"1.0\"?>\n<test xmlns=\"urn:other\"/>\n"
+        ));
+    }
+
+    #[test]
+    fn merged_magic() {
+        init();
+        // Rules from a later package add to the earlier ones
+        assert!(tree_magic::match_u8(convmime!("application/x-tree-magic-test"), b"TMTEST\x01\x02...."));
+        assert!(tree_magic::match_u8(convmime!("application/x-tree-magic-test"), b"TMTEST\x03\x04...."));
+    }
+
+    // The test mime.cache only has the test types
+    #[cfg(not(feature = "sys_mime_cache"))]
+    #[test]
+    fn merged_with_builtin() {
+        init();
+        // A package that mentions a type keeps its bundled magic
+        assert!(tree_magic::match_u8(convmime!("image/png"), include_bytes!("image/png")));
+        assert_eq!(tree_magic::from_u8(b"TMPNG......").to_string(), "image/png");
+    }
+
+    #[test]
+    fn magic_deleteall() {
+        init();
+        assert!(!tree_magic::match_u8(convmime!("application/x-lzip"), b"LZIP\x01\x0c...."));
+        assert!(tree_magic::match_u8(convmime!("application/x-lzip"), b"TMLZIP......"));
+    }
+
+    #[test]
+    fn priority() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"TMPRIO......").to_string(),
+            "application/x-tree-magic-high"
+        );
+    }
+
+    #[test]
+    // A malformed rule is dropped with its parent, and the rest of the package is kept
+    fn partial_package() {
+        init();
+        assert!(tree_magic::match_u8(convmime!("application/x-tree-magic-partial"), b"TMPART......"));
+        assert!(!tree_magic::match_u8(convmime!("application/x-tree-magic-partial"), b"TMBAD\x03\x04...."));
+        assert_eq!(tree_magic::from_u8(b"TMNOTYPE....").to_string(), "text/plain");
+
+        // The test packages are both the system and the user directory, so each is read twice
+        let errors = tree_magic::package_errors();
+        assert_eq!(errors.len(), 4);
+        assert!(errors.iter().any(|x| x.ends_with(
+            "tree-magic-errors.xml: application/x-tree-magic-partial: Invalid number \"nowhere\""
+        )));
+        assert!(errors.iter().any(|x| x.ends_with("tree-magic-errors.xml: Invalid type \"not a type\"")));
+    }
+
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "sys_mime_cache")]
+
+mod sys_mime_cache {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use std::sync::Once;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    static INIT: Once = Once::new();
+
+    /// Point the XDG directories at the test cache before the type graph is built
+    ///
+    /// `tests/packages/mime/mime.cache` is written by running
+    /// `update-mime-database` on a copy of `tests/packages/mime`.
+    fn init() {
+        INIT.call_once(|| {
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/packages");
+            std::env::set_var("XDG_DATA_HOME", &dir);
+            std::env::set_var("XDG_DATA_DIRS", &dir);
+        });
+    }
+
+    #[test]
+    fn glob_weight() {
+        init();
+        // Both types have *.tmw, but version 2 has the higher weight
+        assert_eq!(
+            tree_magic::from_filename("file.tmw").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test-v2".to_string())
+        );
+    }
+
+    #[test]
+    fn glob_longest_suffix() {
+        init();
+        assert_eq!(
+            tree_magic::from_filename("dir/file.v2.tmtest").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test-v2".to_string())
+        );
+        assert_eq!(
+            tree_magic::from_filename("FILE.TMTEST").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test".to_string())
+        );
+    }
+
+    #[test]
+    fn glob_case_sensitive() {
+        init();
+        assert_eq!(
+            tree_magic::from_filename("TMTEST-1").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test".to_string())
+        );
+        assert_eq!(tree_magic::from_filename("tmtest-1"), None);
+    }
+
+    #[test]
+    fn alias() {
+        init();
+        assert!(tree_magic::is_alias(
+            convmime!("application/x-tmtest"),
+            convmime!("application/x-tree-magic-test")
+        ));
+        assert!(tree_magic::match_u8(convmime!("application/x-tmtest"), b"TMTEST\x01\x02...."));
+    }
+
+    #[test]
+    fn parent() {
+        init();
+        // Version 2 is a subclass of the alias, which the graph resolves
+        let node = tree_magic::TYPE.hash.get(&convmime!("application/x-tree-magic-test")).unwrap();
+        assert_eq!(
+            tree_magic::from_u8_node(*node, b"TMTEST\x01\x02v2\n").map(|x| x.to_string()),
+            Some("application/x-tree-magic-test-v2".to_string())
+        );
+        assert_eq!(tree_magic::from_u8_node(*node, b"TMTEST\x01\x02v1\n"), None);
+    }
+
+    #[test]
+    fn magic() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"TMTEST\x02\x01....").to_string(),
+            "application/x-tree-magic-test"
+        );
+        assert!(!tree_magic::match_u8(convmime!("application/x-tree-magic-test"), b"TMTEST\x01\x01...."));
+    }
+
+    #[test]
+    fn root_xml() {
+        init();
+        assert_eq!(
+            tree_magic::from_u8(b"<?xml version=\"1.0\"?>\n<test xmlns=\"urn:tree-magic\"/>\n").to_string(),
+            "application/x-tree-magic-xml"
+        );
+    }
+
+}
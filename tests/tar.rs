@@ -0,0 +1,184 @@
+mod tar {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+    use tree_magic::tar::{EntryKind, Member, BLOCK_LEN};
+
+    /// Builds a tar header, with `magic` at offset 257
+    fn header(name: &str, size: usize, typeflag: u8, magic: &[u8]) -> Vec<u8> {
+        let mut b = vec![0u8; BLOCK_LEN];
+        b[..name.len()].copy_from_slice(name.as_bytes());
+        b[100..107].copy_from_slice(b"0000644");
+        b[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        b[156] = typeflag;
+        b[257..257 + magic.len()].copy_from_slice(magic);
+
+        b[148..156].copy_from_slice(b"        ");
+        let sum: u32 = b.iter().map(|&x| u32::from(x)).sum();
+        b[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        b
+    }
+
+    /// Appends a member's data, padded to whole blocks
+    fn data(archive: &mut Vec<u8>, contents: &[u8]) {
+        archive.extend_from_slice(contents);
+        archive.resize(archive.len().div_ceil(BLOCK_LEN) * BLOCK_LEN, 0);
+    }
+
+    /// A V7 archive, which has no `ustar` magic
+    fn v7_archive() -> Vec<u8> {
+        let mut b = header("dir/", 0, b'5', b"");
+        b.extend(header("dir/image.gif", include_bytes!("image/gif").len(), b'0', b""));
+        data(&mut b, include_bytes!("image/gif"));
+        b.extend(header("dir/link", 0, b'2', b""));
+        b.extend(header("dir/notes", 6, b'\0', b""));
+        data(&mut b, b"notes\n");
+        b.resize(b.len() + 2 * BLOCK_LEN, 0);
+        b
+    }
+
+    #[test]
+    fn detect_ustar() {
+        assert_eq!(tree_magic::from_u8(include_bytes!("application/x-tar")), "application/x-tar");
+        assert!(tree_magic::match_u8("application/x-tar".parse().unwrap(), include_bytes!("application/x-tar")));
+    }
+
+    #[test]
+    fn detect_v7() {
+        assert_eq!(tree_magic::from_u8(&v7_archive()), "application/x-tar");
+    }
+
+    #[test]
+    // Without a whole header to check, the ustar magic will do
+    fn detect_short() {
+        let b = include_bytes!("application/x-tar");
+        assert!(tree_magic::match_u8("application/x-tar".parse().unwrap(), &b[..300]));
+        assert!(!tree_magic::match_u8("application/x-tar".parse().unwrap(), &v7_archive()[..300]));
+    }
+
+    #[test]
+    // The checksum has to match, not just the magic
+    fn bad_checksum() {
+        let mut b = include_bytes!("application/x-tar").to_vec();
+        b[0] = b'q';
+        assert!(!tree_magic::match_u8("application/x-tar".parse().unwrap(), &b));
+        assert_ne!(tree_magic::from_u8(&b), "application/x-tar");
+    }
+
+    #[test]
+    fn not_tar() {
+        assert!(!tree_magic::match_u8("application/x-tar".parse().unwrap(), include_bytes!("image/gif")));
+        assert!(!tree_magic::match_u8("application/x-tar".parse().unwrap(), &[0u8; 1024]));
+        assert_eq!(tree_magic::tar::from_u8(include_bytes!("application/zip"), 10), None);
+        assert_eq!(tree_magic::tar::from_filepath(Path::new("tests/missing"), 10), None);
+    }
+
+    #[test]
+    fn members() {
+        let members = tree_magic::tar::from_u8(&v7_archive(), 10).unwrap();
+        assert_eq!(members, vec![
+            Member{name: "dir/".to_string(), size: 0, kind: EntryKind::Directory, mimetype: None},
+            Member{
+                name: "dir/image.gif".to_string(),
+                size: include_bytes!("image/gif").len() as u64,
+                kind: EntryKind::File,
+                mimetype: Some("image/gif".parse().unwrap())
+            },
+            Member{name: "dir/link".to_string(), size: 0, kind: EntryKind::Symlink, mimetype: None},
+            Member{
+                name: "dir/notes".to_string(),
+                size: 6,
+                kind: EntryKind::File,
+                mimetype: Some("text/plain".parse().unwrap())
+            }
+        ]);
+    }
+
+    #[test]
+    fn members_limit() {
+        let members = tree_magic::tar::from_u8(&v7_archive(), 2).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1].name, "dir/image.gif");
+        assert_eq!(tree_magic::tar::from_u8(&v7_archive(), 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn members_path() {
+        let members = tree_magic::tar::from_filepath(Path::new("tests/application/x-tar"), 10).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].name, "plain");
+        assert_eq!(members[0].size, 29);
+    }
+
+    #[test]
+    // A member cut off by the end of the input is still listed
+    fn members_cut_off() {
+        let b = v7_archive();
+        let members = tree_magic::tar::from_u8(&b[..2 * BLOCK_LEN + 10], 10).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1].mimetype, None);
+    }
+
+    #[test]
+    fn long_names() {
+        let long = format!("{}/file.gif", "x".repeat(120));
+
+        // GNU stores the name as the data of an 'L' entry
+        let mut b = header("././@LongLink", long.len() + 1, b'L', b"ustar  \0");
+        data(&mut b, format!("{}\0", long).as_bytes());
+        b.extend(header(&long[..100], 6, b'0', b"ustar  \0"));
+        data(&mut b, b"hello\n");
+        // POSIX stores it in an extended header
+        let record = format!(" path={}\n", long);
+        let record = format!("{}{}", record.len() + 3, record);
+        b.extend(header("PaxHeaders/file", record.len(), b'x', b"ustar\x0000"));
+        data(&mut b, record.as_bytes());
+        b.extend(header("file.gif", 6, b'0', b"ustar\x0000"));
+        data(&mut b, b"hello\n");
+
+        let names: Vec<String> = tree_magic::tar::from_u8(&b, 10).unwrap()
+            .into_iter().map(|x| x.name).collect();
+        assert_eq!(names, vec![long.clone(), long]);
+    }
+
+    #[test]
+    // Only the start of a huge extended header is read, and the member after it is still found
+    fn long_name_huge() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let size = 1 << 30;
+        let record = " path=big.gif\n";
+        let record = format!("{}{}", record.len() + 2, record);
+        let mut b = header("PaxHeaders/big.gif", size, b'x', b"ustar\x0000");
+        b.extend_from_slice(record.as_bytes());
+
+        let path = std::env::temp_dir()
+            .join(format!("tree_magic-tar-{}-huge", std::process::id()));
+        let mut f = std::fs::File::create(&path).unwrap();
+        f.write_all(&b).unwrap();
+        // The rest of the extended header is a hole in a sparse file
+        f.seek(SeekFrom::Start((BLOCK_LEN + size) as u64)).unwrap();
+        f.write_all(&header("file", 6, b'0', b"ustar\x0000")).unwrap();
+        f.write_all(b"hello\n").unwrap();
+        drop(f);
+
+        let members = tree_magic::tar::from_filepath(&path, 10).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].name, "big.gif");
+    }
+
+    #[test]
+    // POSIX archives split long names into a prefix and a name
+    fn ustar_prefix() {
+        let mut b = header("name", 0, b'0', b"ustar\x0000");
+        b[345..351].copy_from_slice(b"prefix");
+        b[148..156].copy_from_slice(b"        ");
+        let sum: u32 = b.iter().map(|&x| u32::from(x)).sum();
+        b[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+
+        let members = tree_magic::tar::from_u8(&b, 10).unwrap();
+        assert_eq!(members[0].name, "prefix/name");
+    }
+}
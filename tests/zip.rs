@@ -0,0 +1,82 @@
+mod zip {
+
+    extern crate tree_magic;
+
+    use std::path::Path;
+
+    macro_rules! convmime {
+        ($x:expr) => {$x.parse().unwrap()}
+    }
+
+    #[test]
+    fn office_open_xml() {
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/vnd.openxmlformats-officedocument.wordprocessingml.document")),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        assert_eq!(
+            tree_magic::from_u8(include_bytes!("application/vnd.openxmlformats-officedocument.presentationml.presentation")),
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+        );
+    }
+
+    #[test]
+    fn java_archive() {
+        let b = include_bytes!("application/x-java-archive");
+        assert_eq!(tree_magic::from_u8(b), "application/x-java-archive");
+        assert!(tree_magic::match_u8(convmime!("application/java-archive"), b));
+    }
+
+    #[test]
+    fn android_package() {
+        let b = include_bytes!("application/vnd.android.package-archive");
+        assert_eq!(tree_magic::from_u8(b), "application/vnd.android.package-archive");
+        // Every Android package is a Java archive, manifest or not
+        assert!(tree_magic::match_u8(convmime!("application/x-java-archive"), b));
+        assert!(!tree_magic::match_u8(
+            convmime!("application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+            b
+        ));
+    }
+
+    #[test]
+    fn plain_zip() {
+        assert_eq!(tree_magic::from_u8(include_bytes!("application/zip")), "application/zip");
+        // Office parts without the content types aren't a document
+        let mut b = include_bytes!("application/vnd.openxmlformats-officedocument.wordprocessingml.document").to_vec();
+        let name = b"[Content_Types].xml";
+        while let Some(i) = b.windows(name.len()).position(|x| x == name) {
+            b[i..i + name.len()].copy_from_slice(b"[Content_Typez].xml");
+        }
+        assert_eq!(tree_magic::from_u8(&b), "application/zip");
+    }
+
+    #[test]
+    fn cut_off() {
+        // Without the central directory, only the container is known
+        let b = include_bytes!("application/vnd.openxmlformats-officedocument.wordprocessingml.document");
+        assert_eq!(tree_magic::from_u8(&b[..60]), "application/zip");
+    }
+
+    #[test]
+    // The central directory of the Word document ends past the header
+    fn from_filepath() {
+        for name in &[
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            "application/x-java-archive",
+            "application/vnd.android.package-archive"
+        ] {
+            assert_eq!(
+                tree_magic::from_filepath(&Path::new("tests").join(name)),
+                Some(convmime!(name))
+            );
+        }
+    }
+
+}